@@ -0,0 +1,51 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Build script
+//!
+//! Capture the compiler version, the target, the optimization level and the enabled features of the build, so
+//! ``report_build_info`` could tell which binary is running.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "unknown".into());
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|feature| feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+
+    println!("cargo:rustc-env=CONSOLE_BUILD_RUSTC={}", version);
+    println!(
+        "cargo:rustc-env=CONSOLE_BUILD_TARGET={}",
+        env::var("TARGET").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=CONSOLE_BUILD_OPT_LEVEL={}",
+        env::var("OPT_LEVEL").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=CONSOLE_BUILD_PROFILE={}",
+        env::var("PROFILE").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=CONSOLE_BUILD_FEATURES={}",
+        features.join(",")
+    );
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,322 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # ANSI escape sequences
+//!
+//! Typed escape sequences for colors, text attributes, cursor movement, scroll regions and erasing instead of raw
+//! ``\x1b[...`` literals. All of them implement ``Display``, so they are formatted into any buffer or printed with the
+//! regular macros, ``print`` passes them straight to the console:
+//! ```ignore
+//! use ruspiro_console::ansi::{Color, Cursor, Erase, Style};
+//!
+//! const ALERT: Style = Style::new().fg(Color::Red).bold();
+//! println!("{} sd card missing", ALERT.paint("ERROR"));
+//! Cursor::To(0, 0).print();
+//! Erase::Line.print();
+//! ```
+//! Rows and columns of the cursor positions are counted from zero starting at the top left corner.
+
+use core::fmt;
+
+/// The sequence resetting all colors and text attributes
+pub const RESET: &str = "\u{1b}[0m";
+
+/// The colors of the text and the background
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    /// one of the 256 colors of the extended palette
+    Indexed(u8),
+    /// a true color given as red, green and blue component
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// write the SGR parameters of the color, ``base`` is 30 for the text and 40 for the background
+    fn write_params(self, f: &mut fmt::Formatter, base: u8) -> fmt::Result {
+        let idx = match self {
+            Color::Indexed(idx) => return write!(f, "{};5;{}", base + 8, idx),
+            Color::Rgb(r, g, b) => return write!(f, "{};2;{};{};{}", base + 8, r, g, b),
+            Color::Black => 0,
+            Color::Red => 1,
+            Color::Green => 2,
+            Color::Yellow => 3,
+            Color::Blue => 4,
+            Color::Magenta => 5,
+            Color::Cyan => 6,
+            Color::White => 7,
+            Color::BrightBlack => 8,
+            Color::BrightRed => 9,
+            Color::BrightGreen => 10,
+            Color::BrightYellow => 11,
+            Color::BrightBlue => 12,
+            Color::BrightMagenta => 13,
+            Color::BrightCyan => 14,
+            Color::BrightWhite => 15,
+        };
+        if idx < 8 {
+            write!(f, "{}", base + idx)
+        } else {
+            // the bright colors use the parameters 60 above the basic ones
+            write!(f, "{}", base + 60 + idx - 8)
+        }
+    }
+}
+
+/// The text attributes of the SGR sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    Bold = 1,
+    Dim = 2,
+    Italic = 3,
+    Underline = 4,
+    Blink = 5,
+    Reverse = 7,
+    Hidden = 8,
+    Strikethrough = 9,
+}
+
+impl Attribute {
+    const ALL: [Attribute; 8] = [
+        Attribute::Bold,
+        Attribute::Dim,
+        Attribute::Italic,
+        Attribute::Underline,
+        Attribute::Blink,
+        Attribute::Reverse,
+        Attribute::Hidden,
+        Attribute::Strikethrough,
+    ];
+}
+
+/// A combination of colors and text attributes formatted as a single SGR sequence. An empty style resets all
+/// colors and attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    /// bit n is set if the attribute with the SGR parameter n is set
+    attributes: u16,
+}
+
+impl Style {
+    /// Create an empty style
+    pub const fn new() -> Self {
+        Style {
+            fg: None,
+            bg: None,
+            attributes: 0,
+        }
+    }
+
+    /// Set the color of the text
+    pub const fn fg(mut self, color: Color) -> Self {
+        self.fg = Some(color);
+        self
+    }
+
+    /// Set the color of the background
+    pub const fn bg(mut self, color: Color) -> Self {
+        self.bg = Some(color);
+        self
+    }
+
+    /// Add a text attribute
+    pub const fn attribute(mut self, attribute: Attribute) -> Self {
+        self.attributes |= 1 << attribute as u16;
+        self
+    }
+
+    /// Add the bold attribute
+    pub const fn bold(self) -> Self {
+        self.attribute(Attribute::Bold)
+    }
+
+    /// Add the underline attribute
+    pub const fn underline(self) -> Self {
+        self.attribute(Attribute::Underline)
+    }
+
+    /// Add the reverse attribute swapping the text and the background color
+    pub const fn reverse(self) -> Self {
+        self.attribute(Attribute::Reverse)
+    }
+
+    /// Wrap the value, so it is formatted with this style followed by a reset
+    pub fn paint<T: fmt::Display>(self, value: T) -> Styled<T> {
+        Styled { style: self, value }
+    }
+
+    /// Pass the sequence to the console
+    pub fn print(&self) {
+        crate::print_args(format_args!("{}", self));
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("\u{1b}[")?;
+        let mut separator = "";
+        for attribute in Attribute::ALL {
+            if self.attributes & (1 << attribute as u16) != 0 {
+                write!(f, "{}{}", separator, attribute as u8)?;
+                separator = ";";
+            }
+        }
+        if let Some(fg) = self.fg {
+            f.write_str(separator)?;
+            fg.write_params(f, 30)?;
+            separator = ";";
+        }
+        if let Some(bg) = self.bg {
+            f.write_str(separator)?;
+            bg.write_params(f, 40)?;
+        }
+        f.write_str("m")
+    }
+}
+
+/// A value formatted with a style followed by a reset, see ``Style::paint``
+pub struct Styled<T> {
+    style: Style,
+    value: T,
+}
+
+impl<T: fmt::Display> fmt::Display for Styled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.style, self.value, RESET)
+    }
+}
+
+/// The sequences moving the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cursor {
+    /// move the cursor the given number of rows up
+    Up(usize),
+    /// move the cursor the given number of rows down
+    Down(usize),
+    /// move the cursor the given number of columns right
+    Right(usize),
+    /// move the cursor the given number of columns left
+    Left(usize),
+    /// move the cursor to the given row and column
+    To(usize, usize),
+    /// move the cursor to the given column of the current row
+    Column(usize),
+    /// save the cursor position
+    Save,
+    /// restore the cursor position saved last
+    Restore,
+    /// hide the cursor
+    Hide,
+    /// show the cursor
+    Show,
+    /// request the terminal to report the cursor position as ``ESC[row;colR``
+    ReportPosition,
+}
+
+impl Cursor {
+    /// Pass the sequence to the console
+    pub fn print(&self) {
+        crate::print_args(format_args!("{}", self));
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Cursor::Up(rows) => write!(f, "\u{1b}[{}A", rows),
+            Cursor::Down(rows) => write!(f, "\u{1b}[{}B", rows),
+            Cursor::Right(cols) => write!(f, "\u{1b}[{}C", cols),
+            Cursor::Left(cols) => write!(f, "\u{1b}[{}D", cols),
+            Cursor::To(row, col) => write!(f, "\u{1b}[{};{}H", row + 1, col + 1),
+            Cursor::Column(col) => write!(f, "\u{1b}[{}G", col + 1),
+            Cursor::Save => f.write_str("\u{1b}7"),
+            Cursor::Restore => f.write_str("\u{1b}8"),
+            Cursor::Hide => f.write_str("\u{1b}[?25l"),
+            Cursor::Show => f.write_str("\u{1b}[?25h"),
+            Cursor::ReportPosition => f.write_str("\u{1b}[6n"),
+        }
+    }
+}
+
+/// The sequences restricting the scrolling of the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scroll {
+    /// scroll only the rows ``top`` to ``bottom``, this moves the cursor to the top left corner
+    Region(usize, usize),
+    /// scroll all rows again
+    Reset,
+}
+
+impl Scroll {
+    /// Pass the sequence to the console
+    pub fn print(&self) {
+        crate::print_args(format_args!("{}", self));
+    }
+}
+
+impl fmt::Display for Scroll {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Scroll::Region(top, bottom) => write!(f, "\u{1b}[{};{}r", top + 1, bottom + 1),
+            Scroll::Reset => f.write_str("\u{1b}[r"),
+        }
+    }
+}
+
+/// The sequences erasing parts of the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Erase {
+    /// erase the whole screen
+    Screen,
+    /// erase from the cursor to the end of the screen
+    Below,
+    /// erase from the start of the screen to the cursor
+    Above,
+    /// erase the whole line of the cursor
+    Line,
+    /// erase from the cursor to the end of the line
+    LineRight,
+    /// erase from the start of the line to the cursor
+    LineLeft,
+}
+
+impl Erase {
+    /// Pass the sequence to the console
+    pub fn print(&self) {
+        crate::print_args(format_args!("{}", self));
+    }
+}
+
+impl fmt::Display for Erase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Erase::Screen => "\u{1b}[2J",
+            Erase::Below => "\u{1b}[0J",
+            Erase::Above => "\u{1b}[1J",
+            Erase::Line => "\u{1b}[2K",
+            Erase::LineRight => "\u{1b}[0K",
+            Erase::LineLeft => "\u{1b}[1K",
+        })
+    }
+}
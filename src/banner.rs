@@ -0,0 +1,114 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Boot banner
+//!
+//! Every kernel greets with a startup banner telling which binary is running. The ``banner!`` macro prints the name
+//! and the version of the calling crate, the git hash and the build time if the build script of the kernel provided
+//! them with the ``GIT_HASH`` and ``BUILD_TIMESTAMP`` environment variables, and optionally the board:
+//! ```ignore
+//! banner!("Raspberry Pi 3 Model B+");
+//! ```
+//! prints
+//! ```text
+//! +--------------------------------+
+//! | ruspiro-kernel 0.1.0           |
+//! | git    6b3e9f0                 |
+//! | built  2019-10-14 12:00:00 UTC |
+//! | board  Raspberry Pi 3 Model B+ |
+//! +--------------------------------+
+//! ```
+//! A build script sets the variables with ``cargo:rustc-env=GIT_HASH=...``. The ``Banner`` is available for
+//! banners composed at runtime.
+
+use crate::display_width;
+use core::fmt;
+
+/// The content of a boot banner
+pub struct Banner<'a> {
+    name: &'a str,
+    version: &'a str,
+    git_hash: Option<&'a str>,
+    build_time: Option<&'a str>,
+    board: Option<&'a str>,
+}
+
+impl<'a> Banner<'a> {
+    /// Create a banner with the name and the version of the kernel
+    pub const fn new(name: &'a str, version: &'a str) -> Self {
+        Banner {
+            name,
+            version,
+            git_hash: None,
+            build_time: None,
+            board: None,
+        }
+    }
+
+    /// Add the git hash the kernel has been built from
+    pub const fn git_hash(mut self, git_hash: Option<&'a str>) -> Self {
+        self.git_hash = git_hash;
+        self
+    }
+
+    /// Add the time the kernel has been built
+    pub const fn build_time(mut self, build_time: Option<&'a str>) -> Self {
+        self.build_time = build_time;
+        self
+    }
+
+    /// Add the board the kernel is running on
+    pub const fn board(mut self, board: Option<&'a str>) -> Self {
+        self.board = board;
+        self
+    }
+
+    /// Print the banner with the console
+    pub fn print(&self) {
+        crate::print_args(format_args!("{}", self));
+    }
+}
+
+impl fmt::Display for Banner<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let details = [
+            ("git", self.git_hash),
+            ("built", self.build_time),
+            ("board", self.board),
+        ];
+        let title = display_width(self.name) + 1 + display_width(self.version);
+        // the details are written behind a label column of this width
+        let label = 7;
+        let width = details
+            .iter()
+            .filter_map(|(_, value)| Some(label + display_width((*value)?)))
+            .fold(title, usize::max);
+        write!(f, "+{:-<1$}+\r\n", "", width + 2)?;
+        write!(
+            f,
+            "| {} {}{:pad$} |\r\n",
+            self.name,
+            self.version,
+            "",
+            pad = width - title
+        )?;
+        for (name, value) in details.iter() {
+            if let Some(value) = value {
+                write!(
+                    f,
+                    "| {:label$}{}{:pad$} |\r\n",
+                    name,
+                    value,
+                    "",
+                    label = label,
+                    pad = width - label - display_width(value)
+                )?;
+            }
+        }
+        write!(f, "+{:-<1$}+\r\n", "", width + 2)
+    }
+}
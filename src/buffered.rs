@@ -0,0 +1,92 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Formatting buffers
+//!
+//! Formatting a message runs arbitrary ``Display`` and ``Debug`` implementations. Doing this while the console is
+//! held lets every other core wait for the formatting code and deadlocks if this code waits for another core in
+//! turn. ``print!`` and ``println!`` therefore format their message into one of ``FORMAT_BUFFERS`` static buffers of
+//! ``FORMAT_BUFFER_SIZE`` bytes first and pass the formatted text to the console afterwards.
+//!
+//! A message that does not fit into the buffer is passed in several chunks. The console is held from the first
+//! chunk on until the message is complete, so it still reaches the output channels contiguously. If all buffers are
+//! in use, e.g. by a ``Display`` implementation that prints itself, the message is streamed while holding the
+//! console.
+
+use crate::{Locked, CONSOLE};
+use core::fmt;
+
+/// The size of each buffer ``print!`` formats the message into
+pub const FORMAT_BUFFER_SIZE: usize = 256;
+
+/// The number of messages that could be formatted at the same time without holding the console
+pub const FORMAT_BUFFERS: usize = 4;
+
+/// The static buffers to format a message into
+static BUFFERS: [Locked<[u8; FORMAT_BUFFER_SIZE]>; FORMAT_BUFFERS] =
+    [const { Locked::new([0; FORMAT_BUFFER_SIZE]) }; FORMAT_BUFFERS];
+
+/// ``fmt::Write`` adapter collecting the formatted chunks in a buffer and passing them on to the console once the
+/// buffer is full
+struct BufferedWriter<'a> {
+    data: &'a mut [u8; FORMAT_BUFFER_SIZE],
+    fill: usize,
+    /// the console has been locked for the remaining chunks
+    locked: bool,
+}
+
+impl BufferedWriter<'_> {
+    /// pass the content of the buffer to the console
+    fn emit(&mut self) {
+        // the writer only copies complete strings, so the buffer always contains valid UTF-8
+        if let Ok(s) = core::str::from_utf8(&self.data[..self.fill]) {
+            if !s.is_empty() {
+                crate::print(s);
+            }
+        }
+        self.fill = 0;
+    }
+}
+
+impl fmt::Write for BufferedWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.fill + s.len() > FORMAT_BUFFER_SIZE {
+            if !self.locked {
+                self.locked = CONSOLE.use_for(|console| console.lock_output());
+            }
+            self.emit();
+            if s.len() > FORMAT_BUFFER_SIZE {
+                crate::print(s);
+                return Ok(());
+            }
+        }
+        self.data[self.fill..self.fill + s.len()].copy_from_slice(s.as_bytes());
+        self.fill += s.len();
+        Ok(())
+    }
+}
+
+/// Format the message into a free buffer and pass it to the console. Returns ``false`` if all buffers are in use
+/// and nothing has been printed.
+pub(crate) fn print_buffered(args: fmt::Arguments) -> bool {
+    BUFFERS.iter().any(|buffer| {
+        buffer
+            .try_with(|data| {
+                let mut writer = BufferedWriter {
+                    data,
+                    fill: 0,
+                    locked: false,
+                };
+                let _ = fmt::write(&mut writer, args);
+                writer.emit();
+                if writer.locked {
+                    CONSOLE.use_for(|console| console.unlock_output());
+                }
+            })
+            .is_some()
+    })
+}
@@ -0,0 +1,82 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Build information
+//!
+//! To answer which binary is actually running on the board from the serial log ``report_build_info`` prints the
+//! compiler version, the target, the optimization level and the enabled features of the console captured by the
+//! build script:
+//! ```ignore
+//! banner!("Raspberry Pi 3 Model B+");
+//! report_build_info();
+//! ```
+//! prints
+//! ```text
+//! rustc     rustc 1.40.0 (73528e339 2019-12-16)
+//! target    aarch64-unknown-linux-gnu
+//! opt-level 3 (release)
+//! features  location, panic-handler
+//! ```
+
+use core::fmt;
+
+/// The build information captured by the build script
+pub struct BuildInfo {
+    /// the version of the compiler
+    pub rustc: &'static str,
+    /// the target triple
+    pub target: &'static str,
+    /// the optimization level
+    pub opt_level: &'static str,
+    /// the profile, ``debug`` or ``release``
+    pub profile: &'static str,
+    /// the enabled features of the console separated by commas
+    pub features: &'static str,
+}
+
+/// The build information of this binary
+pub const BUILD_INFO: BuildInfo = BuildInfo {
+    rustc: env!("CONSOLE_BUILD_RUSTC"),
+    target: env!("CONSOLE_BUILD_TARGET"),
+    opt_level: env!("CONSOLE_BUILD_OPT_LEVEL"),
+    profile: env!("CONSOLE_BUILD_PROFILE"),
+    features: env!("CONSOLE_BUILD_FEATURES"),
+};
+
+impl BuildInfo {
+    /// The enabled features of the console
+    pub fn features(&self) -> impl Iterator<Item = &'static str> {
+        self.features
+            .split(',')
+            .filter(|feature| !feature.is_empty())
+    }
+}
+
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rustc     {}\r\n", self.rustc)?;
+        write!(f, "target    {}\r\n", self.target)?;
+        write!(f, "opt-level {} ({})\r\n", self.opt_level, self.profile)?;
+        write!(f, "features  ")?;
+        let mut features = self.features();
+        match features.next() {
+            Some(first) => {
+                write!(f, "{}", first)?;
+                for feature in features {
+                    write!(f, ", {}", feature)?;
+                }
+                f.write_str("\r\n")
+            }
+            None => f.write_str("none\r\n"),
+        }
+    }
+}
+
+/// Print the build information of this binary with the console
+pub fn report_build_info() {
+    crate::print_args(format_args!("{}", BUILD_INFO));
+}
@@ -0,0 +1,78 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Output capture
+//!
+//! The ``CaptureConsole`` keeps everything written to it in memory, so tests are able to check the log output of
+//! their kernel modules. The console takes ownership of its output channels, so all clones of a ``CaptureConsole``
+//! share the same buffer and a clone is kept to read the output back:
+//! ```ignore
+//! let capture = CaptureConsole::new();
+//! with_console(capture.clone(), || sdcard.initialize());
+//! assert!(capture.contains("sd card ready"));
+//! capture.clear();
+//! ```
+
+use crate::{ConsoleImpl, Locked};
+use alloc::{string::String, sync::Arc};
+
+/// The output channel keeping all output in memory
+#[derive(Clone)]
+pub struct CaptureConsole {
+    /// the text shared by all clones
+    buffer: Arc<Locked<String>>,
+}
+
+impl CaptureConsole {
+    /// Create the console with an empty buffer
+    pub fn new() -> Self {
+        CaptureConsole {
+            buffer: Arc::new(Locked::new(String::new())),
+        }
+    }
+
+    /// A copy of the output captured so far
+    pub fn contents(&self) -> String {
+        self.buffer.with(|text| text.clone())
+    }
+
+    /// Hand back the output captured so far and clear the buffer
+    pub fn take(&self) -> String {
+        self.buffer.with(core::mem::take)
+    }
+
+    /// Clear the output captured so far
+    pub fn clear(&self) {
+        self.buffer.with(|text| text.clear());
+    }
+
+    /// Check if the output captured so far contains the given text
+    pub fn contains(&self, pattern: &str) -> bool {
+        self.buffer.with(|text| text.contains(pattern))
+    }
+
+    /// Check if nothing has been captured so far
+    pub fn is_empty(&self) -> bool {
+        self.buffer.with(|text| text.is_empty())
+    }
+}
+
+impl Default for CaptureConsole {
+    fn default() -> Self {
+        CaptureConsole::new()
+    }
+}
+
+impl ConsoleImpl for CaptureConsole {
+    fn putc(&self, c: char) {
+        self.buffer.with(|text| text.push(c));
+    }
+
+    fn puts(&self, s: &str) {
+        self.buffer.with(|text| text.push_str(s));
+    }
+}
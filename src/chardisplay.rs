@@ -0,0 +1,384 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Character displays
+//!
+//! Small OLED or LCD screens attached with SPI or I2C show a grid of characters. Instead of the text semantics of
+//! ``ConsoleImpl`` their drivers only implement the ``CharDisplay`` trait to draw a character at a position, scroll
+//! and clear the screen. The ``CharDisplayConsole`` turns such a display into an output channel:
+//! ```ignore
+//! impl CharDisplay for Ssd1306 {
+//!     fn size(&self) -> (usize, usize) {
+//!         (16, 8)
+//!     }
+//!
+//!     fn write_char(&mut self, col: usize, row: usize, c: char) {
+//!         self.draw_glyph(col * 8, row * 8, c);
+//!     }
+//!
+//!     fn scroll(&mut self) {
+//!         self.shift_pages_up();
+//!     }
+//!
+//!     fn clear(&mut self) {
+//!         self.fill(0);
+//!     }
+//! }
+//!
+//! CONSOLE.take_for(|cons| cons.add_sink(CharDisplayConsole::new(oled)));
+//! ```
+//! The console keeps track of the cursor, wraps lines at the right border and scrolls the display once the cursor
+//! moves below the last line. Carriage return, line feed, tab and backspace move the cursor. Like on a terminal the
+//! ANSI escape sequences moving, saving and restoring the cursor, erasing the display or a line and setting the
+//! scroll region are emulated, so the helpers of the ``screen`` module and the status line work on the display as
+//! well. All other escape sequences like the colors of the severity prefixes are skipped.
+
+use crate::{ConsoleImpl, Locked};
+
+/// Every display showing a grid of characters need to implement this trait
+pub trait CharDisplay {
+    /// the number of columns and rows of the display
+    fn size(&self) -> (usize, usize);
+    /// draw the character at the given column and row
+    fn write_char(&mut self, col: usize, row: usize, c: char);
+    /// move the content of all rows one row up and clear the last row
+    fn scroll(&mut self);
+    /// move the content of the rows ``top`` to ``bottom`` one row up and clear the row ``bottom``. The default
+    /// implementation scrolls the whole display, displays able to scroll only a part of the rows override it.
+    fn scroll_region(&mut self, top: usize, bottom: usize) {
+        let _ = (top, bottom);
+        self.scroll();
+    }
+    /// clear the whole display
+    fn clear(&mut self);
+    /// ensure all changes are visible on the display. The default implementation does nothing as unbuffered
+    /// displays have nothing to flush
+    fn flush(&mut self) {}
+}
+
+/// The state of the text output
+struct Cursor {
+    col: usize,
+    row: usize,
+    /// the console is parsing an escape sequence: 1 after the escape character, 2 inside a control sequence
+    escape: u8,
+    /// the first two parameters of the control sequence, 0 if not given
+    params: [usize; 2],
+    /// the index of the parameter currently parsed
+    param: usize,
+    /// the first and the last row the display scrolls, ``None`` if all rows scroll
+    region: Option<(usize, usize)>,
+    /// the position saved with ``ESC 7``
+    saved: (usize, usize),
+}
+
+/// The output channel writing the output to a character display
+pub struct CharDisplayConsole<D> {
+    /// the display and the cursor
+    state: Locked<(D, Cursor)>,
+}
+
+impl<D: CharDisplay> CharDisplayConsole<D> {
+    /// Create the console writing to the given display with the cursor at the top left corner. The display is not
+    /// cleared.
+    pub const fn new(display: D) -> Self {
+        CharDisplayConsole {
+            state: Locked::new((
+                display,
+                Cursor {
+                    col: 0,
+                    row: 0,
+                    escape: 0,
+                    params: [0; 2],
+                    param: 0,
+                    region: None,
+                    saved: (0, 0),
+                },
+            )),
+        }
+    }
+
+    /// Hand back the display
+    pub fn into_inner(self) -> D {
+        self.state.into_inner().0
+    }
+
+    /// Access the display, e.g. to change its settings
+    pub fn display_mut(&mut self) -> &mut D {
+        &mut self.state.get_mut().0
+    }
+
+    /// The column and the row the next character is written at
+    pub fn cursor(&self) -> (usize, usize) {
+        self.with_display(|_, cursor| (cursor.col, cursor.row))
+    }
+
+    /// Move the cursor to the given column and row. Positions outside the display are moved to the border.
+    pub fn set_cursor(&self, col: usize, row: usize) {
+        self.with_display(|display, cursor| {
+            let (cols, rows) = display.size();
+            cursor.col = col.min(cols.saturating_sub(1));
+            cursor.row = row.min(rows.saturating_sub(1));
+        });
+    }
+
+    /// Clear the display and move the cursor to the top left corner
+    pub fn clear(&self) {
+        self.with_display(|display, cursor| {
+            display.clear();
+            cursor.col = 0;
+            cursor.row = 0;
+        });
+    }
+
+    /// run the given function with exclusive access to the display and the cursor
+    fn with_display<R>(&self, f: impl FnOnce(&mut D, &mut Cursor) -> R) -> R {
+        self.state.with(|(display, cursor)| f(display, cursor))
+    }
+}
+
+/// write a single character at the cursor
+fn put_char(display: &mut impl CharDisplay, cursor: &mut Cursor, c: char) {
+    let (cols, rows) = display.size();
+    if cols == 0 || rows == 0 {
+        return;
+    }
+    match (cursor.escape, c) {
+        (0, '\u{1b}') => cursor.escape = 1,
+        (1, '[') => {
+            cursor.escape = 2;
+            cursor.params = [0; 2];
+            cursor.param = 0;
+        }
+        (1, '7') => {
+            cursor.saved = (cursor.col, cursor.row);
+            cursor.escape = 0;
+        }
+        (1, '8') => {
+            (cursor.col, cursor.row) = cursor.saved;
+            cursor.escape = 0;
+        }
+        (1, _) => cursor.escape = 0,
+        (2, '0'..='9') => {
+            if let Some(param) = cursor.params.get_mut(cursor.param) {
+                *param = param
+                    .saturating_mul(10)
+                    .saturating_add(c as usize - '0' as usize);
+            }
+        }
+        (2, ';') => cursor.param += 1,
+        // a control sequence ends with a character in the range of '@' to '~'
+        (2, '@'..='~') => {
+            cursor.escape = 0;
+            control(display, cursor, c, cols, rows);
+        }
+        (2, _) => (),
+        (_, '\r') => cursor.col = 0,
+        (_, '\n') => new_line(display, cursor, rows),
+        (_, '\t') => {
+            // a tab does not move the cursor beyond the end of the line
+            for _ in 0..(8 - cursor.col % 8).min(cols.saturating_sub(cursor.col)) {
+                put_char(display, cursor, ' ');
+            }
+        }
+        (_, '\u{8}') => cursor.col = cursor.col.saturating_sub(1),
+        (_, c) if c.is_control() => (),
+        (_, c) => {
+            if cursor.col >= cols {
+                new_line(display, cursor, rows);
+            }
+            display.write_char(cursor.col, cursor.row, c);
+            cursor.col += 1;
+        }
+    }
+}
+
+/// perform the control sequence with the given final character
+fn control(display: &mut impl CharDisplay, cursor: &mut Cursor, c: char, cols: usize, rows: usize) {
+    // the rows and columns of the control sequences are counted from 1
+    let [first, second] = cursor.params;
+    match c {
+        // move the cursor
+        'H' | 'f' => {
+            cursor.row = first.max(1).min(rows) - 1;
+            cursor.col = second.max(1).min(cols) - 1;
+        }
+        // set the scroll region and move the cursor to the top left corner
+        'r' => {
+            let top = first.max(1).min(rows) - 1;
+            let bottom = if second == 0 { rows } else { second.min(rows) } - 1;
+            if top < bottom {
+                cursor.region = if top == 0 && bottom == rows - 1 {
+                    None
+                } else {
+                    Some((top, bottom))
+                };
+                cursor.col = 0;
+                cursor.row = 0;
+            }
+        }
+        // erase a part of the line
+        'K' => {
+            let range = match first {
+                0 => cursor.col..cols,
+                1 => 0..(cursor.col + 1).min(cols),
+                _ => 0..cols,
+            };
+            for col in range {
+                display.write_char(col, cursor.row, ' ');
+            }
+        }
+        // erase a part of the display
+        'J' => match first {
+            2 => display.clear(),
+            0 => {
+                for col in cursor.col..cols {
+                    display.write_char(col, cursor.row, ' ');
+                }
+                for row in cursor.row + 1..rows {
+                    for col in 0..cols {
+                        display.write_char(col, row, ' ');
+                    }
+                }
+            }
+            _ => (),
+        },
+        _ => (),
+    }
+}
+
+/// move the cursor to the start of the next line and scroll the display if it is at the bottom of the scroll region
+fn new_line(display: &mut impl CharDisplay, cursor: &mut Cursor, rows: usize) {
+    cursor.col = 0;
+    match cursor.region {
+        Some((top, bottom)) if cursor.row == bottom => display.scroll_region(top, bottom),
+        _ if cursor.row + 1 < rows => cursor.row += 1,
+        None => display.scroll(),
+        // below the scroll region the cursor stays on the last row
+        Some(_) => (),
+    }
+}
+
+impl<D: CharDisplay> ConsoleImpl for CharDisplayConsole<D> {
+    fn putc(&self, c: char) {
+        self.with_display(|display, cursor| put_char(display, cursor, c));
+    }
+
+    fn puts(&self, s: &str) {
+        self.with_display(|display, cursor| s.chars().for_each(|c| put_char(display, cursor, c)));
+    }
+
+    fn flush(&self) {
+        self.with_display(|display, _| display.flush());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::String, vec, vec::Vec};
+
+    /// a display keeping the characters in a grid
+    struct Grid {
+        cells: Vec<Vec<char>>,
+    }
+
+    impl Grid {
+        fn new(cols: usize, rows: usize) -> Self {
+            Grid {
+                cells: vec![vec!['.'; cols]; rows],
+            }
+        }
+
+        fn rows(&self) -> Vec<String> {
+            self.cells.iter().map(|row| row.iter().collect()).collect()
+        }
+    }
+
+    impl CharDisplay for Grid {
+        fn size(&self) -> (usize, usize) {
+            (self.cells[0].len(), self.cells.len())
+        }
+
+        fn write_char(&mut self, col: usize, row: usize, c: char) {
+            self.cells[row][col] = c;
+        }
+
+        fn scroll(&mut self) {
+            let cols = self.cells[0].len();
+            self.cells.remove(0);
+            self.cells.push(vec!['.'; cols]);
+        }
+
+        fn scroll_region(&mut self, top: usize, bottom: usize) {
+            let cols = self.cells[0].len();
+            self.cells.remove(top);
+            self.cells.insert(bottom, vec!['.'; cols]);
+        }
+
+        fn clear(&mut self) {
+            for row in self.cells.iter_mut() {
+                row.iter_mut().for_each(|c| *c = '.');
+            }
+        }
+    }
+
+    fn shown(cols: usize, rows: usize, s: &str) -> Vec<String> {
+        let console = CharDisplayConsole::new(Grid::new(cols, rows));
+        console.puts(s);
+        console.into_inner().rows()
+    }
+
+    #[test]
+    fn wraps_at_the_right_border() {
+        assert_eq!(shown(4, 3, "abcdef"), ["abcd", "ef..", "...."]);
+        assert_eq!(shown(4, 3, "abcd\r\nx"), ["abcd", "x...", "...."]);
+    }
+
+    #[test]
+    fn scrolls_below_the_last_line() {
+        assert_eq!(shown(4, 2, "one\ntwo\nsix"), ["two.", "six."]);
+    }
+
+    #[test]
+    fn moves_the_cursor_with_control_characters() {
+        assert_eq!(shown(10, 1, "a\tb"), ["a       b."]);
+        assert_eq!(shown(4, 1, "abc\u{8}x\rz"), ["zbx."]);
+        let console = CharDisplayConsole::new(Grid::new(4, 2));
+        console.puts("ab\n");
+        assert_eq!(console.cursor(), (0, 1));
+        console.set_cursor(9, 9);
+        assert_eq!(console.cursor(), (3, 1));
+    }
+
+    #[test]
+    fn skips_escape_sequences() {
+        assert_eq!(shown(8, 1, "\u{1b}[31mE\u{1b}[0m: x"), ["E: x...."]);
+    }
+
+    #[test]
+    fn emulates_the_cursor_sequences() {
+        assert_eq!(shown(4, 3, "\u{1b}[2;3Hx\u{1b}[Hy"), ["y...", "..x.", "...."]);
+        assert_eq!(shown(4, 2, "ab\u{1b}7\r\ncd\u{1b}8x"), ["abx.", "cd.."]);
+    }
+
+    #[test]
+    fn emulates_the_erase_sequences() {
+        assert_eq!(shown(4, 2, "abcd\u{1b}[1;2H\u{1b}[K"), ["a   ", "...."]);
+        assert_eq!(shown(4, 2, "abcd\u{1b}[1;3H\u{1b}[1K"), ["   d", "...."]);
+        assert_eq!(shown(4, 2, "abcdef\u{1b}[1;2H\u{1b}[J"), ["a   ", "    "]);
+        assert_eq!(shown(4, 2, "abcdef\u{1b}[2J"), ["....", "...."]);
+    }
+
+    #[test]
+    fn keeps_the_rows_below_the_scroll_region() {
+        assert_eq!(
+            shown(4, 3, "\u{1b}[3;1Hbar\u{1b}[1;2rone\ntwo\nsix"),
+            ["two.", "six.", "bar."]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Character set filter
+//!
+//! Not every output channel is able to display any character. A serial terminal configured for ASCII shows garbage
+//! for multi byte characters, and binary data passed with ``Console::puts_bytes`` might not be valid UTF-8 at all.
+//! The ``CharsetConsole`` wraps an output channel and replaces every character it can't handle:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.add_sink(CharsetConsole::new(uart, Charset::Ascii)));
+//! ```
+//! Invalid UTF-8 sequences are replaced with U+FFFD for ``Charset::Utf8`` and with ``?`` for ``Charset::Ascii``,
+//! the latter also replaces every character outside of ASCII. ``with_replacement`` sets a different replacement
+//! character.
+
+use crate::{ConsoleImpl, ConsoleInput};
+
+/// The characters an output channel is able to display
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charset {
+    /// Any valid UTF-8
+    Utf8,
+    /// The 7 bit ASCII characters only
+    Ascii,
+}
+
+impl Charset {
+    /// Check if the character is part of the character set
+    pub fn contains(self, c: char) -> bool {
+        match self {
+            Charset::Utf8 => true,
+            Charset::Ascii => c.is_ascii(),
+        }
+    }
+}
+
+/// The output channel replacing all characters the wrapped one is not able to display
+pub struct CharsetConsole<C> {
+    inner: C,
+    charset: Charset,
+    replacement: char,
+}
+
+impl<C: ConsoleImpl> CharsetConsole<C> {
+    /// Create a new output channel passing the characters of the given character set to the given output channel
+    pub const fn new(inner: C, charset: Charset) -> Self {
+        let replacement = match charset {
+            Charset::Utf8 => '\u{FFFD}',
+            Charset::Ascii => '?',
+        };
+        CharsetConsole {
+            inner,
+            charset,
+            replacement,
+        }
+    }
+
+    /// Replace the characters outside of the character set and invalid UTF-8 with the given character
+    pub const fn with_replacement(mut self, replacement: char) -> Self {
+        self.replacement = replacement;
+        self
+    }
+
+    /// Hand back the wrapped output channel
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// pass the supported runs of the string and the replacement of all other characters
+    fn write_str(&self, s: &str) {
+        let mut start = 0;
+        for (idx, c) in s.char_indices() {
+            if !self.charset.contains(c) {
+                if start < idx {
+                    self.inner.puts(&s[start..idx]);
+                }
+                self.inner.putc(self.replacement);
+                start = idx + c.len_utf8();
+            }
+        }
+        if start == 0 {
+            self.inner.puts(s);
+        } else if start < s.len() {
+            self.inner.puts(&s[start..]);
+        }
+    }
+}
+
+impl<C: ConsoleImpl> ConsoleImpl for CharsetConsole<C> {
+    fn putc(&self, c: char) {
+        if self.charset.contains(c) {
+            self.inner.putc(c);
+        } else {
+            self.inner.putc(self.replacement);
+        }
+    }
+
+    fn puts(&self, s: &str) {
+        self.write_str(s);
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        for chunk in bytes.utf8_chunks() {
+            if !chunk.valid().is_empty() {
+                self.write_str(chunk.valid());
+            }
+            if !chunk.invalid().is_empty() {
+                self.inner.putc(self.replacement);
+            }
+        }
+    }
+
+    fn input(&self) -> Option<&dyn ConsoleInput> {
+        self.inner.input()
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+
+    fn poll(&self) {
+        self.inner.poll();
+    }
+
+    fn close(&mut self) {
+        self.inner.close();
+    }
+}
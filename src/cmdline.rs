@@ -0,0 +1,172 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Kernel command line
+//!
+//! Like Linux the console could be configured at boot with options of the kernel command line, e.g. read from the
+//! ``cmdline.txt``, the ATAGS or the device tree:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.apply_cmdline("console=serial0 loglevel=debug log_filter=sdhci=trace"));
+//! ```
+//! The options understood are
+//! - ``loglevel=<level>`` sets the log level, given as name like ``warn`` or as Linux console level. Like Linux the
+//!   console level enables the messages below it: ``1`` to ``4`` print errors, ``5`` warnings, ``6`` and ``7`` info
+//!   messages and from ``8`` on debug messages. Errors are always printed, so ``0`` acts like ``1`` and does not
+//!   silence the console
+//! - ``quiet`` sets the log level to ``Level::Warn``
+//! - ``console_color=on|off`` enables or disables the colorized severity prefix
+//! - ``log_filter=<filter>`` sets the target filter, see ``Filter::parse``
+//!
+//! All other options are ignored. Values could be enclosed in double quotes.
+
+use crate::{Console, ConsoleConfig, Filter, Level};
+use core::fmt;
+
+/// The error returned if an option of the kernel command line carries an invalid value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CmdlineError<'a> {
+    option: &'a str,
+}
+
+impl<'a> CmdlineError<'a> {
+    /// The invalid option like ``loglevel=loud``
+    pub fn option(&self) -> &'a str {
+        self.option
+    }
+}
+
+impl fmt::Display for CmdlineError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid console option: {}", self.option)
+    }
+}
+
+impl ConsoleConfig {
+    /// Create a configuration from the console options of the kernel command line. Fails with the first option
+    /// carrying an invalid value.
+    pub fn from_cmdline(cmdline: &str) -> Result<Self, CmdlineError<'_>> {
+        cmdline
+            .split_whitespace()
+            .try_fold(ConsoleConfig::new(), |config, option| {
+                parse_option(config, option).ok_or(CmdlineError { option })
+            })
+    }
+}
+
+impl Console {
+    /// Apply the console options of the kernel command line. Options carrying an invalid value are skipped, the
+    /// first of them is returned once all valid options are applied.
+    pub fn apply_cmdline<'a>(&mut self, cmdline: &'a str) -> Result<(), CmdlineError<'a>> {
+        let mut result = Ok(());
+        for option in cmdline.split_whitespace() {
+            match parse_option(ConsoleConfig::new(), option) {
+                Some(config) => self.configure(config),
+                None if result.is_ok() => result = Err(CmdlineError { option }),
+                None => (),
+            }
+        }
+        result
+    }
+}
+
+/// add a single option to the configuration, ``None`` if its value is invalid
+fn parse_option(config: ConsoleConfig, option: &str) -> Option<ConsoleConfig> {
+    let (key, value) = option.split_once('=').unwrap_or((option, ""));
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+    let config = match key {
+        "loglevel" => config.log_level(parse_level(value)?),
+        "quiet" => config.log_level(Level::Warn),
+        "console_color" => match value {
+            "on" | "1" | "true" => config.color(true),
+            "off" | "0" | "false" => config.color(false),
+            _ => return None,
+        },
+        "log_filter" => config.filter(Filter::parse(value).ok()?),
+        _ => config,
+    };
+    Some(config)
+}
+
+/// parse a level name or a Linux console level, which enables the messages below the given level
+fn parse_level(value: &str) -> Option<Level> {
+    match value.parse::<u32>() {
+        Ok(0..=4) => Some(Level::Error),
+        Ok(5) => Some(Level::Warn),
+        Ok(6) | Ok(7) => Some(Level::Info),
+        Ok(_) => Some(Level::Debug),
+        Err(_) => value.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linux_console_levels() {
+        assert_eq!(parse_level("0"), Some(Level::Error));
+        assert_eq!(parse_level("4"), Some(Level::Error));
+        assert_eq!(parse_level("5"), Some(Level::Warn));
+        assert_eq!(parse_level("6"), Some(Level::Info));
+        assert_eq!(parse_level("7"), Some(Level::Info));
+        assert_eq!(parse_level("8"), Some(Level::Debug));
+        assert_eq!(parse_level("15"), Some(Level::Debug));
+    }
+
+    #[test]
+    fn level_names() {
+        assert_eq!(parse_level("warn"), Some(Level::Warn));
+        assert_eq!(parse_level("trace"), Some(Level::Trace));
+        assert_eq!(parse_level("loud"), None);
+        assert_eq!(parse_level(""), None);
+    }
+
+    #[test]
+    fn valid_options() {
+        for option in &[
+            "loglevel=debug",
+            "loglevel=\"7\"",
+            "quiet",
+            "console_color=on",
+            "console_color=0",
+            "log_filter=sdhci=trace",
+            "root=/dev/mmcblk0p2",
+        ] {
+            assert!(
+                parse_option(ConsoleConfig::new(), option).is_some(),
+                "{}",
+                option
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_options() {
+        for option in &[
+            "loglevel=loud",
+            "loglevel",
+            "console_color=maybe",
+            "log_filter=sdhci=loud",
+        ] {
+            assert!(
+                parse_option(ConsoleConfig::new(), option).is_none(),
+                "{}",
+                option
+            );
+        }
+    }
+
+    #[test]
+    fn first_invalid_option() {
+        let error = ConsoleConfig::from_cmdline("quiet loglevel=loud console_color=maybe").err();
+        assert_eq!(error.map(|error| error.option()), Some("loglevel=loud"));
+        assert!(ConsoleConfig::from_cmdline("console=serial0 loglevel=3 quiet").is_ok());
+    }
+}
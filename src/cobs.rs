@@ -0,0 +1,192 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # COBS framing
+//!
+//! If the Uart carrying the console output is shared with other traffic the host needs to know where a message
+//! starts and ends. The ``CobsConsole`` wraps an output channel and sends every line as a frame encoded with the
+//! Consistent Overhead Byte Stuffing. The encoded frame does not contain any zero byte, a single zero byte terminates
+//! it, so the host is able to resynchronize on the next zero byte after garbage or lost bytes:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.replace(CobsConsole::new(uart)));
+//! ```
+//! The output is collected until a line break (``\n``) has been written or ``COBS_FRAME_SIZE`` bytes are collected.
+//! Flushing the console sends the collected output as frame as well. ``cobs_encode`` encodes arbitrary data, e.g.
+//! to frame binary data sent with ``Console::puts_bytes``.
+
+use crate::{ConsoleImpl, ConsoleInput, Locked};
+
+/// The maximum number of bytes sent as a single frame
+pub const COBS_FRAME_SIZE: usize = 254;
+
+/// Encode the data with COBS and pass the encoded chunks to the output function. The last chunk is the terminating
+/// zero byte.
+pub fn cobs_encode(data: &[u8], mut output: impl FnMut(&[u8])) {
+    // every block is prefixed with its length plus one and followed by an implicit zero, except for blocks of 254
+    // non-zero bytes
+    let mut start = 0;
+    for (idx, byte) in data.iter().enumerate() {
+        if *byte == 0 {
+            output(&[(idx - start + 1) as u8]);
+            output(&data[start..idx]);
+            start = idx + 1;
+        } else if idx - start + 1 == 254 {
+            output(&[255]);
+            output(&data[start..=idx]);
+            start = idx + 1;
+        }
+    }
+    output(&[(data.len() - start + 1) as u8]);
+    output(&data[start..]);
+    output(&[0]);
+}
+
+/// The output collected for the next frame
+struct Frame {
+    data: [u8; COBS_FRAME_SIZE],
+    len: usize,
+}
+
+/// The output channel sending the output to another output channel as COBS encoded frames
+pub struct CobsConsole<C> {
+    inner: C,
+    frame: Locked<Frame>,
+}
+
+impl<C: ConsoleImpl> CobsConsole<C> {
+    /// Create a new output channel sending frames to the given output channel
+    pub const fn new(inner: C) -> Self {
+        CobsConsole {
+            inner,
+            frame: Locked::new(Frame {
+                data: [0; COBS_FRAME_SIZE],
+                len: 0,
+            }),
+        }
+    }
+
+    /// Hand back the wrapped output channel. Output collected for the next frame is dropped.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// collect the bytes and send a frame once a line is complete or the frame is full
+    fn write_bytes(&self, bytes: &[u8]) {
+        self.frame.with(|frame| {
+            for byte in bytes {
+                frame.data[frame.len] = *byte;
+                frame.len += 1;
+                if *byte == b'\n' || frame.len == COBS_FRAME_SIZE {
+                    self.send(frame);
+                }
+            }
+        });
+    }
+
+    /// send the collected output as a single frame
+    fn send(&self, frame: &mut Frame) {
+        if frame.len == 0 {
+            return;
+        }
+        // a frame of 254 non-zero bytes needs two code bytes and the terminating zero byte
+        let mut encoded = [0; COBS_FRAME_SIZE + 3];
+        let mut len = 0;
+        cobs_encode(&frame.data[..frame.len], |chunk| {
+            encoded[len..len + chunk.len()].copy_from_slice(chunk);
+            len += chunk.len();
+        });
+        self.inner.puts_bytes(&encoded[..len]);
+        frame.len = 0;
+    }
+
+    /// send the output collected so far as frame
+    fn send_pending(&self) {
+        self.frame.with(|frame| self.send(frame));
+    }
+}
+
+impl<C: ConsoleImpl> ConsoleImpl for CobsConsole<C> {
+    fn putc(&self, c: char) {
+        self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    fn puts(&self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        self.write_bytes(bytes);
+    }
+
+    fn input(&self) -> Option<&dyn ConsoleInput> {
+        self.inner.input()
+    }
+
+    fn flush(&self) {
+        self.send_pending();
+        self.inner.flush();
+    }
+
+    fn poll(&self) {
+        self.inner.poll();
+    }
+
+    fn close(&mut self) {
+        self.send_pending();
+        self.inner.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn encode(data: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        cobs_encode(data, |chunk| encoded.extend_from_slice(chunk));
+        encoded
+    }
+
+    #[test]
+    fn encodes_zero_bytes() {
+        assert_eq!(encode(&[]), [1, 0]);
+        assert_eq!(encode(&[0]), [1, 1, 0]);
+        assert_eq!(
+            encode(&[0x11, 0x22, 0x00, 0x33]),
+            [3, 0x11, 0x22, 2, 0x33, 0]
+        );
+        assert_eq!(encode(&[0x11, 0x00, 0x00]), [2, 0x11, 1, 1, 0]);
+    }
+
+    #[test]
+    fn terminates_with_a_single_zero() {
+        let encoded = encode(b"hello\r\n");
+        assert_eq!(encoded.last(), Some(&0));
+        assert_eq!(encoded.iter().filter(|byte| **byte == 0).count(), 1);
+    }
+
+    #[test]
+    fn splits_blocks_of_254_bytes() {
+        let data: Vec<u8> = (1..=254).collect();
+        let encoded = encode(&data);
+        assert_eq!(encoded.len(), 257);
+        assert_eq!(encoded[0], 255);
+        assert_eq!(&encoded[1..255], &data[..]);
+        assert_eq!(&encoded[255..], [1, 0]);
+    }
+
+    #[test]
+    fn splits_blocks_of_255_bytes() {
+        let data: Vec<u8> = (0..255).map(|idx| (idx % 254) as u8 + 1).collect();
+        let encoded = encode(&data);
+        assert_eq!(encoded.len(), 258);
+        assert_eq!(encoded[0], 255);
+        assert_eq!(&encoded[1..255], &data[..254]);
+        assert_eq!(&encoded[255..], [2, data[254], 0]);
+    }
+}
@@ -0,0 +1,172 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Console configuration
+//!
+//! Instead of calling the setters of the console one by one the runtime options could be gathered in a
+//! ``ConsoleConfig`` and applied at once:
+//! ```ignore
+//! let config = ConsoleConfig::new()
+//!     .newline(Newline::CrLf)
+//!     .color(true)
+//!     .log_level(Level::Debug)
+//!     .timestamp_source(SystemTimer);
+//! CONSOLE.take_for(|cons| cons.configure(config));
+//! ```
+//! Only the options set in the configuration are changed, all others keep their current value.
+
+use crate::{Console, Filter, Level, LineDiscipline, LogFormatter, Newline, TimestampSource};
+use alloc::boxed::Box;
+
+/// A set of runtime options of the console
+#[derive(Default)]
+pub struct ConsoleConfig {
+    newline: Option<Newline>,
+    color: Option<bool>,
+    level: Option<Level>,
+    filter: Option<Filter>,
+    location: Option<bool>,
+    sequence: Option<bool>,
+    suppress_repeats: Option<bool>,
+    echo: Option<bool>,
+    flow_control: Option<bool>,
+    discipline: Option<LineDiscipline>,
+    max_line_length: Option<Option<usize>>,
+    timestamp: Option<Box<dyn TimestampSource>>,
+    formatter: Option<Box<dyn LogFormatter>>,
+}
+
+impl ConsoleConfig {
+    /// Create a configuration that does not change any option
+    pub fn new() -> Self {
+        ConsoleConfig::default()
+    }
+
+    /// Set the newline mode, see ``Console::set_newline``
+    pub fn newline(mut self, newline: Newline) -> Self {
+        self.newline = Some(newline);
+        self
+    }
+
+    /// Enable or disable the colorized severity prefix, see ``Console::set_color``
+    pub fn color(mut self, enable: bool) -> Self {
+        self.color = Some(enable);
+        self
+    }
+
+    /// Set the log level, see ``Console::set_log_level``
+    pub fn log_level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Set the target filter, see ``Console::set_filter``
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Enable or disable the source location prefix, see ``Console::set_location``
+    pub fn location(mut self, enable: bool) -> Self {
+        self.location = Some(enable);
+        self
+    }
+
+    /// Enable or disable the sequence numbers, see ``Console::set_sequence``
+    pub fn sequence(mut self, enable: bool) -> Self {
+        self.sequence = Some(enable);
+        self
+    }
+
+    /// Enable or disable the repeat suppression, see ``Console::set_suppress_repeats``
+    pub fn suppress_repeats(mut self, enable: bool) -> Self {
+        self.suppress_repeats = Some(enable);
+        self
+    }
+
+    /// Enable or disable the echo of received characters, see ``Console::echo``
+    pub fn echo(mut self, enable: bool) -> Self {
+        self.echo = Some(enable);
+        self
+    }
+
+    /// Enable or disable the software flow control, see ``Console::set_flow_control``
+    pub fn flow_control(mut self, enable: bool) -> Self {
+        self.flow_control = Some(enable);
+        self
+    }
+
+    /// Set the line discipline of the input, see ``Console::set_line_discipline``
+    pub fn line_discipline(mut self, discipline: LineDiscipline) -> Self {
+        self.discipline = Some(discipline);
+        self
+    }
+
+    /// Set the line length limit, see ``Console::set_max_line_length``
+    pub fn max_line_length(mut self, max: Option<usize>) -> Self {
+        self.max_line_length = Some(max);
+        self
+    }
+
+    /// Set the source of the timestamps, see ``Console::set_timestamp_source``
+    pub fn timestamp_source<T: TimestampSource + 'static>(mut self, source: T) -> Self {
+        self.timestamp = Some(Box::from(source));
+        self
+    }
+
+    /// Set the formatter of log records, see ``Console::set_formatter``
+    pub fn formatter<T: LogFormatter + 'static>(mut self, formatter: T) -> Self {
+        self.formatter = Some(Box::from(formatter));
+        self
+    }
+}
+
+impl Console {
+    /// Apply all options set in the configuration. The Console takes ownership of the timestamp source and the
+    /// formatter of the configuration.
+    pub fn configure(&mut self, config: ConsoleConfig) {
+        if let Some(newline) = config.newline {
+            self.set_newline(newline);
+        }
+        if let Some(enable) = config.color {
+            self.set_color(enable);
+        }
+        if let Some(level) = config.level {
+            self.set_log_level(level);
+        }
+        if let Some(filter) = config.filter {
+            self.set_filter(filter);
+        }
+        if let Some(enable) = config.location {
+            self.set_location(enable);
+        }
+        if let Some(enable) = config.sequence {
+            self.set_sequence(enable);
+        }
+        if let Some(enable) = config.suppress_repeats {
+            self.set_suppress_repeats(enable);
+        }
+        if let Some(enable) = config.echo {
+            self.echo(enable);
+        }
+        if let Some(enable) = config.flow_control {
+            self.set_flow_control(enable);
+        }
+        if let Some(discipline) = config.discipline {
+            self.set_line_discipline(discipline);
+        }
+        if let Some(max) = config.max_line_length {
+            self.set_max_line_length(max);
+        }
+        if let Some(source) = config.timestamp {
+            self.timestamp = Some(source);
+        }
+        if let Some(formatter) = config.formatter {
+            self.formatter = Some(formatter);
+        }
+    }
+}
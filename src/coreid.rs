@@ -0,0 +1,50 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Core identification
+//!
+//! On multi-core kernels it is helpful to know which core printed a message. The console does not know how to
+//! detect the current core, so provide a structure that implements the ``CoreIdProvider`` trait:
+//! ```ignore
+//! struct Mpidr;
+//!
+//! impl CoreIdProvider for Mpidr {
+//!     fn core_id(&self) -> usize {
+//!         read_mpidr() & 0x3
+//!     }
+//! }
+//!
+//! CONSOLE.take_for(|cons| cons.set_core_id_provider(Mpidr));
+//! ```
+//! From this point every message of the severity macros is prefixed with ``[core N]``.
+
+use crate::Console;
+use alloc::boxed::Box;
+
+/// Every provider of the current core id need to implement this trait
+pub trait CoreIdProvider {
+    /// the id of the core this function is called on
+    fn core_id(&self) -> usize;
+}
+
+impl Console {
+    /// Set the provider of the core id the messages of the severity macros are prefixed with. The Console takes
+    /// ownership of the provider.
+    pub fn set_core_id_provider<T: CoreIdProvider + 'static>(&mut self, provider: T) {
+        self.core_id.replace(Box::from(provider));
+    }
+
+    /// Remove the core id provider. The messages are no longer prefixed with the core id.
+    pub fn clear_core_id_provider(&mut self) {
+        self.core_id = None;
+    }
+
+    /// The id of the current core if a core id provider has been set
+    pub fn core_id(&self) -> Option<usize> {
+        self.core_id.as_ref().map(|provider| provider.core_id())
+    }
+}
@@ -0,0 +1,230 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Deferred output
+//!
+//! Writing to the output channel from an interrupt handler may block until another core finished its output or
+//! even deadlock if the interrupted code was just writing to the same channel. The console does not know whether it
+//! is called in interrupt context, so provide a structure that implements the ``InterruptDetector`` trait:
+//! ```ignore
+//! struct Daif;
+//!
+//! impl InterruptDetector for Daif {
+//!     fn in_interrupt(&self) -> bool {
+//!         IRQ_NESTING.load(Ordering::Relaxed) > 0
+//!     }
+//! }
+//!
+//! CONSOLE.take_for(|cons| cons.set_interrupt_detector(Daif));
+//! ```
+//! From this point all output in interrupt context is formatted into a lock-free queue of ``QUEUE_SLOTS`` messages
+//! instead of being written. The queued messages are written in thread context once ``Console::poll`` is called, e.g.
+//! from the main loop of the kernel:
+//! ```ignore
+//! loop {
+//!     CONSOLE.use_for(|cons| cons.poll());
+//! }
+//! ```
+//! Schedulers that need to bound the time spent in the console call ``console_service`` from their idle task
+//! instead. It writes at most the given number of queued messages and lets every output channel do a bounded amount
+//! of buffered work with ``ConsoleImpl::poll``:
+//! ```ignore
+//! fn idle_task() {
+//!     loop {
+//!         console_service(4);
+//!         yield_now();
+//!     }
+//! }
+//! ```
+//!
+//! # Queued mode
+//! If several cores print frequently they contend for the output channel. In queued mode every core only formats
+//! its messages into the queue, which does not need any lock. Whichever core finds the output channel idle after
+//! queueing its message writes all queued messages, while the other cores return immediately:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_queued(true));
+//! ```
+//! Queued messages are truncated to ``QUEUE_MESSAGE_SIZE`` bytes and dropped if the queue is full. Log records are
+//! formatted to text before they are queued, so output channels providing a ``RecordSink`` receive them as text.
+
+use crate::{Console, Level, CONSOLE};
+use alloc::boxed::Box;
+use core::fmt;
+
+/// Every detector of the interrupt context need to implement this trait
+pub trait InterruptDetector {
+    /// whether this function is called from an interrupt handler
+    fn in_interrupt(&self) -> bool;
+}
+
+impl Console {
+    /// Set the detector of the interrupt context. Output in interrupt context is deferred until ``poll`` is called.
+    /// The Console takes ownership of the detector.
+    pub fn set_interrupt_detector<T: InterruptDetector + 'static>(&mut self, detector: T) {
+        self.interrupt.replace(Box::from(detector));
+    }
+
+    /// Remove the detector of the interrupt context. Output is no longer deferred.
+    pub fn clear_interrupt_detector(&mut self) {
+        self.interrupt = None;
+    }
+
+    /// Check if the console is called in interrupt context. Always ``false`` without an interrupt detector
+    pub fn in_interrupt(&self) -> bool {
+        self.interrupt
+            .as_ref()
+            .is_some_and(|detector| detector.in_interrupt())
+    }
+
+    /// Enable or disable the queued mode. In queued mode all output is passed through the lock-free queue and
+    /// written by a single core at a time. Output still queued when disabling it is written on the next ``poll``.
+    pub fn set_queued(&mut self, enable: bool) {
+        self.queued = enable;
+    }
+
+    /// Check if the queued mode is enabled
+    pub fn queued(&self) -> bool {
+        self.queued
+    }
+
+    /// Write the deferred output to the output channels in the order it has been printed. Returns the number of
+    /// messages written. Does nothing if called in interrupt context or while another core writes the deferred
+    /// output.
+    pub fn poll(&self) -> usize {
+        self.drain(usize::MAX)
+    }
+
+    /// Perform a bounded amount of pending work: write at most ``budget`` deferred messages and let the current
+    /// active console and all additional sinks do their buffered work with ``ConsoleImpl::poll``. Returns the number
+    /// of messages written. Does nothing in interrupt context.
+    pub fn service(&self, budget: usize) -> usize {
+        if self.in_interrupt() {
+            return 0;
+        }
+        let count = self.drain(budget);
+        self.guarded(|| {
+            self.get_current().poll();
+            for sink in self.sink_iter() {
+                sink.poll();
+            }
+        });
+        count
+    }
+
+    /// write at most ``limit`` deferred messages
+    fn drain(&self, limit: usize) -> usize {
+        if self.in_interrupt() {
+            return 0;
+        }
+        let mut count = 0;
+        // a message queued while another core drains the queue is picked up by this loop of the draining core
+        while count < limit && !self.queue.is_empty() {
+            let written = self.queue.drain(limit - count, |level, s| match level {
+                Some(level) => self.puts_level_direct(level, s),
+                None => self.puts_direct(s),
+            });
+            if written == 0 {
+                break;
+            }
+            count += written;
+        }
+        count
+    }
+
+    /// Check if there is deferred output waiting for ``poll``
+    pub fn has_deferred(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// The number of messages dropped because the queue was full
+    pub fn deferred_dropped(&self) -> usize {
+        self.queue.dropped()
+    }
+
+    /// whether output is passed through the queue instead of being written directly
+    pub(crate) fn defers_output(&self) -> bool {
+        self.queued || self.in_interrupt()
+    }
+
+    /// Queue a message and write the queue unless in interrupt context. Returns ``false`` if the message has been
+    /// dropped. Like direct output, output of a muted console and nested output of an output channel that prints
+    /// while it is writing is dropped, unless the output channel has been interrupted.
+    pub(crate) fn defer(
+        &self,
+        level: Option<Level>,
+        format: impl FnOnce(&mut dyn fmt::Write) -> fmt::Result,
+    ) -> bool {
+        if self.is_muted() || (self.is_busy() && !self.in_interrupt()) {
+            return false;
+        }
+        let queued = self.queue.push(level, format);
+        self.poll();
+        queued
+    }
+}
+
+/// Perform a bounded amount of pending work of the console, see ``Console::service``. This is meant to be called
+/// from the idle task or the main loop of a scheduler.
+pub fn console_service(budget: usize) -> usize {
+    CONSOLE.use_for(|console| console.service(budget))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConsoleImpl, CONSOLE, CONSOLE_TEST_LOCK};
+    use alloc::{string::String, sync::Arc};
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Mutex, PoisonError};
+
+    static INTERRUPT: AtomicBool = AtomicBool::new(false);
+
+    struct Flag;
+
+    impl InterruptDetector for Flag {
+        fn in_interrupt(&self) -> bool {
+            INTERRUPT.load(Ordering::Relaxed)
+        }
+    }
+
+    struct Collect(Arc<Mutex<String>>);
+
+    impl ConsoleImpl for Collect {
+        fn putc(&self, c: char) {
+            self.0.lock().unwrap().push(c);
+        }
+
+        fn puts(&self, s: &str) {
+            self.0.lock().unwrap().push_str(s);
+        }
+    }
+
+    #[test]
+    fn writes_deferred_output_in_order_on_poll() {
+        let _lock = CONSOLE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let output = Arc::new(Mutex::new(String::new()));
+        CONSOLE.take_for(|console| {
+            console.replace(Collect(output.clone()));
+            console.set_interrupt_detector(Flag);
+        });
+        INTERRUPT.store(true, Ordering::Relaxed);
+        crate::print("one ");
+        crate::print("two ");
+        // polling in interrupt context does not write anything
+        assert_eq!(CONSOLE.use_for(|console| console.poll()), 0);
+        assert!(CONSOLE.use_for(|console| console.has_deferred()));
+        assert_eq!(output.lock().unwrap().as_str(), "");
+        INTERRUPT.store(false, Ordering::Relaxed);
+        assert_eq!(CONSOLE.use_for(|console| console.poll()), 2);
+        assert!(!CONSOLE.use_for(|console| console.has_deferred()));
+        crate::print("three ");
+        CONSOLE.take_for(|console| console.clear_interrupt_detector());
+        assert_eq!(output.lock().unwrap().as_str(), "one two three ");
+    }
+}
@@ -0,0 +1,125 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Line discipline
+//!
+//! The line discipline decides how the received characters are handed to the readers of the console. In the
+//! ``Raw`` mode every character is passed on as soon as it arrives, which suits binary protocols and programs doing
+//! their own line editing. In the ``Canonical`` mode the console collects whole lines first:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_line_discipline(LineDiscipline::Canonical));
+//! ```
+//! A backspace erases the last character of the line, Ctrl-U kills the whole line. ``read_char`` and
+//! ``try_read_char`` only return the characters of a line once it is finished with a newline, which is passed to
+//! the reader as ``\n``. If echo is enabled the line is echoed as it is edited. The ``LineEditor`` and ``read_key``
+//! always read the characters in the raw mode.
+
+use crate::input::{BACKSPACE, DELETE};
+use crate::{Console, Locked};
+use alloc::{collections::VecDeque, string::String};
+
+/// the kill character, Ctrl-U
+const KILL: char = '\u{15}';
+
+/// The way received characters are passed to the readers of the console
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineDiscipline {
+    /// Every character is passed as soon as it is received
+    Raw,
+    /// Characters are passed once the line is finished, lines could be edited with erase and kill
+    Canonical,
+}
+
+/// The lines collected in the canonical mode
+pub(crate) struct LineBuffer {
+    /// the line edited at the moment and the characters of the finished lines not read so far
+    lines: Locked<(String, VecDeque<char>)>,
+}
+
+impl LineBuffer {
+    pub(crate) const fn new() -> Self {
+        LineBuffer {
+            lines: Locked::new((String::new(), VecDeque::new())),
+        }
+    }
+
+    /// run the given function with exclusive access to the edited line and the finished ones
+    fn with_lines<R>(&self, f: impl FnOnce(&mut String, &mut VecDeque<char>) -> R) -> R {
+        self.lines.with(|(line, ready)| f(line, ready))
+    }
+}
+
+impl Console {
+    /// Set the way received characters are passed to the readers. Characters collected in the canonical mode so
+    /// far are dropped.
+    pub fn set_line_discipline(&mut self, discipline: LineDiscipline) {
+        self.lines.with_lines(|line, ready| {
+            line.clear();
+            ready.clear();
+        });
+        self.discipline = discipline;
+    }
+
+    /// The way received characters are passed to the readers
+    pub fn line_discipline(&self) -> LineDiscipline {
+        self.discipline
+    }
+
+    /// read the next character of a finished line, collecting and editing a new line if there is none. Returns
+    /// ``None`` if the input channel delivers no further character, without waiting for it if not blocking.
+    pub(crate) fn read_canonical(&self, blocking: bool) -> Option<char> {
+        loop {
+            if let Some(c) = self.lines.with_lines(|_, ready| ready.pop_front()) {
+                return Some(c);
+            }
+            let c = self.receive(blocking)?;
+            self.edit_line(c);
+        }
+    }
+
+    /// apply a received character to the line edited at the moment
+    fn edit_line(&self, c: char) {
+        match c {
+            '\r' | '\n' => {
+                self.lines.with_lines(|line, ready| {
+                    ready.extend(line.chars());
+                    ready.push_back('\n');
+                    line.clear();
+                });
+                self.echo_str("\r\n");
+            }
+            BACKSPACE | DELETE => {
+                if self.lines.with_lines(|line, _| line.pop()).is_some() {
+                    // move back, blank the character and move back again
+                    self.echo_str("\u{8} \u{8}");
+                }
+            }
+            KILL => {
+                let erased = self.lines.with_lines(|line, _| {
+                    let count = line.chars().count();
+                    line.clear();
+                    count
+                });
+                for _ in 0..erased {
+                    self.echo_str("\u{8} \u{8}");
+                }
+            }
+            c => {
+                self.lines.with_lines(|line, _| line.push(c));
+                if self.echo {
+                    self.putc(c);
+                }
+            }
+        }
+    }
+
+    fn echo_str(&self, s: &str) {
+        if self.echo {
+            self.puts(s);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Memory dumps
+//!
+//! Print memory contents as classic hex dump with 16 bytes per row and an ASCII column:
+//! ```text
+//! 00000000  48 65 6c 6c 6f 2c 20 52  75 73 50 69 52 6f 21 0a  |Hello, RusPiRo!.|
+//! ```
+//! Use the ``hexdump!`` macro to dump a slice, or ``hexdump_raw`` to dump a raw memory region. For MMIO registers or
+//! DMA buffers the layout could be configured with ``dump_memory``:
+//! ```ignore
+//! let cfg = DumpConfig {
+//!     grouping: Grouping::Word,
+//!     ascii: false,
+//!     ..DumpConfig::default()
+//! };
+//! dump_memory(0x3F20_0000, regs, cfg);
+//! // 3f200000  00000001 00000000 00000000 00000000
+//! ```
+
+use crate::ConsoleWriter;
+use core::fmt;
+
+/// The number of bytes displayed as one value of a memory dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// each byte on its own
+    Byte,
+    /// 16 bit little endian values
+    HalfWord,
+    /// 32 bit little endian values
+    Word,
+}
+
+impl Grouping {
+    /// The number of bytes of one group
+    pub fn size(&self) -> usize {
+        match self {
+            Grouping::Byte => 1,
+            Grouping::HalfWord => 2,
+            Grouping::Word => 4,
+        }
+    }
+}
+
+/// The layout of a memory dump
+#[derive(Debug, Clone, Copy)]
+pub struct DumpConfig {
+    /// the number of bytes per row, rounded up to a multiple of the group size
+    pub bytes_per_row: usize,
+    /// how the bytes are grouped into values
+    pub grouping: Grouping,
+    /// show the ASCII column
+    pub ascii: bool,
+    /// the offset of the first byte to dump. The rows stay aligned to the row width, bytes in front of the offset
+    /// are left blank
+    pub offset: usize,
+}
+
+impl Default for DumpConfig {
+    fn default() -> Self {
+        DumpConfig {
+            bytes_per_row: 16,
+            grouping: Grouping::Byte,
+            ascii: true,
+            offset: 0,
+        }
+    }
+}
+
+/// Print the given data as hex dump through the console. The first column shows the address of each row starting
+/// with ``base``.
+pub fn hexdump(base: usize, data: &[u8]) {
+    dump_memory(base, data, DumpConfig::default());
+}
+
+/// Print ``len`` bytes of raw memory starting at ``ptr`` as hex dump through the console. The first column shows the
+/// address of each row.
+/// # Safety
+/// The whole region need to be valid for reads of ``len`` bytes. No other code is allowed to write to it while it is
+/// dumped.
+pub unsafe fn hexdump_raw(ptr: *const u8, len: usize) {
+    hexdump(ptr as usize, core::slice::from_raw_parts(ptr, len));
+}
+
+/// Print the given data as memory dump with the given layout through the console. The first column shows the
+/// address of each row, where ``base`` is the address of the first byte of ``data``.
+pub fn dump_memory(base: usize, data: &[u8], cfg: DumpConfig) {
+    let _ = write_dump(&mut ConsoleWriter, base, data, cfg);
+}
+
+fn write_dump(w: &mut dyn fmt::Write, base: usize, data: &[u8], cfg: DumpConfig) -> fmt::Result {
+    let group = cfg.grouping.size();
+    let row_len = cfg.bytes_per_row.max(1).div_ceil(group) * group;
+    let start = cfg.offset - cfg.offset % row_len;
+    let mut row_start = start;
+    while row_start < data.len() {
+        write!(w, "{:08x} ", base + row_start)?;
+        for group_start in (row_start..row_start + row_len).step_by(group) {
+            // an additional gap in the middle of byte rows improves readability
+            if group == 1 && row_len > 8 && group_start - row_start == row_len / 2 {
+                w.write_str(" ")?;
+            }
+            w.write_str(" ")?;
+            // values are displayed most significant byte first
+            for idx in (group_start..group_start + group).rev() {
+                match data.get(idx) {
+                    Some(byte) if idx >= cfg.offset => write!(w, "{:02x}", byte)?,
+                    _ => w.write_str("  ")?,
+                }
+            }
+        }
+        if cfg.ascii {
+            w.write_str("  |")?;
+            let row = data.iter().enumerate().skip(row_start).take(row_len);
+            for (idx, byte) in row {
+                let c = match *byte {
+                    _ if idx < cfg.offset => ' ',
+                    byte if byte.is_ascii_graphic() || byte == b' ' => byte as char,
+                    _ => '.',
+                };
+                fmt::Write::write_char(w, c)?;
+            }
+            w.write_str("|")?;
+        }
+        w.write_str("\r\n")?;
+        row_start += row_len;
+    }
+    Ok(())
+}
@@ -0,0 +1,148 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Early boot buffer
+//!
+//! Until the first real console is attached with ``Console::replace`` all output is kept in a fixed size ring
+//! buffer of ``EARLY_BUFFER_SIZE`` bytes. Once the console is attached the buffered output is replayed to it, so
+//! messages printed during early boot are not lost. If more output is printed than the buffer is able to hold, the
+//! oldest output is overwritten.
+
+use crate::{ConsoleImpl, Locked};
+
+/// The size of the ring buffer holding the output printed before the first console is attached
+pub const EARLY_BUFFER_SIZE: usize = 4096;
+
+/// Ring buffer keeping the output until the first console is attached
+pub(crate) struct EarlyBuffer {
+    /// the data and the total number of bytes ever written, the write position is this number modulo the buffer
+    /// size
+    ring: Locked<([u8; EARLY_BUFFER_SIZE], usize)>,
+}
+
+impl EarlyBuffer {
+    pub(crate) const fn new() -> Self {
+        EarlyBuffer {
+            ring: Locked::new(([0; EARLY_BUFFER_SIZE], 0)),
+        }
+    }
+
+    /// Append the string to the buffer. Writing to the console must never block, so if the buffer is currently
+    /// updated by someone else (another core or an interrupted one) the string is dropped.
+    pub(crate) fn write(&self, s: &str) {
+        self.ring.try_with(|(data, written)| {
+            for byte in s.bytes() {
+                data[*written % EARLY_BUFFER_SIZE] = byte;
+                *written += 1;
+            }
+        });
+    }
+
+    /// Pass the buffered output to the given console and empty the buffer
+    pub(crate) fn replay(&mut self, console: &dyn ConsoleImpl) {
+        let (data, written) = self.ring.get_mut();
+        let (start, len) = if *written > EARLY_BUFFER_SIZE {
+            (*written % EARLY_BUFFER_SIZE, EARLY_BUFFER_SIZE)
+        } else {
+            (0, *written)
+        };
+        let bytes = (0..len).map(|idx| data[(start + idx) % EARLY_BUFFER_SIZE]);
+        replay_bytes(bytes, |s| console.puts(s));
+        *written = 0;
+    }
+}
+
+/// Pass buffered bytes as chunks of valid UTF-8 to the given output function. Leading continuation bytes of a
+/// partly overwritten character are skipped, as are bytes that are no valid UTF-8 at all.
+pub(crate) fn replay_bytes(bytes: impl Iterator<Item = u8>, mut output: impl FnMut(&str)) {
+    let mut bytes = bytes.skip_while(|byte| *byte & 0xC0 == 0x80).peekable();
+    // an incomplete character at the end of a chunk is carried over to the next one
+    let mut chunk = [0u8; 64];
+    let mut fill = 0;
+    while bytes.peek().is_some() || fill > 0 {
+        while fill < chunk.len() {
+            match bytes.next() {
+                Some(byte) => {
+                    chunk[fill] = byte;
+                    fill += 1;
+                }
+                None => break,
+            }
+        }
+        let (valid, invalid) = match core::str::from_utf8(&chunk[..fill]) {
+            Ok(s) => (s.len(), 0),
+            // an incomplete character at the very end could not be completed any more
+            Err(e) if bytes.peek().is_none() => (e.valid_up_to(), fill - e.valid_up_to()),
+            Err(e) => (e.valid_up_to(), e.error_len().unwrap_or(0)),
+        };
+        // safe as the bytes up to ``valid`` have been validated
+        output(unsafe { core::str::from_utf8_unchecked(&chunk[..valid]) });
+        chunk.copy_within(valid + invalid..fill, 0);
+        fill -= valid + invalid;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use core::cell::RefCell;
+
+    struct Collect(RefCell<String>);
+
+    impl ConsoleImpl for Collect {
+        fn putc(&self, c: char) {
+            self.0.borrow_mut().push(c);
+        }
+
+        fn puts(&self, s: &str) {
+            self.0.borrow_mut().push_str(s);
+        }
+    }
+
+    fn replayed(buffer: &mut EarlyBuffer) -> String {
+        let console = Collect(RefCell::new(String::new()));
+        buffer.replay(&console);
+        console.0.into_inner()
+    }
+
+    #[test]
+    fn replays_the_buffered_output_once() {
+        let mut buffer = EarlyBuffer::new();
+        buffer.write("booting ");
+        buffer.write("kernel\r\n");
+        assert_eq!(replayed(&mut buffer), "booting kernel\r\n");
+        assert_eq!(replayed(&mut buffer), "");
+    }
+
+    #[test]
+    fn keeps_the_latest_output_if_full() {
+        let mut buffer = EarlyBuffer::new();
+        buffer.write("dropped");
+        for _ in 0..EARLY_BUFFER_SIZE / 4 {
+            buffer.write("0123");
+        }
+        buffer.write("end");
+        let output = replayed(&mut buffer);
+        assert_eq!(output.len(), EARLY_BUFFER_SIZE);
+        assert!(output.starts_with("3012"));
+        assert!(output.ends_with("0123end"));
+    }
+
+    #[test]
+    fn skips_a_partly_overwritten_character() {
+        let mut buffer = EarlyBuffer::new();
+        buffer.write("ü");
+        for _ in 0..EARLY_BUFFER_SIZE - 1 {
+            buffer.write("a");
+        }
+        // the first byte of the umlaut has been overwritten, its second byte is dropped
+        let output = replayed(&mut buffer);
+        assert_eq!(output.len(), EARLY_BUFFER_SIZE - 1);
+        assert!(output.bytes().all(|byte| byte == b'a'));
+    }
+}
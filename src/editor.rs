@@ -0,0 +1,317 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Line editor
+//!
+//! Interactive kernels read commands with the ``LineEditor``. Other than ``Console::read_line`` it lets the user
+//! move the cursor within the line and recall previous lines from a history ring:
+//! ```ignore
+//! let mut editor = LineEditor::new();
+//! while let Some(line) = editor.read_line("> ") {
+//!     execute(&line);
+//! }
+//! ```
+//! The arrow keys left and right move the cursor, up and down walk through the history. Home and end (or Ctrl-A and
+//! Ctrl-E) jump to the start and the end of the line, backspace and delete remove the character in front of or at
+//! the cursor and Ctrl-U clears the line. The line is redrawn with ANSI escape sequences, so the editor needs an ANSI
+//! terminal. The history keeps the last ``HISTORY_SIZE`` lines unless configured otherwise; empty lines and
+//! repeats of the previous line are not added.
+//!
+//! A completion function registered with ``set_completion`` is called with the text in front of the cursor once Tab
+//! is pressed and returns the candidates this text could be completed to:
+//! ```ignore
+//! editor.set_completion(|text| {
+//!     ["help", "halt", "dump"].iter().filter(|cmd| cmd.starts_with(text)).map(|cmd| String::from(*cmd)).collect()
+//! });
+//! ```
+//! A single candidate replaces the text, several ones are completed to their common prefix. If they don't share a
+//! longer prefix than the text the candidates are listed below the line.
+
+use crate::ansi::{Cursor, Erase};
+use crate::{read_key, KeyEvent};
+use alloc::{collections::VecDeque, string::String, vec::Vec};
+
+/// The function returning the candidates the text in front of the cursor could be completed to
+pub type Completion = fn(&str) -> Vec<String>;
+
+/// The number of lines the history of a ``LineEditor`` keeps by default
+pub const HISTORY_SIZE: usize = 16;
+
+/// An editor reading lines from the input channel of the current active console
+pub struct LineEditor {
+    history: VecDeque<String>,
+    capacity: usize,
+    completion: Option<Completion>,
+}
+
+impl LineEditor {
+    /// Create an editor keeping the last ``HISTORY_SIZE`` lines
+    pub fn new() -> Self {
+        LineEditor::with_history(HISTORY_SIZE)
+    }
+
+    /// Create an editor keeping the given number of lines in its history
+    pub fn with_history(capacity: usize) -> Self {
+        LineEditor {
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+            completion: None,
+        }
+    }
+
+    /// Print the prompt and read a line edited by the user. The line is added to the history. ``None`` if the
+    /// current active console does not provide any input.
+    pub fn read_line(&mut self, prompt: &str) -> Option<String> {
+        let mut line: Vec<char> = Vec::new();
+        let mut pos = 0;
+        // the index of the history entry shown, the history length while editing a new line
+        let mut entry = self.history.len();
+        // the new line kept while walking through the history
+        let mut draft: Vec<char> = Vec::new();
+        crate::print(prompt);
+        loop {
+            match read_key()? {
+                KeyEvent::Enter => break,
+                KeyEvent::Char(c) => {
+                    line.insert(pos, c);
+                    pos += 1;
+                }
+                KeyEvent::Backspace if pos > 0 => {
+                    pos -= 1;
+                    line.remove(pos);
+                }
+                KeyEvent::Delete if pos < line.len() => {
+                    line.remove(pos);
+                }
+                KeyEvent::Left => pos = pos.saturating_sub(1),
+                KeyEvent::Right => pos = (pos + 1).min(line.len()),
+                KeyEvent::Home | KeyEvent::Ctrl('a') => pos = 0,
+                KeyEvent::End | KeyEvent::Ctrl('e') => pos = line.len(),
+                KeyEvent::Ctrl('u') => {
+                    line.clear();
+                    pos = 0;
+                }
+                KeyEvent::Tab => match self.completion {
+                    Some(complete) => {
+                        if !self.complete(complete, prompt, &mut line, &mut pos) {
+                            continue;
+                        }
+                    }
+                    None => continue,
+                },
+                KeyEvent::Up if entry > 0 => {
+                    if entry == self.history.len() {
+                        draft = line.clone();
+                    }
+                    entry -= 1;
+                    line = self.history[entry].chars().collect();
+                    pos = line.len();
+                }
+                KeyEvent::Down if entry < self.history.len() => {
+                    entry += 1;
+                    line = match self.history.get(entry) {
+                        Some(previous) => previous.chars().collect(),
+                        None => core::mem::take(&mut draft),
+                    };
+                    pos = line.len();
+                }
+                _ => continue,
+            }
+            redraw(prompt, &line, pos);
+        }
+        crate::print("\r\n");
+        let line: String = line.into_iter().collect();
+        self.add_history(&line);
+        Some(line)
+    }
+
+    /// Set the function called on Tab to complete the text in front of the cursor
+    pub fn set_completion(&mut self, completion: Completion) {
+        self.completion.replace(completion);
+    }
+
+    /// Remove the completion function, Tab is ignored again
+    pub fn clear_completion(&mut self) {
+        self.completion.take();
+    }
+
+    /// complete the text in front of the cursor. Returns ``true`` if the line needs to be redrawn.
+    fn complete(
+        &self,
+        complete: Completion,
+        prompt: &str,
+        line: &mut Vec<char>,
+        pos: &mut usize,
+    ) -> bool {
+        let text: String = line[..*pos].iter().collect();
+        let candidates = complete(&text);
+        let mut prefix = match candidates.first() {
+            Some(first) => first.as_str(),
+            None => return false,
+        };
+        for candidate in &candidates[1..] {
+            let common = prefix
+                .char_indices()
+                .zip(candidate.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(prefix.len().min(candidate.len()), |((idx, _), _)| idx);
+            prefix = &prefix[..common];
+        }
+        if candidates.len() > 1 && prefix.chars().count() <= *pos {
+            // nothing to complete, list the candidates and start over below them
+            crate::print("\r\n");
+            for candidate in &candidates {
+                crate::print_args(format_args!("{}  ", candidate));
+            }
+            crate::print("\r\n");
+            crate::print(prompt);
+            return true;
+        }
+        let completed: Vec<char> = prefix.chars().collect();
+        line.splice(..*pos, completed.iter().copied());
+        *pos = completed.len();
+        true
+    }
+
+    /// Add a line to the history. Empty lines and repeats of the previous line are skipped, the oldest line is
+    /// dropped if the history is full.
+    pub fn add_history(&mut self, line: &str) {
+        if self.capacity == 0
+            || line.is_empty()
+            || self.history.back().is_some_and(|last| last == line)
+        {
+            return;
+        }
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(String::from(line));
+    }
+
+    /// The lines of the history from the oldest to the latest one
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        self.history.iter().map(|line| line.as_str())
+    }
+
+    /// Forget all lines of the history
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        LineEditor::new()
+    }
+}
+
+/// redraw the whole line and place the cursor
+fn redraw(prompt: &str, line: &[char], pos: usize) {
+    crate::print_args(format_args!("\r{}", prompt));
+    let mut buf = [0; 4];
+    for c in line {
+        crate::print(c.encode_utf8(&mut buf));
+    }
+    Erase::LineRight.print();
+    if pos < line.len() {
+        Cursor::Left(line.len() - pos).print();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConsoleImpl, ConsoleInput, CONSOLE_TEST_LOCK};
+    use alloc::sync::Arc;
+    use std::sync::{Mutex, PoisonError};
+
+    /// a terminal sending the given keys
+    struct Keys(Arc<Mutex<VecDeque<char>>>);
+
+    impl ConsoleImpl for Keys {
+        fn putc(&self, _: char) {}
+
+        fn puts(&self, _: &str) {}
+
+        fn input(&self) -> Option<&dyn ConsoleInput> {
+            Some(self)
+        }
+    }
+
+    impl ConsoleInput for Keys {
+        fn try_getc(&self) -> Option<char> {
+            self.0.lock().unwrap().pop_front()
+        }
+
+        fn getc(&self) -> Option<char> {
+            self.try_getc()
+        }
+    }
+
+    /// read the lines edited with the given keys
+    fn edit(editor: &mut LineEditor, keys: &str) -> Vec<String> {
+        let _lock = CONSOLE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let keys = Arc::new(Mutex::new(keys.chars().collect()));
+        crate::CONSOLE.take_for(|console| console.replace(Keys(keys)));
+        core::iter::from_fn(|| editor.read_line("> ")).collect()
+    }
+
+    #[test]
+    fn edits_the_line_at_the_cursor() {
+        let mut editor = LineEditor::new();
+        assert_eq!(edit(&mut editor, "ab\u{1b}[Dc\r"), ["acb"]);
+        assert_eq!(edit(&mut editor, "abc\u{1}\u{1b}[3~\u{1b}[C\u{7f}\r"), ["c"]);
+        assert_eq!(edit(&mut editor, "ab\u{15}cd\u{1b}[H!\u{1b}[F?\r"), ["!cd?"]);
+        // unknown sequences are ignored
+        assert_eq!(edit(&mut editor, "\u{1b}[5~x\u{1b}[2~y\r"), ["xy"]);
+    }
+
+    #[test]
+    fn recalls_lines_from_the_history() {
+        let mut editor = LineEditor::new();
+        assert_eq!(
+            edit(&mut editor, "one\rtwo\r\u{1b}[A\u{1b}[A!\rdraft\u{1b}[A\u{1b}[B\r"),
+            ["one", "two", "one!", "draft"]
+        );
+        assert!(editor.history().eq(["one", "two", "one!", "draft"]));
+    }
+
+    #[test]
+    fn keeps_a_bounded_history_without_repeats() {
+        let mut editor = LineEditor::with_history(2);
+        for line in &["one", "", "two", "two", "three"] {
+            editor.add_history(line);
+        }
+        assert!(editor.history().eq(["two", "three"]));
+        editor.clear_history();
+        assert_eq!(editor.history().count(), 0);
+        let mut editor = LineEditor::with_history(0);
+        editor.add_history("one");
+        assert_eq!(editor.history().count(), 0);
+    }
+
+    #[test]
+    fn completes_the_text_in_front_of_the_cursor() {
+        fn commands(text: &str) -> Vec<String> {
+            ["dump", "halt", "help"]
+                .iter()
+                .filter(|command| command.starts_with(text))
+                .map(|command| String::from(*command))
+                .collect()
+        }
+
+        let mut editor = LineEditor::new();
+        editor.set_completion(commands);
+        assert_eq!(edit(&mut editor, "d\t\rh\te\t\r"), ["dump", "help"]);
+        // the text after the cursor is kept
+        assert_eq!(edit(&mut editor, " x\u{1}du\t\r"), ["dump x"]);
+        editor.clear_completion();
+        assert_eq!(edit(&mut editor, "d\t\r"), ["d"]);
+    }
+}
@@ -0,0 +1,119 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Emergency output
+//!
+//! Printing from the ``alloc_error_handler`` or an exception handler must neither allocate memory nor rely on the
+//! log level, the filter or the formatter of the console. ``print_emergency`` passes a string straight to the
+//! current active console and all additional sinks and flushes them afterwards:
+//! ```ignore
+//! #[alloc_error_handler]
+//! fn alloc_error(layout: Layout) -> ! {
+//!     print_emergency_args(format_args!("out of memory allocating {} bytes\r\n", layout.size()));
+//!     loop {}
+//! }
+//! ```
+//! ``print_emergency_args`` formats the message into a static buffer of ``EMERGENCY_BUFFER_SIZE`` bytes first, so
+//! it reaches the output channel with a single ``puts`` without running any formatting code while the console is held.
+//! Messages that do not fit into the buffer are truncated. If the buffer is used by another core at the same time the
+//! message is streamed chunk by chunk instead. Other than regular output the emergency output takes over the console
+//! if another core does not release it in time and unmutes a muted console. It is never deferred, so it hits the
+//! wire even in interrupt context or queued mode.
+
+use crate::{Console, Locked, CONSOLE};
+use core::fmt::{self, Write};
+
+/// The size of the static buffer ``print_emergency_args`` formats the message into
+pub const EMERGENCY_BUFFER_SIZE: usize = 256;
+
+/// The static buffer used to format emergency messages
+static EMERGENCY_BUFFER: Locked<[u8; EMERGENCY_BUFFER_SIZE]> =
+    Locked::new([0; EMERGENCY_BUFFER_SIZE]);
+
+/// ``fmt::Write`` adapter filling a byte buffer and silently truncating everything that does not fit
+pub(crate) struct TruncatingWriter<'a> {
+    data: &'a mut [u8],
+    fill: usize,
+}
+
+impl<'a> TruncatingWriter<'a> {
+    pub(crate) fn new(data: &'a mut [u8]) -> Self {
+        TruncatingWriter { data, fill: 0 }
+    }
+
+    /// the number of bytes written to the buffer
+    pub(crate) fn len(&self) -> usize {
+        self.fill
+    }
+}
+
+impl fmt::Write for TruncatingWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let space = self.data.len() - self.fill;
+        let mut len = s.len().min(space);
+        // never split a character at the end of the buffer
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.data[self.fill..self.fill + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.fill += len;
+        Ok(())
+    }
+}
+
+/// ``fmt::Write`` adapter passing the output to the console without deferring it
+struct DirectWriter<'a>(&'a Console);
+
+impl fmt::Write for DirectWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.puts_direct(s);
+        Ok(())
+    }
+}
+
+/// Pass a string to the current active console and all additional sinks and flush them. This bypasses the log level,
+/// the filter and the formatter of the console and never allocates memory. A muted console is unmuted. The string
+/// is never deferred, so it is written even in interrupt context.
+pub fn print_emergency(s: &str) {
+    CONSOLE.use_for(|console| {
+        console.unmute();
+        console.exclusive_emergency(|| {
+            console.urgent(|| {
+                console.poll();
+                console.puts_direct(s);
+                console.flush_direct();
+            })
+        })
+    });
+}
+
+/// Format the message into a static buffer without allocating memory and pass it with ``print_emergency``. The
+/// message is truncated to ``EMERGENCY_BUFFER_SIZE`` bytes.
+pub fn print_emergency_args(args: fmt::Arguments) {
+    let buffered = EMERGENCY_BUFFER.try_with(|data| {
+        let mut writer = TruncatingWriter::new(data);
+        let _ = writer.write_fmt(args);
+        let fill = writer.len();
+        // the writer only copies complete characters, so the buffer always contains valid UTF-8
+        if let Ok(s) = core::str::from_utf8(&data[..fill]) {
+            print_emergency(s);
+        }
+    });
+    if buffered.is_none() {
+        // the buffer is in use, stream the message instead of waiting for it
+        CONSOLE.use_for(|console| {
+            console.unmute();
+            console.exclusive_emergency(|| {
+                console.urgent(|| {
+                    console.poll();
+                    let _ = fmt::write(&mut DirectWriter(console), args);
+                    console.flush_direct();
+                })
+            })
+        });
+    }
+}
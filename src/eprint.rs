@@ -0,0 +1,72 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Error output
+//!
+//! Like the split of stdout and stderr in the standard library the ``eprint!`` and ``eprintln!`` macros pass their
+//! output to a dedicated error console, e.g. a more reliable channel than the screen:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_error_console(uart));
+//! eprintln!("sd card not responding");
+//! ```
+//! Without an error console the output is passed to the current active console and all additional sinks like the
+//! output of ``print!``. Error output printed in interrupt context or queued mode is deferred to the regular output.
+
+use crate::{Console, ConsoleImpl, CONSOLE};
+use alloc::boxed::Box;
+use core::fmt;
+
+impl Console {
+    /// Set the dedicated console the output of ``eprint!`` and ``eprintln!`` is passed to. A previous error console
+    /// is flushed and closed. The Console takes ownership of the console.
+    pub fn set_error_console<T: ConsoleImpl + 'static>(&mut self, console: T) {
+        self.clear_error_console();
+        self.error.replace(Box::from(console));
+    }
+
+    /// Remove the dedicated error console. It is flushed and closed before it is dropped, the error output is passed
+    /// to the current active console and all additional sinks again.
+    pub fn clear_error_console(&mut self) {
+        if let Some(mut console) = self.error.take() {
+            console.flush();
+            console.close();
+        }
+    }
+
+    /// Pass a string to the error console. If there is none the string is passed to the current active console and
+    /// all additional sinks.
+    pub fn eputs(&self, s: &str) {
+        match self.error {
+            Some(ref console) if !self.defers_output() => {
+                self.guarded(|| self.translated(s, |part| console.puts(part)));
+            }
+            _ => self.puts(s),
+        }
+    }
+}
+
+/// The allocation free printing function hidden behind the eprint! and eprintln! macro. The formatted arguments are
+/// streamed chunk by chunk to the error console.
+pub fn eprint_args(args: fmt::Arguments) {
+    struct ErrorWriter;
+
+    impl fmt::Write for ErrorWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            CONSOLE.use_for(|console| console.eputs(s));
+            Ok(())
+        }
+    }
+
+    let has_error_console =
+        CONSOLE.use_for(|console| console.error.is_some() && !console.defers_output());
+    if has_error_console {
+        let _ = CONSOLE
+            .use_for(|console| console.exclusive_message(|| fmt::write(&mut ErrorWriter, args)));
+    } else {
+        crate::print_args(args);
+    }
+}
@@ -0,0 +1,39 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Console errors
+//!
+//! Errors reported by the fallible output functions like ``try_print``.
+
+use core::fmt;
+
+/// The errors an output channel may report while writing to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleError {
+    /// the output channel did not accept the data in time, e.g. the transmit FIFO stayed full
+    Timeout,
+    /// the output channel is no longer available, e.g. the device has been removed
+    Unavailable,
+    /// the console is already writing on the current core, e.g. the output channel itself printed while writing, or
+    /// the output in interrupt context could not be deferred
+    Busy,
+    /// any other failure the output channel is not able to specify any further
+    Failed,
+}
+
+impl fmt::Display for ConsoleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConsoleError::Timeout => write!(f, "console write timed out"),
+            ConsoleError::Unavailable => write!(f, "console output channel not available"),
+            ConsoleError::Busy => write!(f, "console busy on the current core"),
+            ConsoleError::Failed => write!(f, "console write failed"),
+        }
+    }
+}
+
+impl core::error::Error for ConsoleError {}
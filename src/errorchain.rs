@@ -0,0 +1,63 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Error source chain
+//!
+//! Driver errors are often layered, e.g. a file system error caused by a block device error caused by a timeout of
+//! the SD host controller. Printing only the top-level error hides the actual cause. ``ErrorChain`` formats an error
+//! with the whole chain of its ``source`` errors, each one indented a bit further:
+//! ```ignore
+//! error!("mount failed: {}", ErrorChain(&e));
+//! print_error(&e);
+//! ```
+//! prints
+//! ```text
+//! E: fs - mount failed: reading the boot sector failed
+//!   caused by: block 0 not readable
+//!     caused by: console write timed out
+//! ```
+
+use core::error::Error;
+use core::fmt;
+
+/// Format an error with the chain of its ``source`` errors
+pub struct ErrorChain<'a>(pub &'a dyn Error);
+
+impl<'a> ErrorChain<'a> {
+    /// The ``source`` errors behind the error, starting with the direct one
+    pub fn sources(&self) -> impl Iterator<Item = &'a (dyn Error + 'static)> {
+        core::iter::successors(self.0.source(), |&error: &&'a (dyn Error + 'static)| {
+            error.source()
+        })
+    }
+
+    /// The number of ``source`` errors behind the error
+    pub fn depth(&self) -> usize {
+        self.sources().count()
+    }
+}
+
+impl fmt::Display for ErrorChain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        for (depth, source) in self.sources().enumerate() {
+            write!(
+                f,
+                "\r\n{:indent$}caused by: {}",
+                "",
+                source,
+                indent = (depth + 1) * 2
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Print an error with the chain of its ``source`` errors like ``eprintln!`` does
+pub fn print_error(error: &dyn Error) {
+    crate::eprint_args(format_args!("{}\r\n", ErrorChain(error)));
+}
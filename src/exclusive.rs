@@ -0,0 +1,567 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Message atomicity
+//!
+//! Two cores calling ``println!`` at the same time would interleave their output in the middle of a line. The
+//! console therefore serializes the output of the cores: every ``print!`` invocation, every log record and every
+//! string passed to the console reaches the output channels contiguously while the other cores wait for it. Output of
+//! the core already holding the console, e.g. an interrupt handler without an interrupt detector, is passed through
+//! immediately instead of waiting for itself.
+//!
+//! The cores are identified with the core id provider of the console. Without a provider the console could not tell
+//! whether it is held by the current or by another core. Every string is still serialized, but the pieces of a
+//! formatted message are passed one by one, so the messages of the cores could interleave between them. Nested output
+//! waits a bounded time for the console before it is passed through. Emergency output waits a bounded time only and
+//! takes over the console if the other core does not release it, e.g. because it halted while printing.
+//!
+//! The right lock depends on the kernel. The ``SpinLock`` is used if no other has been set. A single core kernel
+//! does not need any lock and uses the ``NoLock``, a kernel printing from interrupt handlers masks the interrupts
+//! while holding the console with the ``IrqSafeLock``. Any other primitive, like a lock of ``ruspiro-lock``, could be
+//! used by implementing the ``ConsoleLock`` trait:
+//! ```ignore
+//! struct Daif;
+//!
+//! impl InterruptMask for Daif {
+//!     fn disable(&self) -> bool {
+//!         let enabled = irqs_enabled();
+//!         disable_irqs();
+//!         enabled
+//!     }
+//!
+//!     fn restore(&self, enabled: bool) {
+//!         if enabled {
+//!             enable_irqs();
+//!         }
+//!     }
+//! }
+//!
+//! CONSOLE.take_for(|cons| cons.set_console_lock(IrqSafeLock::new(Daif)));
+//! ```
+
+use crate::Console;
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// The number of attempts emergency output waits for another core to release the console before it takes over
+const EMERGENCY_SPINS: usize = 1_000_000;
+
+/// The number of attempts output of an unknown core waits for the console before it is treated as nested output
+const NESTED_SPINS: usize = 10_000_000;
+
+/// Every lock serializing the output of the cores need to implement this trait
+pub trait ConsoleLock {
+    /// acquire the lock without waiting. Returns ``false`` if it is held by another core
+    fn try_lock(&self) -> bool;
+    /// release the lock acquired with ``try_lock`` or ``lock``
+    fn unlock(&self);
+    /// acquire the lock and wait for it as long as it takes. The default implementation spins on ``try_lock``
+    fn lock(&self) {
+        while !self.try_lock() {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// A plain spin lock, sufficient for multi-core kernels that do not print from interrupt handlers
+pub struct SpinLock {
+    locked: AtomicBool,
+}
+
+impl SpinLock {
+    /// Create a new released spin lock
+    pub const fn new() -> Self {
+        SpinLock {
+            locked: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Default for SpinLock {
+    fn default() -> Self {
+        SpinLock::new()
+    }
+}
+
+impl ConsoleLock for SpinLock {
+    fn try_lock(&self) -> bool {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    fn unlock(&self) {
+        self.locked.store(false, Ordering::Release);
+    }
+}
+
+/// No lock at all for single core kernels
+pub struct NoLock;
+
+impl ConsoleLock for NoLock {
+    fn try_lock(&self) -> bool {
+        true
+    }
+
+    fn unlock(&self) {}
+}
+
+/// A value shared between the cores that is only accessed while holding its ``SpinLock``
+pub(crate) struct Locked<T> {
+    value: UnsafeCell<T>,
+    lock: SpinLock,
+}
+
+// safe as the access to the value is guarded by the lock
+unsafe impl<T: Send> Sync for Locked<T> {}
+
+impl<T> Locked<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Locked {
+            value: UnsafeCell::new(value),
+            lock: SpinLock::new(),
+        }
+    }
+
+    /// hand back the value
+    pub(crate) fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /// access the value without locking, the exclusive borrow ensures no other core accesses it
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    /// run the given function with exclusive access to the value, waiting for the lock as long as it takes
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.lock.lock();
+        self.locked(f)
+    }
+
+    /// run the given function with exclusive access to the value if the lock is not held by someone else. Returns
+    /// ``None`` without waiting otherwise.
+    pub(crate) fn try_with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        if self.lock.try_lock() {
+            Some(self.locked(f))
+        } else {
+            None
+        }
+    }
+
+    /// run the given function with the lock acquired and release it afterwards, even if the function unwinds
+    fn locked<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        struct Unlock<'a>(&'a SpinLock);
+
+        impl Drop for Unlock<'_> {
+            fn drop(&mut self) {
+                self.0.unlock();
+            }
+        }
+
+        let _unlock = Unlock(&self.lock);
+        // safe as the lock ensures exclusive access to the value
+        f(unsafe { &mut *self.value.get() })
+    }
+}
+
+/// Masking the interrupts of the current core is specific to the architecture, the ``IrqSafeLock`` requires an
+/// implementation of this trait
+pub trait InterruptMask {
+    /// disable the interrupts of the current core. Returns whether they have been enabled before
+    fn disable(&self) -> bool;
+    /// restore the interrupt state of the current core returned by ``disable``
+    fn restore(&self, enabled: bool);
+}
+
+/// A spin lock masking the interrupts of the current core while it is held, so an interrupt handler printing on the
+/// same core never waits for the output it interrupted. The interrupts are enabled while waiting for the lock.
+pub struct IrqSafeLock<M: InterruptMask> {
+    mask: M,
+    lock: SpinLock,
+    /// the interrupt state of the core holding the lock before it has been acquired
+    enabled: AtomicBool,
+}
+
+impl<M: InterruptMask> IrqSafeLock<M> {
+    /// Create a new released lock masking the interrupts with the given mask
+    pub const fn new(mask: M) -> Self {
+        IrqSafeLock {
+            mask,
+            lock: SpinLock::new(),
+            enabled: AtomicBool::new(false),
+        }
+    }
+}
+
+impl<M: InterruptMask> ConsoleLock for IrqSafeLock<M> {
+    fn try_lock(&self) -> bool {
+        let enabled = self.mask.disable();
+        if self.lock.try_lock() {
+            self.enabled.store(enabled, Ordering::Relaxed);
+            true
+        } else {
+            self.mask.restore(enabled);
+            false
+        }
+    }
+
+    fn unlock(&self) {
+        let enabled = self.enabled.load(Ordering::Relaxed);
+        self.lock.unlock();
+        self.mask.restore(enabled);
+    }
+}
+
+/// How the output lock has been acquired
+#[derive(Clone, Copy)]
+enum Acquired {
+    /// the core already held the lock
+    Nested,
+    /// the lock has been acquired and needs to be released
+    Locked,
+    /// the lock held by another core has been taken over by emergency output
+    TakenOver,
+}
+
+/// The core currently passing output to the channels
+pub(crate) struct OutputLock {
+    /// the core id plus one of the owner, zero if the lock is free or the owner is unknown
+    owner: AtomicUsize,
+    /// the lock used if no other has been set
+    spin: SpinLock,
+}
+
+impl OutputLock {
+    pub(crate) const fn new() -> Self {
+        OutputLock {
+            owner: AtomicUsize::new(0),
+            spin: SpinLock::new(),
+        }
+    }
+
+    /// Acquire the lock for the given owner unless it holds it already. With a limit of spins the lock is taken over
+    /// once the spins are used up. An unknown owner, zero, might hold the lock already, so it waits a bounded time
+    /// only before the output is treated as nested.
+    fn lock(&self, lock: &dyn ConsoleLock, owner: usize, spins: Option<usize>) -> Acquired {
+        if owner != 0 && self.owner.load(Ordering::Acquire) == owner {
+            return Acquired::Nested;
+        }
+        let acquired = match spins {
+            None if owner != 0 => {
+                lock.lock();
+                Acquired::Locked
+            }
+            None if Self::try_lock(lock, NESTED_SPINS) => Acquired::Locked,
+            None => return Acquired::Nested,
+            Some(spins) if Self::try_lock(lock, spins) => Acquired::Locked,
+            Some(_) => Acquired::TakenOver,
+        };
+        self.owner.store(owner, Ordering::Release);
+        acquired
+    }
+
+    /// try to acquire the lock with the given number of attempts
+    fn try_lock(lock: &dyn ConsoleLock, spins: usize) -> bool {
+        for _ in 0..spins {
+            if lock.try_lock() {
+                return true;
+            }
+            core::hint::spin_loop();
+        }
+        false
+    }
+
+    /// Release the lock acquired for the given owner
+    fn unlock(&self, lock: &dyn ConsoleLock, owner: usize, acquired: Acquired) {
+        if let Acquired::Nested = acquired {
+            return;
+        }
+        // the owner only changes if the lock has been taken over meanwhile
+        let _ = self
+            .owner
+            .compare_exchange(owner, 0, Ordering::Release, Ordering::Relaxed);
+        if let Acquired::Locked = acquired {
+            lock.unlock();
+        }
+    }
+}
+
+impl Console {
+    /// Set the lock serializing the output of the cores, e.g. an ``IrqSafeLock`` for kernels printing from interrupt
+    /// handlers. The Console takes ownership of the lock.
+    pub fn set_console_lock<T: ConsoleLock + 'static>(&mut self, lock: T) {
+        self.lock.replace(Box::from(lock));
+    }
+
+    /// Remove the lock. The output of the cores is serialized with a ``SpinLock`` again.
+    pub fn clear_console_lock(&mut self) {
+        self.lock = None;
+    }
+
+    /// Run the given output function while the other cores wait for the console
+    pub(crate) fn exclusive<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.exclusive_within(None, f)
+    }
+
+    /// Run the given function passing a message in several pieces while the other cores wait for the console.
+    /// Without a core id provider the pieces are serialized one by one, as waiting for the console while holding it
+    /// could not be detected.
+    pub(crate) fn exclusive_message<R>(&self, f: impl FnOnce() -> R) -> R {
+        if self.core_id().is_some() {
+            self.exclusive(f)
+        } else {
+            f()
+        }
+    }
+
+    /// Run the given emergency output function while the other cores wait for the console. If another core does not
+    /// release the console in time it is taken over. Without a core id provider the pieces of the output are
+    /// serialized one by one like with ``exclusive_message`` and each waits a bounded time only.
+    pub(crate) fn exclusive_emergency<R>(&self, f: impl FnOnce() -> R) -> R {
+        if self.core_id().is_some() {
+            self.exclusive_within(Some(EMERGENCY_SPINS), f)
+        } else {
+            f()
+        }
+    }
+
+    fn exclusive_within<R>(&self, spins: Option<usize>, f: impl FnOnce() -> R) -> R {
+        /// releases the lock even if the output function unwinds
+        struct Unlock<'a> {
+            console: &'a Console,
+            owner: usize,
+            acquired: Acquired,
+        }
+
+        impl Drop for Unlock<'_> {
+            fn drop(&mut self) {
+                let console = self.console;
+                console
+                    .output_lock
+                    .unlock(console.console_lock(), self.owner, self.acquired);
+            }
+        }
+
+        let owner = self.lock_owner();
+        let _unlock = Unlock {
+            console: self,
+            owner,
+            acquired: self.output_lock.lock(self.console_lock(), owner, spins),
+        };
+        f()
+    }
+
+    /// Let the other cores wait for the console until ``unlock_output`` is called, e.g. to pass a message in several
+    /// chunks. Returns ``false`` if the lock must not be released, as the current core holds it already, the output
+    /// is deferred anyway or the cores could not be told apart without a core id provider.
+    pub(crate) fn lock_output(&self) -> bool {
+        if self.defers_output() || self.core_id().is_none() {
+            return false;
+        }
+        let acquired = self
+            .output_lock
+            .lock(self.console_lock(), self.lock_owner(), None);
+        matches!(acquired, Acquired::Locked)
+    }
+
+    /// release the console locked with ``lock_output``
+    pub(crate) fn unlock_output(&self) {
+        self.output_lock
+            .unlock(self.console_lock(), self.lock_owner(), Acquired::Locked);
+    }
+
+    fn console_lock(&self) -> &dyn ConsoleLock {
+        match self.lock {
+            Some(ref lock) => lock.as_ref(),
+            None => &self.output_lock.spin,
+        }
+    }
+
+    /// the core id plus one of the current core, zero if it is unknown
+    fn lock_owner(&self) -> usize {
+        self.core_id().map_or(0, |id| id.wrapping_add(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CONSOLE, CONSOLE_TEST_LOCK};
+    use alloc::{string::String, sync::Arc};
+    use std::sync::{Mutex, PoisonError};
+
+    #[test]
+    fn spin_lock_is_held_until_released() {
+        let lock = SpinLock::new();
+        assert!(lock.try_lock());
+        assert!(!lock.try_lock());
+        lock.unlock();
+        assert!(lock.try_lock());
+    }
+
+    #[test]
+    fn nested_output_passes_through() {
+        let output = OutputLock::new();
+        let lock = SpinLock::new();
+        let outer = output.lock(&lock, 1, None);
+        assert!(matches!(outer, Acquired::Locked));
+        assert!(matches!(output.lock(&lock, 1, None), Acquired::Nested));
+        output.unlock(&lock, 1, outer);
+        assert_eq!(output.owner.load(Ordering::Relaxed), 0);
+        assert!(lock.try_lock());
+    }
+
+    #[test]
+    fn emergency_output_takes_over() {
+        let output = OutputLock::new();
+        let lock = SpinLock::new();
+        let held = output.lock(&lock, 1, None);
+        let emergency = output.lock(&lock, 2, Some(10));
+        assert!(matches!(emergency, Acquired::TakenOver));
+        assert_eq!(output.owner.load(Ordering::Relaxed), 2);
+        output.unlock(&lock, 2, emergency);
+        // the lock is still held by the core that has been taken over
+        assert!(!lock.try_lock());
+        output.unlock(&lock, 1, held);
+        assert!(lock.try_lock());
+    }
+
+    #[test]
+    fn irq_safe_lock_restores_the_interrupts() {
+        struct Mask(AtomicBool);
+
+        impl InterruptMask for Mask {
+            fn disable(&self) -> bool {
+                self.0.swap(false, Ordering::Relaxed)
+            }
+
+            fn restore(&self, enabled: bool) {
+                self.0.store(enabled, Ordering::Relaxed);
+            }
+        }
+
+        let lock = IrqSafeLock::new(Mask(AtomicBool::new(true)));
+        assert!(lock.try_lock());
+        assert!(!lock.mask.0.load(Ordering::Relaxed));
+        // a failed attempt keeps the interrupts masked for the holder
+        assert!(!lock.try_lock());
+        assert!(!lock.mask.0.load(Ordering::Relaxed));
+        lock.unlock();
+        assert!(lock.mask.0.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn output_of_cores_does_not_interleave() {
+        let output = Arc::new(OutputLock::new());
+        let text = Arc::new(Mutex::new(String::new()));
+        let cores: alloc::vec::Vec<_> = ['a', 'b', 'c']
+            .iter()
+            .enumerate()
+            .map(|(core, &c)| {
+                let (output, text) = (output.clone(), text.clone());
+                std::thread::spawn(move || {
+                    let lock = &output.spin;
+                    for _ in 0..50 {
+                        let acquired = output.lock(lock, core + 1, None);
+                        for _ in 0..8 {
+                            text.lock().unwrap().push(c);
+                            std::thread::yield_now();
+                        }
+                        output.unlock(lock, core + 1, acquired);
+                    }
+                })
+            })
+            .collect();
+        for core in cores {
+            core.join().unwrap();
+        }
+        let text = text.lock().unwrap();
+        assert_eq!(text.len(), 3 * 50 * 8);
+        for line in text.as_bytes().chunks(8) {
+            assert!(line.iter().all(|&c| c == line[0]));
+        }
+    }
+
+    #[test]
+    fn locked_value_is_not_accessible_while_held() {
+        let value = Locked::new(1);
+        value.with(|outer| {
+            *outer += 1;
+            assert_eq!(value.try_with(|inner| *inner), None);
+        });
+        assert_eq!(value.try_with(|inner| *inner), Some(2));
+    }
+
+    #[test]
+    fn locked_value_is_released_on_unwind() {
+        let value = Locked::new(0);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            value.with(|_| panic!("printing failed"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(value.try_with(|inner| *inner), Some(0));
+    }
+
+    #[test]
+    fn unknown_cores_wait_a_bounded_time_only() {
+        let output = OutputLock::new();
+        let lock = SpinLock::new();
+        let outer = output.lock(&lock, 0, None);
+        assert!(matches!(outer, Acquired::Locked));
+        // the lock might be held by the current core, so the output is passed through after waiting for it
+        assert!(matches!(output.lock(&lock, 0, None), Acquired::Nested));
+        output.unlock(&lock, 0, outer);
+        assert!(lock.try_lock());
+    }
+
+    #[test]
+    fn output_of_unknown_cores_does_not_interleave() {
+        let output = Arc::new(OutputLock::new());
+        let text = Arc::new(Mutex::new(String::new()));
+        let cores: alloc::vec::Vec<_> = ['a', 'b', 'c']
+            .iter()
+            .map(|&c| {
+                let (output, text) = (output.clone(), text.clone());
+                std::thread::spawn(move || {
+                    let lock = &output.spin;
+                    for _ in 0..50 {
+                        let acquired = output.lock(lock, 0, None);
+                        assert!(matches!(acquired, Acquired::Locked));
+                        for _ in 0..8 {
+                            text.lock().unwrap().push(c);
+                        }
+                        output.unlock(lock, 0, acquired);
+                    }
+                })
+            })
+            .collect();
+        for core in cores {
+            core.join().unwrap();
+        }
+        let text = text.lock().unwrap();
+        for line in text.as_bytes().chunks(8) {
+            assert!(line.iter().all(|&c| c == line[0]));
+        }
+    }
+
+    #[test]
+    fn console_is_released_on_unwind() {
+        let _lock = CONSOLE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        CONSOLE.use_for(|console| {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                console.exclusive(|| panic!("printing failed"))
+            }));
+            assert!(result.is_err());
+            assert!(console.console_lock().try_lock());
+            console.console_lock().unlock();
+        });
+    }
+}
@@ -0,0 +1,135 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Target filter
+//!
+//! Enable the output of specific targets (usually module paths) with their own severity. The filter is given as a
+//! comma separated list of ``target=level`` directives. A level without a target sets the default for all other
+//! targets. The level ``off`` disables a target completely.
+//! ```ignore
+//! let filter = Filter::parse("uart=debug,sdcard=warn,info").unwrap();
+//! CONSOLE.take_for(|cons| cons.set_filter(filter));
+//! ```
+//! If several directives match a target the most specific (longest) one is used. Targets not matching any directive
+//! and not covered by a default level use the log level of the console.
+
+use crate::{Console, Level, ParseLevelError};
+use alloc::{string::String, vec::Vec};
+
+/// A parsed target filter specification
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    default: Option<Option<Level>>,
+    directives: Vec<(String, Option<Level>)>,
+}
+
+impl Filter {
+    /// Parse a filter specification like ``"uart=debug,sdcard=warn,info"``
+    pub fn parse(spec: &str) -> Result<Self, ParseLevelError> {
+        let mut filter = Filter::default();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let mut parts = directive.splitn(2, '=');
+            let first = parts.next().unwrap_or("").trim();
+            match parts.next() {
+                Some(level) => {
+                    let level = parse_level(level.trim())?;
+                    filter.directives.push((String::from(first), level));
+                }
+                None => match parse_level(first) {
+                    Ok(level) => filter.default = Some(level),
+                    // a single name that is no level enables everything of this target
+                    Err(_) => filter
+                        .directives
+                        .push((String::from(first), Some(Level::Trace))),
+                },
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Check the given severity and target against this filter. Returns ``None`` if neither a directive matches
+    /// the target nor a default level is set.
+    pub fn enabled(&self, level: Level, target: &str) -> Option<bool> {
+        let matched = self
+            .directives
+            .iter()
+            .filter(|(name, _)| target.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, level)| *level)
+            .or(self.default)?;
+
+        Some(match matched {
+            Some(max) => level <= max,
+            None => false,
+        })
+    }
+}
+
+/// parse a level name of a directive, where ``off`` disables the target
+fn parse_level(s: &str) -> Result<Option<Level>, ParseLevelError> {
+    if s.eq_ignore_ascii_case("off") {
+        Ok(None)
+    } else {
+        s.parse().map(Some)
+    }
+}
+
+impl Console {
+    /// Set the target filter of the console. Messages are checked against this filter before they are formatted.
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = Some(filter);
+    }
+
+    /// Remove the target filter. Only the log level of the console applies again.
+    pub fn clear_filter(&mut self) {
+        self.filter = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_directives_and_default() {
+        let filter = Filter::parse("uart=debug, sdcard=warn ,info").unwrap();
+        assert_eq!(filter.enabled(Level::Debug, "uart"), Some(true));
+        assert_eq!(filter.enabled(Level::Trace, "uart"), Some(false));
+        assert_eq!(filter.enabled(Level::Info, "sdcard"), Some(false));
+        assert_eq!(filter.enabled(Level::Warn, "sdcard"), Some(true));
+        assert_eq!(filter.enabled(Level::Info, "kernel"), Some(true));
+        assert_eq!(filter.enabled(Level::Debug, "kernel"), Some(false));
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let filter = Filter::parse("kernel=warn,kernel::mmu=trace").unwrap();
+        assert_eq!(filter.enabled(Level::Trace, "kernel::mmu"), Some(true));
+        assert_eq!(filter.enabled(Level::Info, "kernel::irq"), Some(false));
+    }
+
+    #[test]
+    fn off_and_bare_targets() {
+        let filter = Filter::parse("noisy=off,sdhci").unwrap();
+        assert_eq!(filter.enabled(Level::Error, "noisy"), Some(false));
+        assert_eq!(filter.enabled(Level::Trace, "sdhci"), Some(true));
+        assert_eq!(filter.enabled(Level::Error, "uart"), None);
+        assert_eq!(
+            Filter::parse("off").unwrap().enabled(Level::Error, "uart"),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_levels() {
+        assert!(Filter::parse("uart=loud").is_err());
+        assert!(Filter::parse("")
+            .unwrap()
+            .enabled(Level::Info, "uart")
+            .is_none());
+    }
+}
@@ -0,0 +1,293 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Software flow control
+//!
+//! Slow terminal emulators and capture scripts ask the sender to pause with XOFF (Ctrl-S) and to resume with XON
+//! (Ctrl-Q). Once enabled the console checks the input channel for those characters before any output and waits
+//! while the output is paused:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_flow_control(true));
+//! ```
+//! XON and XOFF are not passed to the readers of the console. Other characters received while checking for them are
+//! kept until they are read, up to ``FLOW_INPUT_SIZE`` characters. Further characters are dropped and counted, see
+//! ``Console::flow_input_dropped``. If XON does not arrive within ``FLOW_PAUSE_SPINS`` checks of the input the output
+//! resumes anyway, so a lost XON does not stall the console forever. Emergency output like ``print_emergency``, ``fatal!`` and panics does not wait for XON at all.
+
+use crate::Console;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+/// The character resuming the output
+pub const XON: char = '\u{11}';
+
+/// The character pausing the output
+pub const XOFF: char = '\u{13}';
+
+/// The number of received characters kept while checking the input for XON and XOFF
+pub const FLOW_INPUT_SIZE: usize = 32;
+
+/// The number of attempts paused output checks the input for XON before it resumes without it
+pub const FLOW_PAUSE_SPINS: usize = 10_000_000;
+
+/// The state of the software flow control
+pub(crate) struct FlowControl {
+    enabled: AtomicBool,
+    paused: AtomicBool,
+    /// the number of emergency outputs in progress, which do not wait for XON
+    urgent: AtomicUsize,
+    /// the characters received while checking for XON and XOFF
+    pending: [AtomicU32; FLOW_INPUT_SIZE],
+    /// the total number of characters ever kept
+    head: AtomicUsize,
+    /// the total number of characters ever read
+    tail: AtomicUsize,
+    /// the number of characters dropped as there was no space left
+    dropped: AtomicUsize,
+}
+
+impl FlowControl {
+    pub(crate) const fn new() -> Self {
+        // only used to initialize the pending characters, each use creates a new one
+        #[allow(clippy::declare_interior_mutable_const)]
+        const EMPTY: AtomicU32 = AtomicU32::new(0);
+        FlowControl {
+            enabled: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            urgent: AtomicUsize::new(0),
+            pending: [EMPTY; FLOW_INPUT_SIZE],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// update the state for XON and XOFF. Returns the character if it is none of them.
+    fn filter(&self, c: char) -> Option<char> {
+        if !self.enabled.load(Ordering::Acquire) {
+            return Some(c);
+        }
+        match c {
+            XON => self.paused.store(false, Ordering::Release),
+            XOFF => self.paused.store(true, Ordering::Release),
+            c => return Some(c),
+        }
+        None
+    }
+
+    /// keep a received character for the readers, it is dropped if there is no space left
+    fn keep(&self, c: char) {
+        let head = self.head.load(Ordering::Acquire);
+        if head - self.tail.load(Ordering::Acquire) < FLOW_INPUT_SIZE {
+            self.pending[head % FLOW_INPUT_SIZE].store(c as u32, Ordering::Release);
+            self.head.store(head + 1, Ordering::Release);
+        } else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// take the oldest character kept for the readers
+    fn take(&self) -> Option<char> {
+        loop {
+            let tail = self.tail.load(Ordering::Acquire);
+            if tail == self.head.load(Ordering::Acquire) {
+                return None;
+            }
+            let c = self.pending[tail % FLOW_INPUT_SIZE].load(Ordering::Acquire);
+            if self
+                .tail
+                .compare_exchange(tail, tail + 1, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return char::from_u32(c);
+            }
+        }
+    }
+}
+
+impl Console {
+    /// Enable or disable the software flow control. Disabling it resumes paused output.
+    pub fn set_flow_control(&mut self, enable: bool) {
+        self.flow.enabled.store(enable, Ordering::Release);
+        if !enable {
+            self.flow.paused.store(false, Ordering::Release);
+        }
+    }
+
+    /// Check if the software flow control is enabled
+    pub fn flow_control_enabled(&self) -> bool {
+        self.flow.enabled.load(Ordering::Acquire)
+    }
+
+    /// Check if the terminal paused the output with XOFF
+    pub fn is_paused(&self) -> bool {
+        self.flow.paused.load(Ordering::Acquire)
+    }
+
+    /// The number of received characters dropped while checking the input for XON and XOFF, because
+    /// ``FLOW_INPUT_SIZE`` characters were already waiting to be read
+    pub fn flow_input_dropped(&self) -> usize {
+        self.flow.dropped.load(Ordering::Relaxed)
+    }
+
+    /// run the given emergency output function without waiting for XON, as the output might be the last one
+    pub(crate) fn urgent<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.flow.urgent.fetch_add(1, Ordering::AcqRel);
+        let result = f();
+        self.flow.urgent.fetch_sub(1, Ordering::AcqRel);
+        result
+    }
+
+    /// check the input channel for XON and XOFF and wait as long as the output is paused, but at most
+    /// ``FLOW_PAUSE_SPINS`` checks. Emergency output does not wait.
+    pub(crate) fn wait_for_xon(&self) {
+        if !self.flow_control_enabled() {
+            return;
+        }
+        let input = match self.get_current().input() {
+            Some(input) => input,
+            None => return,
+        };
+        let mut spins = 0;
+        loop {
+            match input.try_getc() {
+                Some(c) => {
+                    if let Some(c) = self.flow.filter(c) {
+                        self.flow.keep(self.check_break(c));
+                    }
+                }
+                None if !self.is_paused() || self.flow.urgent.load(Ordering::Acquire) > 0 => break,
+                None if spins < FLOW_PAUSE_SPINS => {
+                    spins += 1;
+                    core::hint::spin_loop();
+                }
+                None => {
+                    // the XON got lost, resume instead of stalling the console forever
+                    self.flow.paused.store(false, Ordering::Release);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// receive the next character, either one kept while checking for XON and XOFF or a new one from the input
+    /// channel of the current active console
+    pub(crate) fn receive(&self, blocking: bool) -> Option<char> {
+        if let Some(c) = self.flow.take() {
+            return Some(c);
+        }
+        let input = self.get_current().input()?;
+        loop {
+            let c = if blocking {
+                input.getc()?
+            } else {
+                input.try_getc()?
+            };
+            if let Some(c) = self.flow.filter(c) {
+                return Some(self.check_break(c));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{print_emergency, ConsoleImpl, ConsoleInput, CONSOLE, CONSOLE_TEST_LOCK};
+    use alloc::{collections::VecDeque, format, string::String, sync::Arc};
+    use std::sync::{Mutex, PoisonError};
+
+    /// a terminal sending the given characters and collecting the output
+    #[derive(Clone, Default)]
+    struct Terminal {
+        sent: Arc<Mutex<VecDeque<char>>>,
+        output: Arc<Mutex<String>>,
+    }
+
+    impl ConsoleImpl for Terminal {
+        fn putc(&self, c: char) {
+            self.output.lock().unwrap().push(c);
+        }
+
+        fn puts(&self, s: &str) {
+            self.output.lock().unwrap().push_str(s);
+        }
+
+        fn input(&self) -> Option<&dyn ConsoleInput> {
+            Some(self)
+        }
+    }
+
+    impl ConsoleInput for Terminal {
+        fn try_getc(&self) -> Option<char> {
+            self.sent.lock().unwrap().pop_front()
+        }
+
+        fn getc(&self) -> Option<char> {
+            self.try_getc()
+        }
+    }
+
+    /// run the given function with flow control enabled on the terminal that sent the given characters
+    fn with_terminal(sent: &str, f: impl FnOnce(&Terminal)) {
+        let _lock = CONSOLE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let terminal = Terminal::default();
+        terminal.sent.lock().unwrap().extend(sent.chars());
+        CONSOLE.take_for(|console| {
+            console.replace(terminal.clone());
+            console.set_flow_control(true);
+        });
+        f(&terminal);
+        CONSOLE.take_for(|console| console.set_flow_control(false));
+    }
+
+    #[test]
+    fn emergency_output_does_not_wait_for_xon() {
+        with_terminal("\u{13}", |terminal| {
+            print_emergency("halted");
+            assert!(CONSOLE.use_for(|console| console.is_paused()));
+            assert_eq!(terminal.output.lock().unwrap().as_str(), "halted");
+        });
+    }
+
+    #[test]
+    fn paused_output_resumes_without_xon() {
+        with_terminal("\u{13}", |terminal| {
+            CONSOLE.use_for(|console| console.puts("late"));
+            assert!(!CONSOLE.use_for(|console| console.is_paused()));
+            assert_eq!(terminal.output.lock().unwrap().as_str(), "late");
+        });
+    }
+
+    #[test]
+    fn keeps_the_input_received_while_paused() {
+        with_terminal("\u{13}a\u{11}", |terminal| {
+            CONSOLE.use_for(|console| console.puts("x"));
+            assert_eq!(
+                CONSOLE.use_for(|console| console.try_read_char()),
+                Some('a')
+            );
+            assert_eq!(terminal.output.lock().unwrap().as_str(), "x");
+        });
+    }
+
+    #[test]
+    fn counts_the_input_dropped_while_paused() {
+        let sent = format!("{}{}{}", XOFF, "a".repeat(FLOW_INPUT_SIZE + 8), XON);
+        with_terminal(&sent, |_| {
+            let dropped = CONSOLE.use_for(|console| console.flow_input_dropped());
+            CONSOLE.use_for(|console| console.puts("x"));
+            assert_eq!(
+                CONSOLE.use_for(|console| console.flow_input_dropped()),
+                dropped + 8
+            );
+            let kept = core::iter::from_fn(|| CONSOLE.use_for(|console| console.try_read_char()));
+            assert_eq!(kept.count(), FLOW_INPUT_SIZE);
+        });
+    }
+}
@@ -0,0 +1,110 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Log record formatting
+//!
+//! Before a log record is written to an output channel that only handles text it is passed to the ``LogFormatter``
+//! of the console. The ``TextFormatter`` is used if no other has been set. To customize the prefixes, their order
+//! or the layout as a whole provide a structure implementing the ``LogFormatter`` trait:
+//! ```ignore
+//! struct Plain;
+//!
+//! impl LogFormatter for Plain {
+//!     fn format(&self, _: &Console, record: &LogRecord, w: &mut dyn fmt::Write) -> fmt::Result {
+//!         write!(w, "{} {}\r\n", record.level(), record.args())
+//!     }
+//! }
+//!
+//! CONSOLE.take_for(|cons| cons.set_formatter(Plain));
+//! ```
+
+use crate::{Console, LogRecord};
+use alloc::boxed::Box;
+use core::fmt;
+
+/// Every formatter of log records need to implement this trait
+pub trait LogFormatter {
+    /// format the given record into the writer. The console is passed to consult its settings, like the color.
+    fn format(&self, console: &Console, record: &LogRecord, w: &mut dyn fmt::Write) -> fmt::Result;
+}
+
+/// The default formatter producing human readable text like ``I: kernel - mapped region len=4096``. The message is
+/// prefixed with the sequence number if enabled, the timestamp and the core id if the record carries them, followed
+/// by the (colorized) severity prefix and the target. If enabled the source location follows the target. The fields
+/// are appended to the message.
+pub struct TextFormatter;
+
+impl LogFormatter for TextFormatter {
+    fn format(&self, console: &Console, record: &LogRecord, w: &mut dyn fmt::Write) -> fmt::Result {
+        if let (true, Some(sequence)) = (console.sequence_enabled(), record.sequence()) {
+            write!(w, "#{} ", sequence)?;
+        }
+        if let Some(timestamp) = record.timestamp() {
+            write!(
+                w,
+                "[{:>5}.{:06}] ",
+                timestamp.as_secs(),
+                timestamp.subsec_micros()
+            )?;
+        }
+        if let Some(core) = record.core_id() {
+            write!(w, "[core {}] ", core)?;
+        }
+        match record.level().color() {
+            Some(color) if console.color_enabled() => {
+                write!(w, "{}{}\u{1b}[0m", color, record.level().prefix())?
+            }
+            _ => w.write_str(record.level().prefix())?,
+        }
+        write!(w, ": {}", record.target())?;
+        if let (true, Some(file), Some(line)) =
+            (console.location_enabled(), record.file(), record.line())
+        {
+            write!(w, " ({}:{})", file, line)?;
+        }
+        write!(w, " - {}", record.args())?;
+        for field in record.fields() {
+            write!(w, " {}={}", field.key(), field.value())?;
+        }
+        w.write_str("\r\n")
+    }
+}
+
+impl Console {
+    /// Enable or disable the source location in the formatted log records. A record only carries its source
+    /// location if the ``location`` feature is enabled, in this case the output of the location is enabled by
+    /// default.
+    pub fn set_location(&mut self, enable: bool) {
+        self.location = enable;
+    }
+
+    /// Check if the source location is part of the formatted log records
+    pub fn location_enabled(&self) -> bool {
+        self.location
+    }
+
+    /// Set the formatter used to format log records to text. The Console takes ownership of the formatter.
+    pub fn set_formatter<T: LogFormatter + 'static>(&mut self, formatter: T) {
+        self.formatter.replace(Box::from(formatter));
+    }
+
+    /// Remove the formatter. Log records are formatted with the ``TextFormatter`` again.
+    pub fn clear_formatter(&mut self) {
+        self.formatter = None;
+    }
+
+    /// Format a log record to text using the formatter of the console. In raw mode only the bare message is written.
+    pub fn format_record(&self, record: &LogRecord, w: &mut dyn fmt::Write) -> fmt::Result {
+        if self.raw {
+            return w.write_fmt(record.args());
+        }
+        match self.formatter {
+            Some(ref formatter) => formatter.format(self, record, w),
+            None => TextFormatter.format(self, record, w),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Framebuffer text console
+//!
+//! The ``FrameBufferConsole`` renders the output into a linear 32 bit per pixel framebuffer, e.g. the one allocated
+//! from the GPU with the mailbox property interface, so the HDMI screen becomes a kernel console:
+//! ```ignore
+//! let fb = mailbox::allocate_framebuffer(1024, 768, 32);
+//! let screen = unsafe { FrameBufferConsole::new(fb.base, fb.width, fb.height, fb.pitch) };
+//! CONSOLE.take_for(|cons| cons.add_sink(screen));
+//! ```
+//! The characters are drawn with the built-in 8x16 pixel font like on any other ``CharDisplay``, so the console
+//! keeps track of the cursor, wraps lines and scrolls the screen. Characters the font does not provide are drawn
+//! as ``?``.
+//!
+//! The console is available with the ``framebuffer`` feature only, as the glyphs of the font are covered by the
+//! Bitstream Vera font license given in ``LICENSE-FONT``.
+
+use crate::font::{FONT, FONT_HEIGHT, FONT_WIDTH};
+use crate::{CharDisplay, CharDisplayConsole, ConsoleImpl};
+use core::ptr::{read_volatile, write_volatile};
+
+/// The framebuffer as grid of characters
+struct FrameBuffer {
+    base: *mut u8,
+    width: usize,
+    height: usize,
+    pitch: usize,
+    foreground: u32,
+    background: u32,
+}
+
+// safe as the framebuffer is reserved for the console
+unsafe impl Send for FrameBuffer {}
+
+impl FrameBuffer {
+    fn pixel(&self, x: usize, y: usize) -> *mut u32 {
+        // safe as the callers only pass coordinates inside the framebuffer
+        unsafe { self.base.add(y * self.pitch + x * 4) as *mut u32 }
+    }
+
+    /// fill a single pixel line with the background
+    fn fill_line(&mut self, y: usize) {
+        for x in 0..self.width {
+            // safe as the pixel is inside the framebuffer
+            unsafe { write_volatile(self.pixel(x, y), self.background) };
+        }
+    }
+}
+
+impl CharDisplay for FrameBuffer {
+    fn size(&self) -> (usize, usize) {
+        (self.width / FONT_WIDTH, self.height / FONT_HEIGHT)
+    }
+
+    fn write_char(&mut self, col: usize, row: usize, c: char) {
+        let glyph = match c {
+            ' '..='~' => &FONT[c as usize - 0x20],
+            _ => &FONT[b'?' as usize - 0x20],
+        };
+        for (y, bits) in glyph.iter().enumerate() {
+            for x in 0..FONT_WIDTH {
+                let value = if bits & (0x80 >> x) != 0 {
+                    self.foreground
+                } else {
+                    self.background
+                };
+                let pixel = self.pixel(col * FONT_WIDTH + x, row * FONT_HEIGHT + y);
+                // safe as the glyph is drawn inside the framebuffer
+                unsafe { write_volatile(pixel, value) };
+            }
+        }
+    }
+
+    fn scroll(&mut self) {
+        self.scroll_region(0, self.size().1 - 1);
+    }
+
+    fn scroll_region(&mut self, top: usize, bottom: usize) {
+        let end = (bottom + 1) * FONT_HEIGHT;
+        for y in (top + 1) * FONT_HEIGHT..end {
+            for x in 0..self.width {
+                // safe as both pixels are inside the framebuffer
+                unsafe {
+                    write_volatile(
+                        self.pixel(x, y - FONT_HEIGHT),
+                        read_volatile(self.pixel(x, y)),
+                    )
+                };
+            }
+        }
+        for y in end - FONT_HEIGHT..end {
+            self.fill_line(y);
+        }
+    }
+
+    fn clear(&mut self) {
+        for y in 0..self.height {
+            self.fill_line(y);
+        }
+    }
+}
+
+/// The output channel drawing the output into a framebuffer
+pub struct FrameBufferConsole {
+    console: CharDisplayConsole<FrameBuffer>,
+    columns: usize,
+    rows: usize,
+}
+
+impl FrameBufferConsole {
+    /// Create the console drawing into the framebuffer at ``base`` with the given size in pixels and ``pitch`` bytes
+    /// per line. The text is drawn white on black with the cursor at the top left corner.
+    /// # Safety
+    /// The framebuffer needs to be valid for reads and writes of ``height * pitch`` bytes with 32 bits per pixel and
+    /// aligned to 4 bytes. No other code is allowed to draw into it as long as the console exists.
+    pub unsafe fn new(base: *mut u8, width: usize, height: usize, pitch: usize) -> Self {
+        FrameBufferConsole {
+            console: CharDisplayConsole::new(FrameBuffer {
+                base,
+                width,
+                height,
+                pitch,
+                foreground: 0x00FF_FFFF,
+                background: 0x0000_0000,
+            }),
+            columns: width / FONT_WIDTH,
+            rows: height / FONT_HEIGHT,
+        }
+    }
+
+    /// Set the pixel values of the text and the background used for the following output
+    pub fn set_colors(&mut self, foreground: u32, background: u32) {
+        let framebuffer = self.console.display_mut();
+        framebuffer.foreground = foreground;
+        framebuffer.background = background;
+    }
+
+    /// The number of characters per line
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// The number of lines on the screen
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The column and the row the next character is drawn at
+    pub fn cursor(&self) -> (usize, usize) {
+        self.console.cursor()
+    }
+
+    /// Move the cursor to the given column and row. Positions outside the screen are moved to the border.
+    pub fn set_cursor(&self, col: usize, row: usize) {
+        self.console.set_cursor(col, row)
+    }
+
+    /// Fill the screen with the background and move the cursor to the top left corner
+    pub fn clear(&self) {
+        self.console.clear()
+    }
+}
+
+impl ConsoleImpl for FrameBufferConsole {
+    fn putc(&self, c: char) {
+        self.console.putc(c)
+    }
+
+    fn puts(&self, s: &str) {
+        self.console.puts(s)
+    }
+}
@@ -0,0 +1,53 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Host output
+//!
+//! Available with the ``std`` feature for host side tests and simulators. The ``StdConsole`` writes the output to
+//! the standard output of the process. With this feature the console falls back to the standard output as long as
+//! no other console is attached instead of keeping the output in the early boot buffer, so the same ``println!``
+//! and ``info!`` code runs on the host without any setup:
+//! ```ignore
+//! fn main() {
+//!     info!("simulating the boot sequence");
+//!     kernel::initialize();
+//! }
+//! ```
+//! The ``panic-handler`` feature has no effect in combination with the ``std`` feature as the standard library
+//! already provides the panic handler.
+
+use crate::ConsoleImpl;
+use std::io::{self, Write};
+
+/// The output channel writing the output to the standard output of the process
+#[derive(Clone, Copy, Default)]
+pub struct StdConsole;
+
+impl StdConsole {
+    /// Create the console writing to the standard output
+    pub const fn new() -> Self {
+        StdConsole
+    }
+}
+
+impl ConsoleImpl for StdConsole {
+    fn putc(&self, c: char) {
+        self.puts(c.encode_utf8(&mut [0; 4]));
+    }
+
+    fn puts(&self, s: &str) {
+        let _ = io::stdout().write_all(s.as_bytes());
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        let _ = io::stdout().write_all(bytes);
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+    }
+}
@@ -0,0 +1,131 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Console input
+//!
+//! The read side of the console. An output channel that is also able to receive data (like the Uart) implements the
+//! ``ConsoleInput`` trait and hands it out through ``ConsoleImpl::input``. This way the same structure that has been
+//! passed to the console with ``replace`` serves both directions.
+
+use crate::{Console, LineDiscipline, CONSOLE};
+use alloc::string::String;
+
+/// backspace character as send by most terminals
+pub(crate) const BACKSPACE: char = '\u{8}';
+/// delete character, send by some terminals instead of backspace
+pub(crate) const DELETE: char = '\u{7f}';
+
+/// Every console that is able to receive characters need to implement this trait
+pub trait ConsoleInput {
+    /// check the input channel for a pending character. This shall return immediately with ``None`` if there is
+    /// no character available, so the console can be polled without blocking the core.
+    fn try_getc(&self) -> Option<char>;
+
+    /// wait for the next character to arrive at the input channel. Returns ``None`` if the channel is not able to
+    /// deliver any character. The default implementation polls ``try_getc`` until a character arrives.
+    fn getc(&self) -> Option<char> {
+        loop {
+            if let Some(c) = self.try_getc() {
+                return Some(c);
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
+
+impl Console {
+    /// Enable or disable the automatic echo of received characters. If enabled every character read from the
+    /// console is written back to the output channel, so interactive shells don't need to echo manually.
+    pub fn echo(&mut self, enable: bool) {
+        self.echo = enable;
+    }
+
+    /// Read the next character from the input channel of the current active console. Returns ``None`` if the active
+    /// console does not provide any input. The character is echoed if enabled. In the canonical line discipline
+    /// this waits for a finished line.
+    pub fn read_char(&self) -> Option<char> {
+        if self.discipline == LineDiscipline::Canonical {
+            return self.read_canonical(true);
+        }
+        let c = self.receive(true)?;
+        if self.echo {
+            self.putc(c);
+        }
+        Some(c)
+    }
+
+    /// Check the input channel of the current active console for a pending character without blocking. Returns
+    /// ``None`` if no character is available or the active console does not provide any input. The character is
+    /// echoed if enabled. In the canonical line discipline only the characters of a finished line are returned.
+    pub fn try_read_char(&self) -> Option<char> {
+        if self.discipline == LineDiscipline::Canonical {
+            return self.read_canonical(false);
+        }
+        let c = self.receive(false)?;
+        if self.echo {
+            self.putc(c);
+        }
+        Some(c)
+    }
+
+    /// read the next character from the input channel of the current active console without echo
+    pub(crate) fn read_raw(&self) -> Option<char> {
+        self.receive(true)
+    }
+
+    /// Read a whole line from the input channel of the current active console. Characters are collected into the
+    /// given buffer until a newline (``\r`` or ``\n``) is received, a backspace removes the last collected character.
+    /// The buffer is cleared before reading and the collected line (without the newline) is returned. Reading stops
+    /// early if the console does not deliver any further character. If echo is enabled the line is echoed as it is
+    /// edited.
+    pub fn read_line<'a>(&self, buf: &'a mut String) -> &'a str {
+        buf.clear();
+        if self.discipline == LineDiscipline::Canonical {
+            while let Some(c) = self.read_canonical(true) {
+                if c == '\n' {
+                    break;
+                }
+                buf.push(c);
+            }
+            return buf.as_str();
+        }
+        while let Some(c) = self.receive(true) {
+            match c {
+                '\r' | '\n' => {
+                    if self.echo {
+                        self.puts("\r\n");
+                    }
+                    break;
+                }
+                BACKSPACE | DELETE => {
+                    if buf.pop().is_some() && self.echo {
+                        // move back, blank the character and move back again
+                        self.puts("\u{8} \u{8}");
+                    }
+                }
+                _ => {
+                    buf.push(c);
+                    if self.echo {
+                        self.putc(c);
+                    }
+                }
+            }
+        }
+        buf.as_str()
+    }
+}
+
+/// Read a single character from the current active console. This is the counterpart of the ``print`` function.
+pub fn read_char() -> Option<char> {
+    CONSOLE.use_for(|console| console.read_char())
+}
+
+/// Poll the current active console for a pending character. Other than ``read_char`` this returns immediately with
+/// ``None`` if there is nothing to read, which allows cooperative schedulers to check for input without blocking.
+pub fn try_read_char() -> Option<char> {
+    CONSOLE.use_for(|console| console.try_read_char())
+}
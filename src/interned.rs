@@ -0,0 +1,255 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Interned logging
+//!
+//! Formatting text costs CPU time and every character needs to be transmitted over the Uart. For high rate logging
+//! the ``*_interned!`` macros do not format the message at all. The format string is stored once in the binary and
+//! only its address and the raw arguments are sent, the host formats the message with the help of the binary:
+//! ```ignore
+//! loop {
+//!     info_interned!("irq {} handled in {}us", irq, elapsed);
+//! }
+//! ```
+//! The format strings are placed in the ``.console_strings`` link section, each prefixed with the module path and a
+//! NUL character. Only values implementing ``InternedArg`` could be passed as arguments: integers, floats, ``bool``,
+//! ``char`` and string slices.
+//!
+//! Each message is sent as a single line starting with the ASCII record separator ``\x1e``, followed by the frame
+//! encoded as base64 without padding. The frame contains the address of the format string as LEB128 varint, the
+//! severity (``Level as u8``) and all arguments. Each argument starts with a tag byte: ``u`` is followed by an
+//! unsigned LEB128 varint, ``i`` by a zigzag encoded signed varint, ``f`` by a little endian ``f64``, ``b`` by a
+//! single byte, ``c`` by the code point as varint and ``s`` by the length as varint and the UTF-8 bytes. A frame
+//! holds up to ``INTERNED_FRAME_SIZE`` bytes, strings are truncated to fit and arguments that do not fit at all are
+//! omitted.
+//!
+//! The log level and the target filter apply like for the other severity macros. As the message is no log record it
+//! bypasses the routing and the formatter and is passed to the current active console and all additional sinks.
+
+use crate::Level;
+
+/// The maximum size of a frame in bytes before it is base64 encoded
+pub const INTERNED_FRAME_SIZE: usize = 64;
+
+/// The frame of an interned message
+pub struct InternedFrame {
+    data: [u8; INTERNED_FRAME_SIZE],
+    len: usize,
+}
+
+impl InternedFrame {
+    /// Create a new frame for the interned format string at the given address
+    pub fn new(id: usize, level: Level) -> Self {
+        let mut frame = InternedFrame {
+            data: [0; INTERNED_FRAME_SIZE],
+            len: 0,
+        };
+        frame.push_varint(id as u64);
+        frame.push(&[level as u8]);
+        frame
+    }
+
+    /// The encoded frame
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+
+    /// Add an unsigned integer argument
+    pub fn push_unsigned(&mut self, value: u64) {
+        let mut varint = [0; 11];
+        varint[0] = b'u';
+        let len = encode_varint(value, &mut varint[1..]);
+        self.push(&varint[..=len]);
+    }
+
+    /// Add a signed integer argument
+    pub fn push_signed(&mut self, value: i64) {
+        let mut varint = [0; 11];
+        varint[0] = b'i';
+        let len = encode_varint(((value << 1) ^ (value >> 63)) as u64, &mut varint[1..]);
+        self.push(&varint[..=len]);
+    }
+
+    /// Add a floating point argument
+    pub fn push_float(&mut self, value: f64) {
+        let mut float = [0; 9];
+        float[0] = b'f';
+        float[1..].copy_from_slice(&value.to_le_bytes());
+        self.push(&float);
+    }
+
+    /// Add a boolean argument
+    pub fn push_bool(&mut self, value: bool) {
+        self.push(&[b'b', value as u8]);
+    }
+
+    /// Add a character argument
+    pub fn push_char(&mut self, value: char) {
+        let mut varint = [0; 11];
+        varint[0] = b'c';
+        let len = encode_varint(value as u64, &mut varint[1..]);
+        self.push(&varint[..=len]);
+    }
+
+    /// Add a string argument. The string is truncated at a character boundary if it does not fit into the frame.
+    pub fn push_str(&mut self, value: &str) {
+        let mut header = [0; 11];
+        header[0] = b's';
+        // the length needs to fit first, the header shrinks with the length of the string
+        let mut len = value.len();
+        loop {
+            let header_len = encode_varint(len as u64, &mut header[1..]) + 1;
+            let space = INTERNED_FRAME_SIZE - self.len;
+            if header_len + len <= space {
+                self.push(&header[..header_len]);
+                self.push(&value.as_bytes()[..len]);
+                return;
+            }
+            if header_len >= space {
+                return;
+            }
+            len = space - header_len;
+            while !value.is_char_boundary(len) {
+                len -= 1;
+            }
+        }
+    }
+
+    /// add the bytes to the frame if all of them fit
+    fn push(&mut self, bytes: &[u8]) {
+        if self.len + bytes.len() <= INTERNED_FRAME_SIZE {
+            self.data[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+        }
+    }
+
+    fn push_varint(&mut self, value: u64) {
+        let mut varint = [0; 10];
+        let len = encode_varint(value, &mut varint);
+        self.push(&varint[..len]);
+    }
+}
+
+/// encode the value as LEB128 varint into the buffer, returns the number of bytes used
+fn encode_varint(mut value: u64, buf: &mut [u8]) -> usize {
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            return len + 1;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+/// Every value that could be passed as argument to the ``*_interned!`` macros need to implement this trait
+pub trait InternedArg {
+    /// add the value to the frame
+    fn encode(&self, frame: &mut InternedFrame);
+}
+
+macro_rules! interned_unsigned {
+    ($($t:ty),*) => ($(
+        impl InternedArg for $t {
+            fn encode(&self, frame: &mut InternedFrame) {
+                frame.push_unsigned(*self as u64);
+            }
+        }
+    )*);
+}
+
+macro_rules! interned_signed {
+    ($($t:ty),*) => ($(
+        impl InternedArg for $t {
+            fn encode(&self, frame: &mut InternedFrame) {
+                frame.push_signed(*self as i64);
+            }
+        }
+    )*);
+}
+
+interned_unsigned!(u8, u16, u32, u64, usize);
+interned_signed!(i8, i16, i32, i64, isize);
+
+impl InternedArg for f32 {
+    fn encode(&self, frame: &mut InternedFrame) {
+        frame.push_float(*self as f64);
+    }
+}
+
+impl InternedArg for f64 {
+    fn encode(&self, frame: &mut InternedFrame) {
+        frame.push_float(*self);
+    }
+}
+
+impl InternedArg for bool {
+    fn encode(&self, frame: &mut InternedFrame) {
+        frame.push_bool(*self);
+    }
+}
+
+impl InternedArg for char {
+    fn encode(&self, frame: &mut InternedFrame) {
+        frame.push_char(*self);
+    }
+}
+
+impl InternedArg for str {
+    fn encode(&self, frame: &mut InternedFrame) {
+        frame.push_str(self);
+    }
+}
+
+impl<T: InternedArg + ?Sized> InternedArg for &T {
+    fn encode(&self, frame: &mut InternedFrame) {
+        (**self).encode(frame);
+    }
+}
+
+/// Copy the interned string into a byte array of its length. Used by the ``*_interned!`` macros to store the format
+/// strings in the link section.
+#[doc(hidden)]
+pub const fn intern<const N: usize>(s: &str) -> [u8; N] {
+    let bytes = s.as_bytes();
+    let mut data = [0; N];
+    let mut i = 0;
+    while i < N {
+        data[i] = bytes[i];
+        i += 1;
+    }
+    data
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The printing function hidden behind the interned severity macros. The frame is base64 encoded and passed to the
+/// console as a single line.
+pub fn print_interned(frame: &InternedFrame) {
+    // record separator, base64 without padding and the line break
+    let mut line = [0; 1 + (INTERNED_FRAME_SIZE * 4).div_ceil(3) + 2];
+    line[0] = 0x1e;
+    let mut len = 1;
+    for chunk in frame.as_bytes().chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..=chunk.len() {
+            line[len] = BASE64[(bits >> (18 - 6 * i) & 0x3f) as usize];
+            len += 1;
+        }
+    }
+    line[len..len + 2].copy_from_slice(b"\r\n");
+    len += 2;
+    // the line consists of ASCII characters only
+    if let Ok(s) = core::str::from_utf8(&line[..len]) {
+        crate::print(s);
+    }
+}
@@ -0,0 +1,177 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # ``embedded-io`` integration
+//!
+//! Available with the ``embedded-io`` feature. The integration works in both directions. The ``IoConsole`` turns any
+//! writer implementing ``embedded_io::Write`` into an output channel of the console:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.replace(IoConsole::new(uart)));
+//! ```
+//! The other way around the ``ConsoleWriter`` implements ``embedded_io::Write`` and the ``ConsoleReader``
+//! implements ``embedded_io::Read``, so libraries written against ``embedded-io`` could use the console directly:
+//! ```ignore
+//! let mut shell = Shell::new(ConsoleReader::new(), ConsoleWriter);
+//! ```
+//! Like the ``SerialConsole`` the ``IoConsole`` locks the writer while a string is written.
+
+use crate::{ConsoleError, ConsoleImpl, ConsoleWriter, Locked, CONSOLE};
+use embedded_io::{Error, ErrorKind, ErrorType, Read, Write};
+
+impl Error for ConsoleError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ConsoleError::Timeout => ErrorKind::TimedOut,
+            ConsoleError::Unavailable => ErrorKind::NotConnected,
+            ConsoleError::Busy | ConsoleError::Failed => ErrorKind::Other,
+        }
+    }
+}
+
+/// The output channel writing to a writer of ``embedded-io``
+pub struct IoConsole<W> {
+    writer: Locked<W>,
+}
+
+impl<W> IoConsole<W> {
+    /// Create a new output channel writing to the given writer
+    pub const fn new(writer: W) -> Self {
+        IoConsole {
+            writer: Locked::new(writer),
+        }
+    }
+
+    /// Hand back the writer
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl<W: Write> IoConsole<W> {
+    /// write all bytes and map the error of the writer to the console error
+    fn write_bytes(&self, bytes: &[u8]) -> Result<(), ConsoleError> {
+        self.writer
+            .with(|writer| writer.write_all(bytes))
+            .map_err(|error| match error.kind() {
+                ErrorKind::TimedOut => ConsoleError::Timeout,
+                ErrorKind::NotConnected | ErrorKind::BrokenPipe => ConsoleError::Unavailable,
+                _ => ConsoleError::Failed,
+            })
+    }
+}
+
+impl<W: Write> ConsoleImpl for IoConsole<W> {
+    fn putc(&self, c: char) {
+        let _ = self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    fn puts(&self, s: &str) {
+        let _ = self.write_bytes(s.as_bytes());
+    }
+
+    fn try_putc(&self, c: char) -> Result<(), ConsoleError> {
+        self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes())
+    }
+
+    fn try_puts(&self, s: &str) -> Result<(), ConsoleError> {
+        self.write_bytes(s.as_bytes())
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        let _ = self.write_bytes(bytes);
+    }
+
+    fn flush(&self) {
+        let _ = self.writer.with(|writer| writer.flush());
+    }
+}
+
+impl ErrorType for ConsoleWriter {
+    type Error = ConsoleError;
+}
+
+/// Writing bytes to the console passes them with ``Console::puts_bytes``, so invalid UTF-8 sent to text only output
+/// channels is replaced with U+FFFD
+impl Write for ConsoleWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, ConsoleError> {
+        CONSOLE.use_for(|console| console.puts_bytes(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), ConsoleError> {
+        crate::console_flush();
+        Ok(())
+    }
+}
+
+/// A reader of the input channel of the current active console implementing ``embedded_io::Read``. The received
+/// characters are handed out UTF-8 encoded.
+pub struct ConsoleReader {
+    /// the bytes of a received character that did not fit into the buffer of the last read
+    pending: [u8; 4],
+    start: usize,
+    end: usize,
+}
+
+impl ConsoleReader {
+    /// Create a new reader of the current active console
+    pub const fn new() -> Self {
+        ConsoleReader {
+            pending: [0; 4],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// move pending bytes into the buffer, returns the number of bytes moved
+    fn take_pending(&mut self, buf: &mut [u8]) -> usize {
+        let len = (self.end - self.start).min(buf.len());
+        buf[..len].copy_from_slice(&self.pending[self.start..self.start + len]);
+        self.start += len;
+        len
+    }
+}
+
+impl Default for ConsoleReader {
+    fn default() -> Self {
+        ConsoleReader::new()
+    }
+}
+
+impl ErrorType for ConsoleReader {
+    type Error = ConsoleError;
+}
+
+/// Reading waits for the first character and adds all further characters that are already pending. The console
+/// echoes them if enabled. A read returns ``0`` bytes if the current active console does not provide any input.
+impl Read for ConsoleReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ConsoleError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut len = self.take_pending(buf);
+        if len == 0 {
+            match crate::read_char() {
+                Some(c) => self.end = c.encode_utf8(&mut self.pending).len(),
+                None => return Ok(0),
+            }
+            self.start = 0;
+            len = self.take_pending(buf);
+        }
+        while len < buf.len() && self.start == self.end {
+            match crate::try_read_char() {
+                Some(c) => {
+                    self.end = c.encode_utf8(&mut self.pending).len();
+                    self.start = 0;
+                    len += self.take_pending(&mut buf[len..]);
+                }
+                None => break,
+            }
+        }
+        Ok(len)
+    }
+}
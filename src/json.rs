@@ -0,0 +1,157 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # JSON lines output
+//!
+//! Format each log record as a single JSON object per line, so the output captured from the Uart can be ingested by
+//! host side tooling without parsing the text layout:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_formatter(JsonFormatter));
+//! info!(len = 4096, "mapped region");
+//! // {"level":"INFO","target":"kernel","seq":7,"message":"mapped region","fields":{"len":"4096"}}
+//! ```
+//! The sequence number, the timestamp (in seconds), the core id and the source location are added if the record carries
+//! them. Field values are always written as JSON strings.
+
+use crate::{Console, LogFormatter, LogRecord};
+use core::fmt::{self, Write};
+
+/// ``fmt::Write`` adapter escaping everything written as content of a JSON string
+struct JsonEscape<'a>(&'a mut dyn fmt::Write);
+
+impl fmt::Write for JsonEscape<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                '"' => self.0.write_str("\\\"")?,
+                '\\' => self.0.write_str("\\\\")?,
+                '\n' => self.0.write_str("\\n")?,
+                '\r' => self.0.write_str("\\r")?,
+                '\t' => self.0.write_str("\\t")?,
+                c if (c as u32) < 0x20 => write!(self.0, "\\u{:04x}", c as u32)?,
+                c => self.0.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// write the given value as JSON string
+fn write_string(w: &mut dyn fmt::Write, value: impl fmt::Display) -> fmt::Result {
+    w.write_char('"')?;
+    write!(JsonEscape(w), "{}", value)?;
+    w.write_char('"')
+}
+
+/// The formatter producing a single line JSON object with the keys ``level``, ``target``, ``seq``, ``timestamp``,
+/// ``core``, ``file``, ``line``, ``message`` and ``fields`` for each log record
+pub struct JsonFormatter;
+
+impl LogFormatter for JsonFormatter {
+    fn format(&self, _: &Console, record: &LogRecord, w: &mut dyn fmt::Write) -> fmt::Result {
+        w.write_str("{\"level\":")?;
+        write_string(w, record.level().name())?;
+        w.write_str(",\"target\":")?;
+        write_string(w, record.target())?;
+        if let Some(sequence) = record.sequence() {
+            write!(w, ",\"seq\":{}", sequence)?;
+        }
+        if let Some(timestamp) = record.timestamp() {
+            write!(
+                w,
+                ",\"timestamp\":{}.{:06}",
+                timestamp.as_secs(),
+                timestamp.subsec_micros()
+            )?;
+        }
+        if let Some(core) = record.core_id() {
+            write!(w, ",\"core\":{}", core)?;
+        }
+        if let Some(file) = record.file() {
+            w.write_str(",\"file\":")?;
+            write_string(w, file)?;
+        }
+        if let Some(line) = record.line() {
+            write!(w, ",\"line\":{}", line)?;
+        }
+        w.write_str(",\"message\":")?;
+        write_string(w, record.args())?;
+        if !record.fields().is_empty() {
+            w.write_str(",\"fields\":{")?;
+            for (idx, field) in record.fields().iter().enumerate() {
+                if idx > 0 {
+                    w.write_char(',')?;
+                }
+                write_string(w, field.key())?;
+                w.write_char(':')?;
+                write_string(w, field.value())?;
+            }
+            w.write_char('}')?;
+        }
+        w.write_str("}\r\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Field, Level, CONSOLE};
+    use alloc::string::String;
+
+    fn escaped(value: &str) -> String {
+        let mut json = String::new();
+        write_string(&mut json, value).unwrap();
+        json
+    }
+
+    fn formatted(record: &LogRecord) -> String {
+        let mut json = String::new();
+        CONSOLE.use_for(|console| JsonFormatter.format(console, record, &mut json)).unwrap();
+        json
+    }
+
+    #[test]
+    fn escapes_strings() {
+        assert_eq!(escaped("plain"), "\"plain\"");
+        assert_eq!(escaped("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(escaped("C:\\boot"), "\"C:\\\\boot\"");
+        assert_eq!(escaped("a\r\n\tb"), "\"a\\r\\n\\tb\"");
+        assert_eq!(escaped("\u{1}\u{1b}"), "\"\\u0001\\u001b\"");
+        assert_eq!(escaped("grüße"), "\"grüße\"");
+    }
+
+    #[test]
+    fn formats_a_record_as_single_line() {
+        assert_eq!(
+            formatted(&LogRecord::new(
+                Level::Info,
+                "kernel",
+                format_args!("mapped\nregion"),
+                &[
+                    Field::new("len", format_args!("{}", 4096)),
+                    Field::new("name", format_args!("{:?}", "boot")),
+                ],
+            )),
+            "{\"level\":\"INFO\",\"target\":\"kernel\",\"message\":\"mapped\\nregion\",\
+             \"fields\":{\"len\":\"4096\",\"name\":\"\\\"boot\\\"\"}}\r\n"
+        );
+    }
+
+    #[test]
+    fn adds_the_metadata_of_the_record() {
+        let record = LogRecord::new(Level::Error, "sdhci", format_args!("timeout"), &[])
+            .with_timestamp(core::time::Duration::from_micros(1_500_042))
+            .with_core_id(2)
+            .with_location("src/sdhci.rs", 42)
+            .with_sequence(7);
+        assert_eq!(
+            formatted(&record),
+            "{\"level\":\"ERROR\",\"target\":\"sdhci\",\"seq\":7,\"timestamp\":1.500042,\"core\":2,\
+             \"file\":\"src/sdhci.rs\",\"line\":42,\"message\":\"timeout\"}\r\n"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Key decoder
+//!
+//! Terminals send the arrow keys, the function keys and the like as escape sequences of several characters. The
+//! ``KeyDecoder`` collects those sequences character by character and turns them into ``KeyEvent``s:
+//! ```ignore
+//! let mut decoder = KeyDecoder::new();
+//! for c in received {
+//!     if let Some(key) = decoder.push(c) {
+//!         handle(key);
+//!     }
+//! }
+//! ```
+//! ``read_key`` reads the next key from the current active console without echo. The sequences of the VT100 and the
+//! xterm family are understood, modifiers like ``ESC [ 1 ; 5 C`` are ignored. As there is no timeout a single
+//! escape can't be told apart from the start of a sequence, pressing escape twice is reported as ``Escape`` and
+//! escape followed by any other character as ``Alt``.
+
+use crate::CONSOLE;
+
+/// A key pressed at the terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyEvent {
+    /// A printable character
+    Char(char),
+    /// A character typed with the control key, e.g. ``Ctrl('c')``
+    Ctrl(char),
+    /// A character typed with the alt key
+    Alt(char),
+    Enter,
+    Tab,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    Right,
+    Left,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    /// The function key with the given number, starting with 1
+    Function(u8),
+    /// An escape sequence that is not understood
+    Unknown,
+}
+
+/// The part of an escape sequence received so far
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    /// ``ESC [`` with the parameters received so far
+    Csi,
+    /// ``ESC O``
+    Ss3,
+}
+
+/// The state machine turning the received characters into keys
+pub struct KeyDecoder {
+    state: State,
+    /// the first parameter of the control sequence
+    param: u16,
+    /// a parameter separator has been received, the following parameters are ignored
+    separated: bool,
+}
+
+impl KeyDecoder {
+    /// Create a decoder waiting for the first character of a key
+    pub const fn new() -> Self {
+        KeyDecoder {
+            state: State::Ground,
+            param: 0,
+            separated: false,
+        }
+    }
+
+    /// Pass the next received character to the decoder. Returns the key once all characters of it are received.
+    pub fn push(&mut self, c: char) -> Option<KeyEvent> {
+        let key = match self.state {
+            State::Ground => match c {
+                '\u{1b}' => return self.enter(State::Escape),
+                '\r' | '\n' => KeyEvent::Enter,
+                '\t' => KeyEvent::Tab,
+                '\u{8}' | '\u{7f}' => KeyEvent::Backspace,
+                '\u{1}'..='\u{1a}' => KeyEvent::Ctrl((c as u8 - 1 + b'a') as char),
+                c if c.is_control() => KeyEvent::Unknown,
+                c => KeyEvent::Char(c),
+            },
+            State::Escape => match c {
+                '[' => return self.enter(State::Csi),
+                'O' => return self.enter(State::Ss3),
+                '\u{1b}' => KeyEvent::Escape,
+                c => KeyEvent::Alt(c),
+            },
+            State::Csi => match c {
+                '0'..='9' if !self.separated => {
+                    self.param = self
+                        .param
+                        .saturating_mul(10)
+                        .saturating_add(c as u16 - '0' as u16);
+                    return None;
+                }
+                '\u{20}'..='\u{3f}' => {
+                    self.separated |= c == ';';
+                    return None;
+                }
+                '~' => tilde_key(self.param),
+                c => final_key(c),
+            },
+            State::Ss3 => match c {
+                'P'..='S' => KeyEvent::Function(c as u8 - b'P' + 1),
+                c => final_key(c),
+            },
+        };
+        self.reset();
+        Some(key)
+    }
+
+    /// Drop the part of an escape sequence received so far
+    pub fn reset(&mut self) {
+        self.enter(State::Ground);
+    }
+
+    /// Check if the decoder is in the middle of an escape sequence
+    pub fn is_pending(&self) -> bool {
+        self.state != State::Ground
+    }
+
+    fn enter(&mut self, state: State) -> Option<KeyEvent> {
+        self.state = state;
+        self.param = 0;
+        self.separated = false;
+        None
+    }
+}
+
+impl Default for KeyDecoder {
+    fn default() -> Self {
+        KeyDecoder::new()
+    }
+}
+
+/// the key of the final character of ``ESC [`` and ``ESC O`` sequences
+fn final_key(c: char) -> KeyEvent {
+    match c {
+        'A' => KeyEvent::Up,
+        'B' => KeyEvent::Down,
+        'C' => KeyEvent::Right,
+        'D' => KeyEvent::Left,
+        'H' => KeyEvent::Home,
+        'F' => KeyEvent::End,
+        'P'..='S' => KeyEvent::Function(c as u8 - b'P' + 1),
+        _ => KeyEvent::Unknown,
+    }
+}
+
+/// the key of ``ESC [ <param> ~`` sequences
+fn tilde_key(param: u16) -> KeyEvent {
+    match param {
+        1 | 7 => KeyEvent::Home,
+        2 => KeyEvent::Insert,
+        3 => KeyEvent::Delete,
+        4 | 8 => KeyEvent::End,
+        5 => KeyEvent::PageUp,
+        6 => KeyEvent::PageDown,
+        11..=15 => KeyEvent::Function((param - 10) as u8),
+        17..=21 => KeyEvent::Function((param - 11) as u8),
+        23 | 24 => KeyEvent::Function((param - 12) as u8),
+        _ => KeyEvent::Unknown,
+    }
+}
+
+/// Read the next key from the current active console without echo. ``None`` if the console does not provide any
+/// further input.
+pub fn read_key() -> Option<KeyEvent> {
+    let mut decoder = KeyDecoder::new();
+    loop {
+        let c = CONSOLE.use_for(|console| console.read_raw())?;
+        if let Some(key) = decoder.push(c) {
+            return Some(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn decode(s: &str) -> Vec<KeyEvent> {
+        let mut decoder = KeyDecoder::new();
+        s.chars().filter_map(|c| decoder.push(c)).collect()
+    }
+
+    #[test]
+    fn decodes_plain_keys() {
+        assert_eq!(
+            decode("a\r\t\u{7f}\u{3}"),
+            [
+                KeyEvent::Char('a'),
+                KeyEvent::Enter,
+                KeyEvent::Tab,
+                KeyEvent::Backspace,
+                KeyEvent::Ctrl('c')
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_escape_sequences() {
+        assert_eq!(
+            decode("\x1b[A\x1b[D\x1bOH\x1b[3~\x1b[6~\x1b[15~\x1bOP"),
+            [
+                KeyEvent::Up,
+                KeyEvent::Left,
+                KeyEvent::Home,
+                KeyEvent::Delete,
+                KeyEvent::PageDown,
+                KeyEvent::Function(5),
+                KeyEvent::Function(1)
+            ]
+        );
+        assert_eq!(decode("\x1b[1;5C"), [KeyEvent::Right]);
+        assert_eq!(
+            decode("\x1bx\x1b\x1b"),
+            [KeyEvent::Alt('x'), KeyEvent::Escape]
+        );
+    }
+
+    #[test]
+    fn keeps_incomplete_sequences_pending() {
+        let mut decoder = KeyDecoder::new();
+        assert_eq!(decoder.push('\u{1b}'), None);
+        assert_eq!(decoder.push('['), None);
+        assert!(decoder.is_pending());
+        decoder.reset();
+        assert!(!decoder.is_pending());
+        assert_eq!(decoder.push('B'), Some(KeyEvent::Char('B')));
+    }
+}
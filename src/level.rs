@@ -0,0 +1,263 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Severity levels
+//!
+//! The severity of the messages printed with the ``trace!``, ``debug!``, ``info!``, ``warn!`` and ``error!`` macros.
+//! Each severity could be routed to a dedicated output channel, e.g. errors to the Uart while info messages go to the
+//! screen:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.route(Level::Error, uart));
+//! ```
+//! Messages below the log level of the console are skipped before they are formatted:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_log_level(Level::Warn));
+//! info!("this is not printed");
+//! ```
+//! ``debug!`` and ``trace!`` messages are skipped unless the log level is raised to ``Level::Debug`` or
+//! ``Level::Trace``. The ``max-level-*`` features (``max-level-off``, ``max-level-error``, ``max-level-warn``,
+//! ``max-level-info``, ``max-level-debug`` and ``max-level-trace``) define the maximum severity compiled into the
+//! binary. Messages above will be removed at compile time, so release kernels do not carry their format strings or the
+//! formatting code.
+//!
+//! During the bring-up of a single core the output of the other cores is mostly noise. The log level could be set
+//! for every core on its own, the core is told by the core id provider of the console:
+//! ```ignore
+//! CONSOLE.take_for(|cons| {
+//!     cons.set_log_level(Level::Warn);
+//!     cons.set_core_log_level(0, Level::Trace);
+//! });
+//! ```
+
+use crate::{ColorDepth, Console, ConsoleImpl, CONSOLE};
+use alloc::boxed::Box;
+use core::{fmt, str::FromStr};
+
+/// The severity of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// very serious errors
+    Error = 1,
+    /// hazardous situations
+    Warn,
+    /// useful information
+    Info,
+    /// lower priority information
+    Debug,
+    /// very low priority, often extremely verbose, information
+    Trace,
+}
+
+/// The maximum severity compiled into the binary as selected with the ``max-level-*`` features. ``None`` if all
+/// messages are removed at compile time.
+pub const STATIC_MAX_LEVEL: Option<Level> = if cfg!(feature = "max-level-off") {
+    None
+} else if cfg!(feature = "max-level-error") {
+    Some(Level::Error)
+} else if cfg!(feature = "max-level-warn") {
+    Some(Level::Warn)
+} else if cfg!(feature = "max-level-info") {
+    Some(Level::Info)
+} else if cfg!(feature = "max-level-debug") {
+    Some(Level::Debug)
+} else {
+    Some(Level::Trace)
+};
+
+/// Check if messages of the given severity are compiled into the binary. The severity macros use this to turn into
+/// no-ops for severities above ``STATIC_MAX_LEVEL``.
+pub const fn static_enabled(level: Level) -> bool {
+    match STATIC_MAX_LEVEL {
+        Some(max) => level as u8 <= max as u8,
+        None => false,
+    }
+}
+
+impl Level {
+    /// All severities from the most to the least serious one
+    pub const ALL: [Level; 5] = [
+        Level::Error,
+        Level::Warn,
+        Level::Info,
+        Level::Debug,
+        Level::Trace,
+    ];
+
+    /// The prefix the severity macros put in front of a message of this level
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Level::Error => "E",
+            Level::Warn => "W",
+            Level::Info => "I",
+            Level::Debug => "D",
+            Level::Trace => "T",
+        }
+    }
+
+    /// The ANSI SGR escape sequence used to colorize the prefix of this level if enabled, ``None`` if the prefix is
+    /// printed in the default color of the terminal
+    pub fn color(&self) -> Option<&'static str> {
+        match self {
+            Level::Error => Some("\u{1b}[31m"),
+            Level::Warn => Some("\u{1b}[33m"),
+            _ => None,
+        }
+    }
+
+    /// The upper case name of this level, e.g. ``"WARN"``
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    /// Parse the level from its name, e.g. ``"warn"``. The name is not case sensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Level::ALL
+            .iter()
+            .find(|level| s.eq_ignore_ascii_case(level.name()))
+            .copied()
+            .ok_or(ParseLevelError)
+    }
+}
+
+/// The error returned if a string does not contain a valid severity name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLevelError;
+
+impl fmt::Display for ParseLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid severity level")
+    }
+}
+
+impl Console {
+    /// Set the log level of the console. Messages with a lower severity than the given level are skipped.
+    pub fn set_log_level(&mut self, level: Level) {
+        self.level = level;
+    }
+
+    /// The current log level of the console
+    pub fn log_level(&self) -> Level {
+        self.level
+    }
+
+    /// Set the log level of the given core. It overrides the log level of the console for messages logged on this
+    /// core. Core ids from ``MAX_CORES`` on are ignored and ``false`` is returned.
+    pub fn set_core_log_level(&mut self, core: usize, level: Level) -> bool {
+        match self.core_levels.get_mut(core) {
+            Some(core_level) => {
+                core_level.replace(level);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the log level of the given core, its messages are checked against the log level of the console again.
+    /// Core ids from ``MAX_CORES`` on are ignored.
+    pub fn clear_core_log_level(&mut self, core: usize) {
+        if let Some(core_level) = self.core_levels.get_mut(core) {
+            core_level.take();
+        }
+    }
+
+    /// The log level the messages logged on the given core are checked against. Core ids from ``MAX_CORES`` on
+    /// never have a log level of their own.
+    pub fn core_log_level(&self, core: usize) -> Level {
+        self.core_levels
+            .get(core)
+            .copied()
+            .flatten()
+            .unwrap_or(self.level)
+    }
+
+    /// Check if messages of the given severity and target pass the log level of the current core or the target
+    /// filter of the console
+    pub fn log_enabled(&self, level: Level, target: &str) -> bool {
+        let max = match self.core_levels.iter().any(Option::is_some) {
+            true => self.core_log_level(self.core_id().unwrap_or(0)),
+            false => self.level,
+        };
+        match self.filter {
+            Some(ref filter) => filter.enabled(level, target).unwrap_or(level <= max),
+            None => level <= max,
+        }
+    }
+
+    /// Enable or disable the colorized severity prefix. Errors are printed red and warnings yellow using ANSI escape
+    /// sequences, so this should only be enabled if the output channel is connected to an ANSI terminal.
+    pub fn set_color(&mut self, enable: bool) {
+        self.color = enable;
+    }
+
+    /// Check if the colorized severity prefix is enabled. Always ``false`` in raw mode or if the terminal detected
+    /// with ``detect_terminal`` does not support colors.
+    pub fn color_enabled(&self) -> bool {
+        self.color
+            && !self.raw
+            && !matches!(self.terminal, Some(caps) if caps.colors == ColorDepth::Monochrome)
+    }
+
+    /// Route all messages of the given severity to a dedicated output channel instead of the current active console
+    /// and the additional sinks. A previous route for this severity is flushed and closed.
+    pub fn route<T: ConsoleImpl + 'static>(&mut self, level: Level, sink: T) {
+        self.clear_route(level);
+        self.routes.push((level, Box::from(sink)));
+    }
+
+    /// Remove the dedicated output channel of the given severity. Messages of this severity are passed to the
+    /// current active console and the additional sinks again.
+    pub fn clear_route(&mut self, level: Level) {
+        if let Some(idx) = self.routes.iter().position(|(route, _)| *route == level) {
+            let (_, mut sink) = self.routes.remove(idx);
+            sink.flush();
+            sink.close();
+        }
+    }
+
+    /// Pass a string of the given severity to its dedicated output channel. If there is none the string is passed
+    /// to the current active console and all additional sinks. Output in interrupt context or queued mode is
+    /// deferred.
+    pub fn puts_level(&self, level: Level, s: &str) {
+        if self.defers_output() {
+            self.defer(Some(level), |w| w.write_str(s));
+            return;
+        }
+        self.puts_level_direct(level, s);
+    }
+
+    /// pass the string to the output channel of its severity without deferring it
+    pub(crate) fn puts_level_direct(&self, level: Level, s: &str) {
+        match self.routes.iter().find(|(route, _)| *route == level) {
+            Some((_, sink)) => {
+                self.guarded(|| self.translated(s, |part| sink.puts(part)));
+            }
+            None => self.puts_direct(s),
+        }
+    }
+}
+
+/// Check if messages of the given severity and target pass the log level or the target filter of the console. The
+/// severity macros use this to skip messages before they are formatted.
+pub fn log_enabled(level: Level, target: &str) -> bool {
+    CONSOLE.use_for(|console| console.log_enabled(level, target))
+}
@@ -4,8 +4,8 @@
  * Author: André Borrmann
  * License: Appache License 2.0
  **********************************************************************************************************************/
-#![doc(html_root_url = "https://docs.rs/ruspiro-console/0.3.1")]
-#![cfg_attr(not(any(test, doctest)), no_std)]
+#![doc(html_root_url = "https://docs.rs/ruspiro-console/0.4.0")]
+#![cfg_attr(not(any(test, doctest, feature = "std")), no_std)]
 
 //! # Console abstraction
 //!
@@ -15,9 +15,10 @@
 //! message that shall be printed. Those are ``info!``, ``warn!`` and ``error!``.
 //!
 //! # Dependencies
-//! This crate uses macros to provide formatted strings. This formatting requires a memory allocator to
-//! be present (as part of the ``alloc`` crate). So when using this crate provide an allocator such as
-//! ``ruspiro_allocator``.
+//! The console takes ownership of the actual output channel which requires a memory allocator to be present (as part
+//! of the ``alloc`` crate). So when using this crate provide an allocator such as ``ruspiro_allocator``. The macros
+//! however stream the formatted output without allocating any memory, so printing works even if the allocator is
+//! under pressure.
 //!
 //! # Example
 //! To actually set an active output channel you need to provide a structure that implements the ``ConsoleImpl`` trait. This
@@ -31,8 +32,20 @@
 //!     fn puts(&self, s: &str) {
 //!         self.send_string(s);
 //!     }
+//!
+//!     fn input(&self) -> Option<&dyn ConsoleInput> {
+//!         Some(self)
+//!     }
+//! }
+//!
+//! impl ConsoleInput for Uart1 {
+//!     fn try_getc(&self) -> Option<char> {
+//!         self.try_receive_char()
+//!     }
 //! }
 //! ```
+//! Providing the ``input`` is optional. Only consoles that return their input side enable ``read_char`` to receive
+//! characters through the console.
 //!
 //! If this trait has been implemented this structure can be used as actual console. To use it there should be the following
 //! code written at the earliest possible point in the main crate of the binary (e.g. the kernel)
@@ -57,25 +70,345 @@ pub extern crate alloc;
 
 #[macro_use]
 pub mod macros;
-pub use macros::*;
 
-use alloc::boxed::Box;
+pub mod ansi;
+
+pub mod banner;
+pub use banner::*;
+
+pub mod buildinfo;
+pub use buildinfo::*;
+
+pub mod buffered;
+pub use buffered::{FORMAT_BUFFERS, FORMAT_BUFFER_SIZE};
+
+pub mod capture;
+pub use capture::*;
+
+pub mod chardisplay;
+pub use chardisplay::*;
+
+pub mod charset;
+pub use charset::*;
+
+pub mod cmdline;
+pub use cmdline::*;
+
+pub mod cobs;
+pub use cobs::*;
+
+pub mod config;
+pub use config::*;
+
+pub mod coreid;
+pub use coreid::*;
+
+pub mod deferred;
+pub use deferred::*;
+
+pub mod discipline;
+pub use discipline::LineDiscipline;
+use discipline::LineBuffer;
+
+pub mod dump;
+pub use dump::*;
+
+// the standard output replaces the early boot buffer with the std feature
+#[cfg_attr(feature = "std", allow(dead_code))]
+pub mod early;
+#[cfg(not(feature = "std"))]
+use early::EarlyBuffer;
+pub use early::EARLY_BUFFER_SIZE;
+
+pub mod editor;
+pub use editor::*;
+
+pub mod emergency;
+pub use emergency::*;
+
+pub mod eprint;
+pub use eprint::*;
+
+pub mod error;
+pub use error::*;
+
+pub mod errorchain;
+pub use errorchain::*;
+
+pub mod exclusive;
+pub use exclusive::*;
+use exclusive::{Locked, OutputLock};
+
+#[cfg(feature = "std")]
+pub mod host;
+#[cfg(feature = "std")]
+pub use host::*;
+
+pub mod input;
+pub use input::*;
+
+pub mod interned;
+pub use interned::*;
+
+#[cfg(feature = "embedded-io")]
+pub mod io;
+#[cfg(feature = "embedded-io")]
+pub use io::*;
+
+pub mod keys;
+pub use keys::*;
+
+pub mod filter;
+pub use filter::*;
+
+pub mod flow;
+pub use flow::{FLOW_INPUT_SIZE, XOFF, XON};
+use flow::FlowControl;
+
+pub mod format;
+pub use format::*;
+
+#[cfg(feature = "framebuffer")]
+pub mod font;
+#[cfg(feature = "framebuffer")]
+pub use font::{FONT_HEIGHT, FONT_WIDTH};
+
+#[cfg(feature = "framebuffer")]
+pub mod framebuffer;
+#[cfg(feature = "framebuffer")]
+pub use framebuffer::*;
+
+pub mod json;
+pub use json::*;
+
+pub mod level;
+pub use level::*;
+
+pub mod logext;
+pub use logext::*;
+
+pub mod mock;
+pub use mock::*;
+
+pub mod morse;
+pub use morse::*;
+
+pub mod mute;
+pub use mute::*;
+
+pub mod newline;
+pub use newline::*;
+
+pub mod pager;
+pub use pager::*;
+
+pub mod progress;
+pub use progress::*;
+
+#[cfg(feature = "qemu")]
+pub mod qemu;
+#[cfg(feature = "qemu")]
+pub use qemu::*;
+
+pub mod queue;
+pub use queue::{QUEUE_MESSAGE_SIZE, QUEUE_SLOTS};
+use queue::MessageQueue;
+
+#[cfg(all(feature = "panic-handler", not(any(test, doctest, feature = "std"))))]
+mod panic;
+
+pub mod raw;
+pub use raw::*;
+
+pub mod ramlog;
+pub use ramlog::*;
+
+pub mod registry;
+use registry::NamedConsole;
+
+pub mod repeat;
+use repeat::RepeatState;
+
+#[cfg(feature = "rtt")]
+pub mod rtt;
+#[cfg(feature = "rtt")]
+pub use rtt::*;
+
+pub mod reentrancy;
+pub use reentrancy::*;
+use reentrancy::ReentrancyGuard;
+
+pub mod record;
+pub use record::*;
+
+pub mod screen;
+pub use screen::*;
+
+#[cfg(feature = "semihosting")]
+pub mod semihosting;
+#[cfg(feature = "semihosting")]
+pub use semihosting::*;
+
+pub mod sequence;
+
+#[cfg(feature = "embedded-hal")]
+pub mod serial;
+#[cfg(feature = "embedded-hal")]
+pub use serial::*;
+
+pub mod shell;
+pub use shell::*;
+
+pub mod signal;
+pub use signal::*;
+
+pub mod span;
+pub use span::{Span, SPAN_INDENT};
+use span::SpanState;
+
+pub mod stack;
+pub use stack::*;
+
+pub mod status;
+pub use status::*;
+
+pub mod table;
+pub use table::*;
+
+pub mod tabs;
+pub use tabs::*;
+
+pub mod tap;
+pub use tap::*;
+
+pub mod terminal;
+pub use terminal::*;
+
+pub mod throttle;
+pub use throttle::*;
+
+pub mod timestamp;
+pub use timestamp::*;
+
+pub mod truncate;
+pub use truncate::ELLIPSIS;
+use truncate::LineLimit;
+
+#[cfg(feature = "ufmt")]
+pub mod uwrite;
+#[cfg(feature = "ufmt")]
+pub use uwrite::*;
+
+pub mod wrap;
+pub use wrap::*;
+
+#[cfg(feature = "log")]
+pub mod logger;
+#[cfg(feature = "log")]
+pub use logger::*;
+
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU64};
 use ruspiro_singleton::Singleton;
 
-/// Every "real" console need to implement this trait. Also the explicit Drop trait need to be implemented
-/// as the drop method of the implementing console will be called as soon as the actual console does release
-/// ownership of it
-pub trait ConsoleImpl: Drop {
+/// Every "real" console need to implement this trait. Once an additional sink or a routed output channel is removed
+/// the ``close`` method is called to allow the implementing console to free or reset its resources.
+pub trait ConsoleImpl {
     /// pass a single character to the output channel
     fn putc(&self, c: char);
     /// pass a string to the output channel
     fn puts(&self, s: &str);
+    /// pass a single character to the output channel and report whether this has been successful. The default
+    /// implementation uses ``putc`` and never fails
+    fn try_putc(&self, c: char) -> Result<(), ConsoleError> {
+        self.putc(c);
+        Ok(())
+    }
+    /// pass a string to the output channel and report whether this has been successful. The default implementation
+    /// uses ``puts`` and never fails
+    fn try_puts(&self, s: &str) -> Result<(), ConsoleError> {
+        self.puts(s);
+        Ok(())
+    }
+    /// pass a buffer of bytes to the output channel, e.g. to queue it for a DMA transfer at once. The default
+    /// implementation passes the valid UTF-8 parts with ``puts`` and replaces invalid bytes with U+FFFD, so consoles
+    /// that need to transfer binary data should override it
+    fn puts_bytes(&self, bytes: &[u8]) {
+        write_lossy(bytes, |s| self.puts(s));
+    }
+    /// pass several buffers of bytes to the output channel in one batch. The default implementation passes each
+    /// buffer with ``puts_bytes``
+    fn write_vectored(&self, bufs: &[&[u8]]) {
+        for bytes in bufs {
+            self.puts_bytes(bytes);
+        }
+    }
+    /// provide the input side of this console if it is able to receive characters. The default console has no input
+    fn input(&self) -> Option<&dyn ConsoleInput> {
+        None
+    }
+    /// provide the record side of this console if it is able to handle structured log records. If not, the records
+    /// are formatted to text and passed with ``puts``
+    fn records(&self) -> Option<&dyn RecordSink> {
+        None
+    }
+    /// ensure all buffered data has been written to the output channel. The default implementation does nothing as
+    /// unbuffered consoles have nothing to flush
+    fn flush(&self) {}
+    /// perform a bounded amount of buffered work without blocking, e.g. move as many buffered bytes to the transmit
+    /// FIFO as it is able to take. Called by ``Console::service``. The default implementation does nothing
+    fn poll(&self) {}
+    /// called when this console is removed as additional sink or routed output channel. A console handed back by
+    /// ``Console::replace`` or ``Console::take_current`` is closed by its new owner. The default implementation does
+    /// nothing
+    fn close(&mut self) {}
 }
 
+/// Serializes the unit tests changing the global console
+#[cfg(test)]
+pub(crate) static CONSOLE_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
 /// The Console singleton used by print! and println! macros
 pub static CONSOLE: Singleton<Console> = Singleton::<Console>::new(Console {
     current: None,
-    default: DefaultConsole {},
+    default: DefaultConsole::new(),
+    sinks: Vec::new(),
+    routes: Vec::new(),
+    level: Level::Info,
+    filter: None,
+    timestamp: None,
+    core_id: None,
+    color: false,
+    formatter: None,
+    location: cfg!(feature = "location"),
+    echo: false,
+    guard: ReentrancyGuard::new(),
+    interrupt: None,
+    queue: MessageQueue::new(),
+    queued: false,
+    newline: Newline::Raw,
+    last_cr: AtomicBool::new(false),
+    raw: false,
+    stack: Vec::new(),
+    registry: Vec::new(),
+    error: None,
+    suppress_repeats: false,
+    repeat: RepeatState::new(),
+    sequence: false,
+    next_sequence: AtomicU64::new(0),
+    output_lock: OutputLock::new(),
+    lock: None,
+    muted: AtomicBool::new(false),
+    status_rows: 0,
+    terminal: None,
+    break_handler: None,
+    flow: FlowControl::new(),
+    discipline: LineDiscipline::Raw,
+    lines: LineBuffer::new(),
+    line_limit: LineLimit::new(),
+    spans: SpanState::new(),
+    core_levels: [None; MAX_CORES],
 });
 
 /// The base printing function hidden behind the print! and println! macro. This function fowards all calls to the
@@ -83,14 +416,154 @@ pub static CONSOLE: Singleton<Console> = Singleton::<Console>::new(Console {
 pub fn print(s: &str) {
     // pass the string to the actual configured console to be printed
     CONSOLE.use_for(|console| {
-        console.get_current().puts(s);
+        console.puts(s);
+    });
+}
+
+/// Flush the current active console. This guarantees that buffered output has been written to the output channel
+/// before the kernel halts, reboots or enters a low-power state.
+pub fn console_flush() {
+    CONSOLE.use_for(|console| console.flush());
+}
+
+/// The allocation free printing function hidden behind the print! and println! macro. The formatted arguments are
+/// collected in a static buffer without formatting them into a heap allocated ``String``, the console is only held
+/// while the formatted text is written. Other cores wait until the whole message has been written.
+pub fn print_args(args: fmt::Arguments) {
+    if buffered::print_buffered(args) {
+        return;
+    }
+    // all buffers are in use, stream the message chunk by chunk while holding the console
+    CONSOLE.use_for(|console| {
+        // deferred output is queued as a whole message instead of chunk by chunk
+        if console.defers_output() {
+            console.defer(None, |w| w.write_fmt(args));
+        } else {
+            let _ = console.exclusive_message(|| fmt::write(&mut ConsoleWriter, args));
+        }
     });
 }
 
+/// The base logging function hidden behind the severity macros like info!. The record is passed to the console
+/// that formats it and passes it to the output channel its severity is routed to. Records below the log level or
+/// rejected by the target filter of the console are skipped.
+pub fn print_record(record: &LogRecord) {
+    CONSOLE.use_for(|console| {
+        if console.log_enabled(record.level(), record.target()) {
+            console.log(record);
+        }
+    });
+}
+
+/// The logging function hidden behind the fatal! macro. Other than ``print_record`` the record is passed to the
+/// console regardless of the log level and the target filter and the console is flushed afterwards. The record is
+/// never deferred, so it is written even in interrupt context.
+pub fn print_fatal(record: &LogRecord) {
+    CONSOLE.use_for(|console| {
+        console.urgent(|| {
+            console.poll();
+            console.write_record_direct(record);
+            console.flush_direct();
+        })
+    });
+}
+
+/// Log a message of the given severity and target without any key-value fields. See ``print_record`` for details.
+pub fn print_log(level: Level, target: &str, args: fmt::Arguments) {
+    print_record(&LogRecord::new(level, target, args, &[]));
+}
+
+/// The fallible printing function hidden behind the try_print! macro. Other than ``print`` this reports if the
+/// actual output channel failed to write the string.
+pub fn try_print(s: &str) -> Result<(), ConsoleError> {
+    CONSOLE.use_for(|console| console.try_puts(s))
+}
+
+/// The fallible counterpart of ``print_args``. The streaming stops at the first chunk the output channel failed
+/// to write and the error is returned.
+pub fn try_print_args(args: fmt::Arguments) -> Result<(), ConsoleError> {
+    struct TryWriter(Result<(), ConsoleError>);
+
+    impl fmt::Write for TryWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 = try_print(s);
+            self.0.map_err(|_| fmt::Error)
+        }
+    }
+
+    let mut writer = TryWriter(Ok(()));
+    let _ = CONSOLE.use_for(|console| console.exclusive_message(|| fmt::write(&mut writer, args)));
+    writer.0
+}
+
+/// A ``fmt::Write`` sink of the console. This allows to use ``write!`` and ``writeln!`` directly or to pass the
+/// console to any library that expects a ``fmt::Write`` implementation.
+/// ```ignore
+/// use core::fmt::Write;
+/// let _ = writeln!(ConsoleWriter, "the answer is {}", 42);
+/// ```
+pub struct ConsoleWriter;
+
+impl fmt::Write for ConsoleWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        print(s);
+        Ok(())
+    }
+}
+
+/// Pass the valid UTF-8 parts of the bytes to the output function, each sequence of invalid bytes is replaced with
+/// U+FFFD
+fn write_lossy(bytes: &[u8], mut output: impl FnMut(&str)) {
+    for chunk in bytes.utf8_chunks() {
+        if !chunk.valid().is_empty() {
+            output(chunk.valid());
+        }
+        if !chunk.invalid().is_empty() {
+            output("\u{FFFD}");
+        }
+    }
+}
+
 /// The representation of the abstract console
 pub struct Console {
     current: Option<Box<dyn ConsoleImpl>>,
     default: DefaultConsole,
+    sinks: Vec<Box<dyn ConsoleImpl>>,
+    routes: Vec<(Level, Box<dyn ConsoleImpl>)>,
+    level: Level,
+    filter: Option<Filter>,
+    timestamp: Option<Box<dyn TimestampSource>>,
+    core_id: Option<Box<dyn CoreIdProvider>>,
+    color: bool,
+    formatter: Option<Box<dyn LogFormatter>>,
+    location: bool,
+    echo: bool,
+    guard: ReentrancyGuard,
+    interrupt: Option<Box<dyn InterruptDetector>>,
+    queue: MessageQueue,
+    queued: bool,
+    newline: Newline,
+    last_cr: AtomicBool,
+    raw: bool,
+    stack: Vec<Option<Box<dyn ConsoleImpl>>>,
+    registry: Vec<NamedConsole>,
+    error: Option<Box<dyn ConsoleImpl>>,
+    suppress_repeats: bool,
+    repeat: RepeatState,
+    sequence: bool,
+    next_sequence: AtomicU64,
+    output_lock: OutputLock,
+    lock: Option<Box<dyn ConsoleLock>>,
+    muted: AtomicBool,
+    status_rows: usize,
+    terminal: Option<TerminalCaps>,
+    break_handler: Option<Box<dyn Fn()>>,
+    flow: FlowControl,
+    discipline: LineDiscipline,
+    lines: LineBuffer,
+    line_limit: LineLimit,
+    spans: SpanState,
+    core_levels: [Option<Level>; MAX_CORES],
 }
 
 impl Console {
@@ -103,29 +576,328 @@ impl Console {
         }
     }
 
-    /// Replacing the current active console. Once the new has been set the [drop] function of the previous one is
-    /// called. The Console takes ownership of the active once. Access to the active console outside the abstraction
-    /// is not possible and should not be.
-    pub fn replace<T: ConsoleImpl + 'static>(&mut self, console: T) {
-        self.current.replace(Box::from(console));
+    /// Pass a single character to the current active console and all additional sinks. Nested output of an output
+    /// channel that prints while it is writing is dropped, output in interrupt context or queued mode is deferred.
+    pub fn putc(&self, c: char) {
+        if self.defers_output() {
+            self.defer(None, |w| w.write_char(c));
+            return;
+        }
+        self.guarded(|| {
+            self.translated(c.encode_utf8(&mut [0; 4]), |part| {
+                for c in part.chars() {
+                    self.get_current().putc(c);
+                    for sink in self.sink_iter() {
+                        sink.putc(c);
+                    }
+                }
+            })
+        });
+    }
+
+    /// Pass a string to the current active console and all additional sinks. Nested output of an output channel
+    /// that prints while it is writing is dropped, output in interrupt context or queued mode is deferred.
+    pub fn puts(&self, s: &str) {
+        if self.defers_output() {
+            self.defer(None, |w| w.write_str(s));
+            return;
+        }
+        self.puts_direct(s);
+    }
+
+    /// pass the string to the output channels without deferring it, even in interrupt context
+    pub(crate) fn puts_direct(&self, s: &str) {
+        self.guarded(|| {
+            self.translated(s, |part| {
+                self.get_current().puts(part);
+                for sink in self.sink_iter() {
+                    sink.puts(part);
+                }
+            })
+        });
+    }
+
+    /// Pass a buffer of bytes to the current active console and all additional sinks with
+    /// ``ConsoleImpl::puts_bytes``. Deferred output is queued as text with invalid UTF-8 replaced by U+FFFD.
+    pub fn puts_bytes(&self, bytes: &[u8]) {
+        if self.defers_output() {
+            self.defer(None, |w| {
+                write_lossy(bytes, |s| {
+                    let _ = w.write_str(s);
+                });
+                Ok(())
+            });
+            return;
+        }
+        self.guarded(|| {
+            self.get_current().puts_bytes(bytes);
+            for sink in self.sink_iter() {
+                sink.puts_bytes(bytes);
+            }
+        });
+    }
+
+    /// Pass several buffers of bytes in one batch to the current active console and all additional sinks with
+    /// ``ConsoleImpl::write_vectored``. Deferred output is queued as text with invalid UTF-8 replaced by U+FFFD.
+    pub fn write_vectored(&self, bufs: &[&[u8]]) {
+        if self.defers_output() {
+            for bytes in bufs {
+                self.puts_bytes(bytes);
+            }
+            return;
+        }
+        self.guarded(|| {
+            self.get_current().write_vectored(bufs);
+            for sink in self.sink_iter() {
+                sink.write_vectored(bufs);
+            }
+        });
+    }
+
+    /// Pass binary data unmodified to the current active console only, e.g. frames of a binary protocol sharing the
+    /// Uart with the console. The data bypasses all text processing and the additional sinks, and it is never
+    /// deferred. Deferred output waiting in the queue is written before unless called in interrupt context.
+    pub fn put_raw(&self, bytes: &[u8]) {
+        self.poll();
+        self.guarded(|| self.get_current().puts_bytes(bytes));
+    }
+
+    /// Pass a string to the current active console and all additional sinks. The string is passed to every sink even
+    /// if one of them fails, the first error that occured is returned. Nested output of an output channel that prints
+    /// while it is writing fails with ``ConsoleError::Busy``, as does deferred output that did not fit into the queue.
+    pub fn try_puts(&self, s: &str) -> Result<(), ConsoleError> {
+        if self.defers_output() {
+            return if self.defer(None, |w| w.write_str(s)) {
+                Ok(())
+            } else {
+                Err(ConsoleError::Busy)
+            };
+        }
+        self.guarded(|| {
+            let mut result = Ok(());
+            self.translated(s, |part| {
+                for sink in core::iter::once(self.get_current()).chain(self.sink_iter()) {
+                    let sink_result = sink.try_puts(part);
+                    if result.is_ok() {
+                        result = sink_result;
+                    }
+                }
+            });
+            result
+        })
+        .unwrap_or(Err(ConsoleError::Busy))
+    }
+
+    /// Flush all buffered output of the current active console, all additional sinks and the error console to their
+    /// output channel.
+    /// Queued output is written first. Does nothing in interrupt context.
+    pub fn flush(&self) {
+        if self.in_interrupt() {
+            return;
+        }
+        self.flush_repeats();
+        self.poll();
+        self.flush_direct();
+    }
+
+    /// flush the output channels without writing the queued output first, even in interrupt context
+    pub(crate) fn flush_direct(&self) {
+        self.guarded(|| {
+            self.get_current().flush();
+            for sink in self.sink_iter() {
+                sink.flush();
+            }
+            if let Some(ref error) = self.error {
+                error.flush();
+            }
+        });
+    }
+
+    /// Attach an additional sink to the console. Every output is passed to the current active console first and
+    /// then to each sink in the order they have been added, e.g. to see the same output on the Uart and on the
+    /// screen. The Console takes ownership of the sink.
+    pub fn add_sink<T: ConsoleImpl + 'static>(&mut self, sink: T) {
+        self.sinks.push(Box::from(sink));
+    }
+
+    /// Remove all additional sinks from the console. Each of them is flushed and closed before it is dropped.
+    pub fn clear_sinks(&mut self) {
+        for mut sink in self.sinks.drain(..) {
+            sink.flush();
+            sink.close();
+        }
+    }
+
+    /// Replacing the current active console. The Console takes ownership of the new one. Once the new has been set
+    /// the previous one is flushed and handed back, so it could be restored later on. It is not closed, call its
+    /// ``close`` function if it is not restored.
+    /// Without the ``std`` feature the output printed while no console is attached is replayed to the new one.
+    pub fn replace<T: ConsoleImpl + 'static>(&mut self, console: T) -> Option<Box<dyn ConsoleImpl>> {
+        #[cfg(not(feature = "std"))]
+        if self.current.is_none() {
+            self.default.early.replay(&console);
+        }
+        let previous = self.current.replace(Box::from(console));
+        if let Some(ref previous) = previous {
+            previous.flush();
+        }
+        previous
+    }
+
+    /// Take the current active console back, e.g. to use the Uart for a different protocol for a while. The console
+    /// is flushed but not closed. Until a console is attached again the output is kept in the early boot buffer and
+    /// replayed once the console is restored with ``replace``:
+    /// ```ignore
+    /// let uart = CONSOLE.take_for(|cons| cons.take_current());
+    /// // ... use the uart directly
+    /// if let Some(uart) = uart {
+    ///     CONSOLE.take_for(|cons| cons.replace(uart));
+    /// }
+    /// ```
+    pub fn take_current(&mut self) -> Option<Box<dyn ConsoleImpl>> {
+        let current = self.current.take();
+        if let Some(ref current) = current {
+            current.flush();
+        }
+        current
     }
 }
 
-/// The default console is a kind of fall back that prints nothing...
-struct DefaultConsole;
+/// A boxed console is a console itself, so a console taken back with ``Console::take_current`` could be restored
+/// with ``Console::replace``.
+impl<T: ConsoleImpl + ?Sized> ConsoleImpl for Box<T> {
+    fn putc(&self, c: char) {
+        (**self).putc(c)
+    }
 
-impl ConsoleImpl for DefaultConsole {
-    fn putc(&self, _: char) {
-        // the default console does nothing as it is not linked to any hardware
+    fn puts(&self, s: &str) {
+        (**self).puts(s)
     }
 
-    fn puts(&self, _: &str) {
-        // the default console does nothing as it is not linked to any hardware
+    fn try_putc(&self, c: char) -> Result<(), ConsoleError> {
+        (**self).try_putc(c)
+    }
+
+    fn try_puts(&self, s: &str) -> Result<(), ConsoleError> {
+        (**self).try_puts(s)
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        (**self).puts_bytes(bytes)
+    }
+
+    fn write_vectored(&self, bufs: &[&[u8]]) {
+        (**self).write_vectored(bufs)
+    }
+
+    fn input(&self) -> Option<&dyn ConsoleInput> {
+        (**self).input()
+    }
+
+    fn records(&self) -> Option<&dyn RecordSink> {
+        (**self).records()
+    }
+
+    fn flush(&self) {
+        (**self).flush()
+    }
+
+    fn poll(&self) {
+        (**self).poll()
+    }
+
+    fn close(&mut self) {
+        (**self).close()
     }
 }
 
-impl Drop for DefaultConsole {
-    fn drop(&mut self) {
-        // the default console has no resources that need to be freed while dropping
+/// A shared console is a console itself, so an output channel like the Uart could be passed to the console and still
+/// be used directly by other parts of the kernel, e.g. a firmware updater:
+/// ```ignore
+/// let uart = Arc::new(Uart::new());
+/// CONSOLE.take_for(|cons| cons.replace(uart.clone()));
+/// ```
+/// The output channel needs to synchronize the concurrent access itself. Closing a shared console only closes the
+/// output channel if there are no other references left.
+impl<T: ConsoleImpl + ?Sized> ConsoleImpl for Arc<T> {
+    fn putc(&self, c: char) {
+        (**self).putc(c)
+    }
+
+    fn puts(&self, s: &str) {
+        (**self).puts(s)
+    }
+
+    fn try_putc(&self, c: char) -> Result<(), ConsoleError> {
+        (**self).try_putc(c)
+    }
+
+    fn try_puts(&self, s: &str) -> Result<(), ConsoleError> {
+        (**self).try_puts(s)
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        (**self).puts_bytes(bytes)
+    }
+
+    fn write_vectored(&self, bufs: &[&[u8]]) {
+        (**self).write_vectored(bufs)
+    }
+
+    fn input(&self) -> Option<&dyn ConsoleInput> {
+        (**self).input()
+    }
+
+    fn records(&self) -> Option<&dyn RecordSink> {
+        (**self).records()
+    }
+
+    fn flush(&self) {
+        (**self).flush()
+    }
+
+    fn poll(&self) {
+        (**self).poll()
+    }
+
+    fn close(&mut self) {
+        if let Some(console) = Arc::get_mut(self) {
+            console.close()
+        }
+    }
+}
+
+/// The default console is a kind of fall back that is not linked to any hardware. It keeps the output in the early
+/// boot buffer until the first real console is attached. With the ``std`` feature it writes to the standard output
+/// instead.
+struct DefaultConsole {
+    #[cfg(not(feature = "std"))]
+    early: EarlyBuffer,
+}
+
+impl DefaultConsole {
+    const fn new() -> Self {
+        DefaultConsole {
+            #[cfg(not(feature = "std"))]
+            early: EarlyBuffer::new(),
+        }
+    }
+}
+
+impl ConsoleImpl for DefaultConsole {
+    fn putc(&self, c: char) {
+        self.puts(c.encode_utf8(&mut [0; 4]));
+    }
+
+    fn puts(&self, s: &str) {
+        #[cfg(feature = "std")]
+        StdConsole.puts(s);
+        #[cfg(not(feature = "std"))]
+        self.early.write(s);
+    }
+
+    #[cfg(feature = "std")]
+    fn flush(&self) {
+        StdConsole.flush();
     }
 }
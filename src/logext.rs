@@ -0,0 +1,90 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Logging extensions
+//!
+//! The initialization of a driver is a chain of fallible steps where each failure should end up in the log. The
+//! ``LogResult`` and ``LogOption`` traits log the error or the missing value with the given target through the console
+//! and pass the value through unchanged:
+//! ```ignore
+//! let clock = mailbox.clock_rate(Clock::Emmc).log_err(module_path!(), "emmc clock rate")?;
+//! let node = devicetree.find("/soc/gpio").log_warn_none("gpio", "missing gpio node");
+//! ```
+//! The error is logged with its ``Debug`` representation behind the context like ``E: sdhci - emmc clock rate:
+//! Timeout``. The target filter applies like for the ``log!`` macro and the records carry the location of the caller
+//! if the ``location`` feature is enabled.
+
+use crate::{Level, LogRecord};
+use core::fmt;
+
+/// Log a message of the given severity on behalf of the caller
+#[track_caller]
+fn log_caller(level: Level, target: &str, args: fmt::Arguments) {
+    if crate::static_enabled(level) && crate::log_enabled(level, target) {
+        let record = LogRecord::new(level, target, args, &[]);
+        #[cfg(feature = "location")]
+        let record = {
+            let location = core::panic::Location::caller();
+            record.with_location(location.file(), location.line())
+        };
+        crate::print_record(&record);
+    }
+}
+
+/// Log the error of a ``Result`` and pass it through
+pub trait LogResult: Sized {
+    /// Log the error at ``Level::Error`` with the given target behind the context if this is an ``Err``
+    fn log_err(self, target: &str, context: &str) -> Self;
+
+    /// Log the error at ``Level::Warn`` with the given target behind the context if this is an ``Err``
+    fn log_warn(self, target: &str, context: &str) -> Self;
+}
+
+impl<T, E: fmt::Debug> LogResult for Result<T, E> {
+    #[track_caller]
+    fn log_err(self, target: &str, context: &str) -> Self {
+        if let Err(ref e) = self {
+            log_caller(Level::Error, target, format_args!("{}: {:?}", context, e));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn log_warn(self, target: &str, context: &str) -> Self {
+        if let Err(ref e) = self {
+            log_caller(Level::Warn, target, format_args!("{}: {:?}", context, e));
+        }
+        self
+    }
+}
+
+/// Log the absence of the value of an ``Option`` and pass it through
+pub trait LogOption: Sized {
+    /// Log the message with the given target at ``Level::Error`` if this is ``None``
+    fn log_err_none(self, target: &str, message: &str) -> Self;
+
+    /// Log the message with the given target at ``Level::Warn`` if this is ``None``
+    fn log_warn_none(self, target: &str, message: &str) -> Self;
+}
+
+impl<T> LogOption for Option<T> {
+    #[track_caller]
+    fn log_err_none(self, target: &str, message: &str) -> Self {
+        if self.is_none() {
+            log_caller(Level::Error, target, format_args!("{}", message));
+        }
+        self
+    }
+
+    #[track_caller]
+    fn log_warn_none(self, target: &str, message: &str) -> Self {
+        if self.is_none() {
+            log_caller(Level::Warn, target, format_args!("{}", message));
+        }
+        self
+    }
+}
@@ -0,0 +1,70 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # ``log`` crate backend
+//!
+//! Available with the ``log`` feature. Registers the console as the global logger of the ``log`` crate, so drivers
+//! written against the ``log`` macros transparently print through the ``CONSOLE``. The records pass the log level,
+//! the target filter and the routing of the console like the messages of the severity macros.
+//! ```ignore
+//! use ruspiro_console::*;
+//!
+//! fn main() {
+//!     init_logger(log::LevelFilter::Info).expect("logger already set");
+//!     log::info!("this is printed through the console");
+//! }
+//! ```
+
+use crate::{Level, LogRecord};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// The logger passing all records of the ``log`` crate to the console
+pub struct ConsoleLogger;
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+            && crate::log_enabled(from_log_level(metadata.level()), metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        // print_record applies the log level and the filter of the console
+        if record.level() <= log::max_level() {
+            let mut console_record =
+                LogRecord::new(from_log_level(record.level()), record.target(), *record.args(), &[]);
+            if let (Some(file), Some(line)) = (record.file(), record.line()) {
+                console_record = console_record.with_location(file, line);
+            }
+            crate::print_record(&console_record);
+        }
+    }
+
+    fn flush(&self) {
+        crate::console_flush();
+    }
+}
+
+/// map the level of the ``log`` crate to the severity of the console
+fn from_log_level(level: log::Level) -> Level {
+    match level {
+        log::Level::Error => Level::Error,
+        log::Level::Warn => Level::Warn,
+        log::Level::Info => Level::Info,
+        log::Level::Debug => Level::Debug,
+        log::Level::Trace => Level::Trace,
+    }
+}
+
+/// Register the console as the global logger of the ``log`` crate. Only records up to the given level are passed
+/// to the console. This fails if a logger has already been set.
+pub fn init_logger(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(level);
+    Ok(())
+}
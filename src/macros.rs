@@ -7,16 +7,21 @@
 
 //! # Convinient output macros to print formatted strings to the configured channel of the console
 //!
-//! Provide the print!() and println!() macro's as used in the libstd crate which is not available here
-//! as we do need formatting on the parameter and formatting requires memory allocation the
-//! use of this functions is only possible if a global allocator is implemented.<br>
-//! You may use the ``ruspiro-allocator`` crate.
+//! Provide the print!() and println!() macro's as used in the libstd crate which is not available here.
+//! The formatted output is streamed chunk by chunk to the console using ``print_args``, so the macros do not need
+//! any memory allocation.
 
 /// This macro works like the ``std::print!`` one.
 #[macro_export]
 macro_rules! print {
-    //$crate::macros::alloc::
-    ($($arg:tt)*) => ($crate::print($crate::alloc::format!($($arg)*).as_str()));
+    ($($arg:tt)*) => ($crate::print_args(format_args!($($arg)*)));
+}
+
+/// This macro works like the ``print!`` one but returns a ``Result`` that reports if the output channel failed to
+/// write the formatted string.
+#[macro_export]
+macro_rules! try_print {
+    ($($arg:tt)*) => ($crate::try_print_args(format_args!($($arg)*)));
 }
 
 /// This macro works like the ``std::println!`` one
@@ -24,30 +29,446 @@ macro_rules! print {
 macro_rules! println {
     () => ($crate::print!("\r\n"));
     ($($arg:tt)*) => ({
-        $crate::print!("{}\r\n", $crate::alloc::format!($($arg)*));
+        $crate::print!("{}\r\n", format_args!($($arg)*));
     })
 }
 
-/// This macro prefixes the output with "I: &lt;module-path&gt; -". Other than this it works like the ``std::println!``
+/// This macro works like the ``print!`` one but formats with ``ufmt`` instead of ``core::fmt``
+#[cfg(feature = "ufmt")]
 #[macro_export]
-macro_rules! info {
+macro_rules! uprint {
+    ($($arg:tt)*) => ($crate::uprint_with(|w| ufmt::uwrite!(w, $($arg)*)));
+}
+
+/// This macro works like the ``println!`` one but formats with ``ufmt`` instead of ``core::fmt``
+#[cfg(feature = "ufmt")]
+#[macro_export]
+macro_rules! uprintln {
+    () => ($crate::print("\r\n"));
+    ($($arg:tt)*) => ($crate::uprint_with(|w| {
+        ufmt::uwrite!(w, $($arg)*)?;
+        ufmt::uWrite::write_str(w, "\r\n")
+    }));
+}
+
+/// This macro works like the ``std::eprint!`` one. The output is passed to the error console of the console, or
+/// like the output of ``print!`` if there is none.
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => ($crate::eprint_args(format_args!($($arg)*)));
+}
+
+/// This macro works like the ``std::eprintln!`` one. The output is passed to the error console of the console, or
+/// like the output of ``println!`` if there is none.
+#[macro_export]
+macro_rules! eprintln {
+    () => ($crate::eprint!("\r\n"));
     ($($arg:tt)*) => ({
-        $crate::print!("I: {} - {}\r\n", module_path!(), $crate::alloc::format!($($arg)*));
+        $crate::eprint!("{}\r\n", format_args!($($arg)*));
     })
 }
 
+/// This macro prefixes the output with "I: &lt;module-path&gt; -". Other than this it works like the ``std::println!``
+/// but accepts a target and key-value fields in front of the message, see ``log!``.
+/// The message is skipped if the log level of the console is below ``Level::Info`` and removed at compile time if a
+/// ``max-level-*`` feature below ``Level::Info`` is set
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => ($crate::log!($crate::Level::Info, $($arg)+));
+}
+
 /// This macro prefixes the output with "W: &lt;module-path&gt; -". Other than this it works like the ``std::println!``
+/// but accepts a target and key-value fields in front of the message, see ``log!``.
+/// The message is skipped if the log level of the console is below ``Level::Warn`` and removed at compile time if a
+/// ``max-level-*`` feature below ``Level::Warn`` is set
 #[macro_export]
 macro_rules! warn {
-    ($($arg:tt)*) => ({
-        $crate::print!("W: {} - {}\r\n", module_path!(), $crate::alloc::format!($($arg)*));
-    })
+    ($($arg:tt)+) => ($crate::log!($crate::Level::Warn, $($arg)+));
 }
 
 /// This macro prefixes the output with "E: &lt;module-path&gt; -". Other than this it works like the ``std::println!``
+/// but accepts a target and key-value fields in front of the message, see ``log!``.
+/// The message is removed at compile time if the ``max-level-off`` feature is set
 #[macro_export]
 macro_rules! error {
-    ($($arg:tt)*) => ({
-        $crate::print!("E: {} - {}\r\n", module_path!(), $crate::alloc::format!($($arg)*));
-    })
+    ($($arg:tt)+) => ($crate::log!($crate::Level::Error, $($arg)+));
+}
+
+/// This macro logs an error message like ``error!`` and panics with the same message afterwards. The message is
+/// printed regardless of the log level, the target filter and the ``max-level-*`` features and the console is
+/// flushed before the panic, so unrecoverable conditions are guaranteed to hit the wire before the panic handler runs
+/// ```ignore
+/// let base = mailbox.framebuffer().unwrap_or_else(|e| fatal!("no framebuffer: {:?}", e));
+/// ```
+#[macro_export]
+macro_rules! fatal {
+    ($($arg:tt)+) => (
+        match format_args!($($arg)+) {
+            args => {
+                $crate::print_fatal(&$crate::__location!($crate::LogRecord::new(
+                    $crate::Level::Error,
+                    module_path!(),
+                    args,
+                    &[],
+                )));
+                panic!("{}", args)
+            }
+        }
+    );
+}
+
+/// This macro prefixes the output with "D: &lt;module-path&gt; -". Other than this it works like the ``std::println!``
+/// but accepts a target and key-value fields in front of the message, see ``log!``.
+/// The message is skipped if the log level of the console is below ``Level::Debug`` and removed at compile time if a
+/// ``max-level-*`` feature below ``Level::Debug`` is set
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => ($crate::log!($crate::Level::Debug, $($arg)+));
+}
+
+/// This macro prefixes the output with "T: &lt;module-path&gt; -". Other than this it works like the ``std::println!``
+/// but accepts a target and key-value fields in front of the message, see ``log!``.
+/// The message is skipped if the log level of the console is below ``Level::Trace`` and removed at compile time if a
+/// ``max-level-*`` feature below ``Level::Trace`` is set
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => ($crate::log!($crate::Level::Trace, $($arg)+));
+}
+
+/// This macro replaces the content of the status line reserved with ``Console::set_status_line``
+/// ```ignore
+/// status!("uptime {}s", uptime);
+/// ```
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => ($crate::print_status(format_args!($($arg)*)));
+}
+
+/// This macro works like the ``std::dbg!`` one. It prints the source location, the expression and its ``Debug``
+/// value with ``eprintln!`` and returns the value:
+/// ```ignore
+/// let a = dbg!(2 * 21); // prints "[src/main.rs:10] 2 * 21 = 42"
+/// ```
+#[macro_export]
+macro_rules! dbg {
+    () => ($crate::eprintln!("[{}:{}]", file!(), line!()));
+    ($val:expr $(,)?) => (
+        match $val {
+            tmp => {
+                $crate::eprintln!("[{}:{}] {} = {:#?}", file!(), line!(), stringify!($val), &tmp);
+                tmp
+            }
+        }
+    );
+    ($($val:expr),+ $(,)?) => (($($crate::dbg!($val)),+,));
+}
+
+/// This macro prints a hex dump of the given slice with 16 bytes per row and an ASCII column through the console.
+/// The rows are labeled with their offset:
+/// ```ignore
+/// hexdump!(&buffer[..]);
+/// ```
+/// Raw memory regions are dumped with the unsafe ``hexdump_raw`` that labels the rows with their address.
+#[macro_export]
+macro_rules! hexdump {
+    ($data:expr) => ($crate::hexdump(0, &$data[..]));
+}
+
+/// This macro passes a message of the given severity to the console. Key-value fields could be given in front of the
+/// message. A field given as ``key = value`` or ``key = %value`` is formatted with ``Display``, ``key = ?value`` with
+/// ``Debug``:
+/// ```ignore
+/// log!(Level::Info, addr = %ptr, len = len, "mapped region");
+/// ```
+/// The target of the message defaults to the module path. A different one, e.g. the name of a component, could be
+/// given with ``target:`` in front of the level or the fields. Filters and formatters see it like a module path:
+/// ```ignore
+/// log!(target: "sdhci", Level::Info, "card detected");
+/// info!(target: "sdhci", "card detected");
+/// ```
+/// The severity macros like ``info!`` are shortcuts of this macro.
+#[macro_export]
+macro_rules! log {
+    (@fields $target:expr, $level:expr, [$($fields:tt)*] $key:ident = % $value:expr, $($rest:tt)+) => (
+        $crate::log!(@fields $target, $level, [$($fields)* ($key, "{}", $value)] $($rest)+)
+    );
+    (@fields $target:expr, $level:expr, [$($fields:tt)*] $key:ident = ? $value:expr, $($rest:tt)+) => (
+        $crate::log!(@fields $target, $level, [$($fields)* ($key, "{:?}", $value)] $($rest)+)
+    );
+    (@fields $target:expr, $level:expr, [$($fields:tt)*] $key:ident = $value:expr, $($rest:tt)+) => (
+        $crate::log!(@fields $target, $level, [$($fields)* ($key, "{}", $value)] $($rest)+)
+    );
+    (@fields $target:expr, $level:expr, [$(($key:ident, $fmt:literal, $value:expr))*] $($arg:tt)+) => ({
+        let level = $level;
+        let target = $target;
+        if $crate::static_enabled(level) && $crate::log_enabled(level, target) {
+            $crate::print_record(&$crate::__location!($crate::LogRecord::new(
+                level,
+                target,
+                format_args!($($arg)+),
+                &[$($crate::Field::new(stringify!($key), format_args!($fmt, $value))),*],
+            )));
+        }
+    });
+    (target: $target:expr, $level:expr, $($arg:tt)+) => ($crate::log!(@fields $target, $level, [] $($arg)+));
+    ($level:expr, target: $target:expr, $($arg:tt)+) => ($crate::log!(@fields $target, $level, [] $($arg)+));
+    ($level:expr, $($arg:tt)+) => ($crate::log!(@fields module_path!(), $level, [] $($arg)+));
+}
+
+/// This macro works like the ``log!`` one but skips the message if the same call site already passed a message
+/// within the last ``interval_ms`` milliseconds. The interval is measured with the timestamp source of the console.
+/// Messages skipped because their severity is disabled for the target do not count:
+/// ```ignore
+/// log_throttled!(Level::Warn, 1000, "device not ready");
+/// warn_throttled!(1000, target: "sdhci", "card not ready");
+/// ```
+#[macro_export]
+macro_rules! log_throttled {
+    (@throttle $target:expr, $level:expr, $interval_ms:expr, $($arg:tt)+) => ({
+        static THROTTLE: $crate::Throttle = $crate::Throttle::new();
+        let level = $level;
+        let target = $target;
+        if $crate::static_enabled(level) && $crate::log_enabled(level, target) && THROTTLE.pass($interval_ms) {
+            $crate::log!(target: target, level, $($arg)+);
+        }
+    });
+    (target: $target:expr, $level:expr, $interval_ms:expr, $($arg:tt)+) => (
+        $crate::log_throttled!(@throttle $target, $level, $interval_ms, $($arg)+)
+    );
+    ($level:expr, $interval_ms:expr, target: $target:expr, $($arg:tt)+) => (
+        $crate::log_throttled!(@throttle $target, $level, $interval_ms, $($arg)+)
+    );
+    ($level:expr, $interval_ms:expr, $($arg:tt)+) => (
+        $crate::log_throttled!(@throttle module_path!(), $level, $interval_ms, $($arg)+)
+    );
+}
+
+/// This macro works like the ``error!`` one but skips the message if the same call site already passed a message
+/// within the last ``interval_ms`` milliseconds, see ``log_throttled!``.
+#[macro_export]
+macro_rules! error_throttled {
+    ($interval_ms:expr, $($arg:tt)+) => ($crate::log_throttled!($crate::Level::Error, $interval_ms, $($arg)+));
+}
+
+/// This macro works like the ``warn!`` one but skips the message if the same call site already passed a message
+/// within the last ``interval_ms`` milliseconds, see ``log_throttled!``.
+#[macro_export]
+macro_rules! warn_throttled {
+    ($interval_ms:expr, $($arg:tt)+) => ($crate::log_throttled!($crate::Level::Warn, $interval_ms, $($arg)+));
+}
+
+/// This macro works like the ``info!`` one but skips the message if the same call site already passed a message
+/// within the last ``interval_ms`` milliseconds, see ``log_throttled!``.
+#[macro_export]
+macro_rules! info_throttled {
+    ($interval_ms:expr, $($arg:tt)+) => ($crate::log_throttled!($crate::Level::Info, $interval_ms, $($arg)+));
+}
+
+/// This macro works like the ``debug!`` one but skips the message if the same call site already passed a message
+/// within the last ``interval_ms`` milliseconds, see ``log_throttled!``.
+#[macro_export]
+macro_rules! debug_throttled {
+    ($interval_ms:expr, $($arg:tt)+) => ($crate::log_throttled!($crate::Level::Debug, $interval_ms, $($arg)+));
+}
+
+/// This macro works like the ``trace!`` one but skips the message if the same call site already passed a message
+/// within the last ``interval_ms`` milliseconds, see ``log_throttled!``.
+#[macro_export]
+macro_rules! trace_throttled {
+    ($interval_ms:expr, $($arg:tt)+) => ($crate::log_throttled!($crate::Level::Trace, $interval_ms, $($arg)+));
+}
+
+/// This macro works like the ``println!`` one but prints the message only the first time this call site is executed
+#[macro_export]
+macro_rules! once {
+    ($($arg:tt)+) => ({
+        static ONCE: ::core::sync::atomic::AtomicBool = ::core::sync::atomic::AtomicBool::new(false);
+        if !ONCE.swap(true, ::core::sync::atomic::Ordering::Relaxed) {
+            $crate::println!($($arg)+);
+        }
+    });
+}
+
+/// This macro works like the ``log!`` one but passes the message only the first time this call site is executed
+/// with the severity enabled for its target, so recoverable but noisy conditions are reported exactly once:
+/// ```ignore
+/// log_once!(Level::Warn, "falling back to polling mode");
+/// warn_once!(target: "sdhci", "falling back to polling mode");
+/// ```
+#[macro_export]
+macro_rules! log_once {
+    (@once $target:expr, $level:expr, $($arg:tt)+) => ({
+        static ONCE: ::core::sync::atomic::AtomicBool = ::core::sync::atomic::AtomicBool::new(false);
+        let level = $level;
+        let target = $target;
+        if $crate::static_enabled(level)
+            && $crate::log_enabled(level, target)
+            && !ONCE.swap(true, ::core::sync::atomic::Ordering::Relaxed)
+        {
+            $crate::log!(target: target, level, $($arg)+);
+        }
+    });
+    (target: $target:expr, $level:expr, $($arg:tt)+) => ($crate::log_once!(@once $target, $level, $($arg)+));
+    ($level:expr, target: $target:expr, $($arg:tt)+) => ($crate::log_once!(@once $target, $level, $($arg)+));
+    ($level:expr, $($arg:tt)+) => ($crate::log_once!(@once module_path!(), $level, $($arg)+));
+}
+
+/// This macro works like the ``error!`` one but passes the message only the first time this call site is executed,
+/// see ``log_once!``.
+#[macro_export]
+macro_rules! error_once {
+    ($($arg:tt)+) => ($crate::log_once!($crate::Level::Error, $($arg)+));
+}
+
+/// This macro works like the ``warn!`` one but passes the message only the first time this call site is executed,
+/// see ``log_once!``.
+#[macro_export]
+macro_rules! warn_once {
+    ($($arg:tt)+) => ($crate::log_once!($crate::Level::Warn, $($arg)+));
+}
+
+/// This macro works like the ``info!`` one but passes the message only the first time this call site is executed,
+/// see ``log_once!``.
+#[macro_export]
+macro_rules! info_once {
+    ($($arg:tt)+) => ($crate::log_once!($crate::Level::Info, $($arg)+));
+}
+
+/// This macro works like the ``debug!`` one but passes the message only the first time this call site is executed,
+/// see ``log_once!``.
+#[macro_export]
+macro_rules! debug_once {
+    ($($arg:tt)+) => ($crate::log_once!($crate::Level::Debug, $($arg)+));
+}
+
+/// This macro works like the ``trace!`` one but passes the message only the first time this call site is executed,
+/// see ``log_once!``.
+#[macro_export]
+macro_rules! trace_once {
+    ($($arg:tt)+) => ($crate::log_once!($crate::Level::Trace, $($arg)+));
+}
+
+/// This macro works like the ``log!`` one but sends the address of the format string stored in the binary and the
+/// raw arguments instead of the formatted message. The host formats the message, see the ``interned`` module:
+/// ```ignore
+/// log_interned!(Level::Info, "irq {} handled in {}us", irq, elapsed);
+/// ```
+#[macro_export]
+macro_rules! log_interned {
+    ($level:expr, $fmt:literal $(, $arg:expr)* $(,)?) => ({
+        let level = $level;
+        if $crate::static_enabled(level) && $crate::log_enabled(level, module_path!()) {
+            const STRING: &str = concat!(module_path!(), "\0", $fmt);
+            #[link_section = ".console_strings"]
+            #[used]
+            static INTERNED: [u8; STRING.len()] = $crate::intern(STRING);
+            let mut frame = $crate::InternedFrame::new(INTERNED.as_ptr() as usize, level);
+            $($crate::InternedArg::encode(&$arg, &mut frame);)*
+            $crate::print_interned(&frame);
+        }
+    });
+}
+
+/// This macro works like the ``error!`` one but sends the message interned, see ``log_interned!``.
+#[macro_export]
+macro_rules! error_interned {
+    ($($arg:tt)+) => ($crate::log_interned!($crate::Level::Error, $($arg)+));
+}
+
+/// This macro works like the ``warn!`` one but sends the message interned, see ``log_interned!``.
+#[macro_export]
+macro_rules! warn_interned {
+    ($($arg:tt)+) => ($crate::log_interned!($crate::Level::Warn, $($arg)+));
+}
+
+/// This macro works like the ``info!`` one but sends the message interned, see ``log_interned!``.
+#[macro_export]
+macro_rules! info_interned {
+    ($($arg:tt)+) => ($crate::log_interned!($crate::Level::Info, $($arg)+));
+}
+
+/// This macro works like the ``debug!`` one but sends the message interned, see ``log_interned!``.
+#[macro_export]
+macro_rules! debug_interned {
+    ($($arg:tt)+) => ($crate::log_interned!($crate::Level::Debug, $($arg)+));
+}
+
+/// This macro works like the ``trace!`` one but sends the message interned, see ``log_interned!``.
+#[macro_export]
+macro_rules! trace_interned {
+    ($($arg:tt)+) => ($crate::log_interned!($crate::Level::Trace, $($arg)+));
+}
+
+/// This macro runs the given block and logs the time it took with ``info!``. It returns the value of the block:
+/// ```ignore
+/// let sectors = measure!("sd card read", { sdcard.read(0, &mut buffer) }); // logs "sd card read took 1.234ms"
+/// ```
+/// The time is measured with the timestamp source of the console. Without a timestamp source nothing is logged.
+#[macro_export]
+macro_rules! measure {
+    ($label:expr, $body:block) => ({
+        let start = $crate::timestamp();
+        let result = $body;
+        if let (Some(start), Some(end)) = (start, $crate::timestamp()) {
+            $crate::info!("{} took {:?}", $label, end.saturating_sub(start));
+        }
+        result
+    });
+}
+
+/// This macro is a shortcut of the ``measure!`` one
+#[macro_export]
+macro_rules! time {
+    ($($arg:tt)+) => ($crate::measure!($($arg)+));
+}
+
+/// This macro prints the boot banner with the name and the version of the calling crate. The git hash and the
+/// build time are taken from the ``GIT_HASH`` and ``BUILD_TIMESTAMP`` environment variables at compile time if they
+/// are set. The board the kernel is running on could be given:
+/// ```ignore
+/// banner!("Raspberry Pi 3 Model B+");
+/// ```
+#[macro_export]
+macro_rules! banner {
+    () => ($crate::banner!(@board None));
+    (@board $board:expr) => (
+        $crate::Banner::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .git_hash(option_env!("GIT_HASH"))
+            .build_time(option_env!("BUILD_TIMESTAMP"))
+            .board($board)
+            .print()
+    );
+    ($board:expr) => ($crate::banner!(@board Some($board)));
+}
+
+/// This macro runs the given block inside a span: the output of the block is indented and enclosed in enter and exit
+/// markers with the name and the id of the span. The exit marker tells the time the block took if there is a
+/// timestamp source. It returns the value of the block:
+/// ```ignore
+/// span!("mmu init", { mmu.init() });
+/// ```
+#[macro_export]
+macro_rules! span {
+    ($name:expr, $body:block) => ({
+        let _span = $crate::Span::enter($name);
+        $body
+    });
+}
+
+/// Attach the source location to a log record if the ``location`` feature is enabled. Otherwise the record is passed
+/// as is and no file names end up in the binary.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "location")]
+macro_rules! __location {
+    ($record:expr) => ($record.with_location(file!(), line!()));
+}
+
+/// Attach the source location to a log record if the ``location`` feature is enabled. Otherwise the record is passed
+/// as is and no file names end up in the binary.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "location"))]
+macro_rules! __location {
+    ($record:expr) => ($record);
 }
@@ -0,0 +1,170 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Mock console
+//!
+//! Other than the ``CaptureConsole`` the ``MockConsole`` records every single call of the console, so tests are
+//! able to check how their output reaches the output channel, e.g. whether a message is passed with a single
+//! ``puts`` or the console has been flushed at the right time. All clones of a ``MockConsole`` share the recorded
+//! calls:
+//! ```ignore
+//! let mock = MockConsole::new();
+//! with_console(mock.clone(), || driver.initialize());
+//! mock.assert_received("boot complete");
+//! mock.assert_received_in_order(&["reset", "ready"]);
+//! assert_eq!(mock.calls().last(), Some(&ConsoleCall::Close));
+//! ```
+//! The ``assert_*`` functions panic with the output received so far if the expectation is not met.
+
+use crate::{write_lossy, ConsoleImpl, Locked};
+use alloc::{string::String, sync::Arc, vec::Vec};
+
+/// A call of the ``MockConsole`` recorded in the order it has been made
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsoleCall {
+    /// ``putc`` with the character passed
+    Putc(char),
+    /// ``puts`` with the string passed
+    Puts(String),
+    /// ``puts_bytes`` with the bytes passed
+    PutsBytes(Vec<u8>),
+    /// ``flush``
+    Flush,
+    /// ``poll``
+    Poll,
+    /// ``close``
+    Close,
+}
+
+/// The output channel recording all calls for later checks
+#[derive(Clone)]
+pub struct MockConsole {
+    /// the calls shared by all clones
+    calls: Arc<Locked<Vec<ConsoleCall>>>,
+}
+
+impl MockConsole {
+    /// Create the console without any recorded calls
+    pub fn new() -> Self {
+        MockConsole {
+            calls: Arc::new(Locked::new(Vec::new())),
+        }
+    }
+
+    /// A copy of all calls recorded so far
+    pub fn calls(&self) -> Vec<ConsoleCall> {
+        self.calls.with(|calls| calls.clone())
+    }
+
+    /// Forget all calls recorded so far
+    pub fn clear(&self) {
+        self.calls.with(|calls| calls.clear());
+    }
+
+    /// The text passed with all recorded ``putc``, ``puts`` and ``puts_bytes`` calls. Invalid UTF-8 is replaced
+    /// with ``U+FFFD``.
+    pub fn output(&self) -> String {
+        self.calls.with(|calls| {
+            let mut output = String::new();
+            for call in calls.iter() {
+                match call {
+                    ConsoleCall::Putc(c) => output.push(*c),
+                    ConsoleCall::Puts(s) => output.push_str(s),
+                    ConsoleCall::PutsBytes(bytes) => write_lossy(bytes, |s| output.push_str(s)),
+                    _ => (),
+                }
+            }
+            output
+        })
+    }
+
+    /// Check if the output received so far contains the given text
+    pub fn received(&self, pattern: &str) -> bool {
+        self.output().contains(pattern)
+    }
+
+    /// The number of times the console has been flushed
+    pub fn flush_count(&self) -> usize {
+        self.calls.with(|calls| {
+            calls
+                .iter()
+                .filter(|call| **call == ConsoleCall::Flush)
+                .count()
+        })
+    }
+
+    /// Panic if the output received so far does not contain the given text
+    #[track_caller]
+    pub fn assert_received(&self, pattern: &str) {
+        let output = self.output();
+        if !output.contains(pattern) {
+            panic!("expected {:?} in console output {:?}", pattern, output);
+        }
+    }
+
+    /// Panic if the output received so far contains the given text
+    #[track_caller]
+    pub fn assert_not_received(&self, pattern: &str) {
+        let output = self.output();
+        if output.contains(pattern) {
+            panic!("unexpected {:?} in console output {:?}", pattern, output);
+        }
+    }
+
+    /// Panic if the output received so far does not contain all the given texts in the given order
+    #[track_caller]
+    pub fn assert_received_in_order(&self, patterns: &[&str]) {
+        let output = self.output();
+        let mut rest = output.as_str();
+        for pattern in patterns {
+            match rest.find(pattern) {
+                Some(idx) => rest = &rest[idx + pattern.len()..],
+                None => panic!(
+                    "expected {:?} in order {:?} in console output {:?}",
+                    pattern, patterns, output
+                ),
+            }
+        }
+    }
+
+    /// record a call
+    fn record(&self, call: ConsoleCall) {
+        self.calls.with(|calls| calls.push(call));
+    }
+}
+
+impl Default for MockConsole {
+    fn default() -> Self {
+        MockConsole::new()
+    }
+}
+
+impl ConsoleImpl for MockConsole {
+    fn putc(&self, c: char) {
+        self.record(ConsoleCall::Putc(c));
+    }
+
+    fn puts(&self, s: &str) {
+        self.record(ConsoleCall::Puts(String::from(s)));
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        self.record(ConsoleCall::PutsBytes(Vec::from(bytes)));
+    }
+
+    fn flush(&self) {
+        self.record(ConsoleCall::Flush);
+    }
+
+    fn poll(&self) {
+        self.record(ConsoleCall::Poll);
+    }
+
+    fn close(&mut self) {
+        self.record(ConsoleCall::Close);
+    }
+}
@@ -0,0 +1,146 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Morse code console
+//!
+//! As last resort if neither a Uart nor a screen is available the ``MorseConsole`` blinks the messages of
+//! ``error!`` in Morse code with an LED. The closure passed on creation switches the LED on or off and keeps it in
+//! this state for the given number of dot units, e.g. 200 milliseconds each:
+//! ```ignore
+//! let morse = MorseConsole::new(
+//!     |on, units| {
+//!         gpio.set(ACT_LED, on);
+//!         timer::sleep(Duration::from_millis(200 * units as u64));
+//!     },
+//!     60_000,
+//! );
+//! CONSOLE.take_for(|cons| cons.add_sink(morse));
+//! ```
+//! Only the message of the record is blinked, the prefix, the fields and any other output are ignored. Letters and
+//! digits are blinked, spaces separate words, all other characters are skipped. As blinking takes long the messages
+//! are rate limited: a message is only blinked if the last one is at least the given interval in milliseconds old,
+//! at most ``MORSE_MAX_CHARS`` characters of it are blinked. The interval is measured with the timestamps of the
+//! records. Without a timestamp source the messages are not rate limited.
+
+use crate::{ConsoleImpl, Level, LogRecord, RecordSink};
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// The maximum number of characters of a message the ``MorseConsole`` blinks
+pub const MORSE_MAX_CHARS: usize = 32;
+
+/// The Morse code of the letters ``A`` to ``Z`` followed by the digits ``0`` to ``9``
+const CODES: [&str; 36] = [
+    ".-", "-...", "-.-.", "-..", ".", "..-.", "--.", "....", "..", ".---", "-.-", ".-..", "--",
+    "-.", "---", ".--.", "--.-", ".-.", "...", "-", "..-", "...-", ".--", "-..-", "-.--", "--..",
+    "-----", ".----", "..---", "...--", "....-", ".....", "-....", "--...", "---..", "----.",
+];
+
+/// The output channel blinking the messages of ``error!`` in Morse code
+pub struct MorseConsole<F> {
+    set_led: F,
+    interval_ms: u64,
+    /// the timestamp in milliseconds of the last blinked message plus one, zero if no message has been blinked so far
+    last: AtomicU64,
+}
+
+impl<F: Fn(bool, u32) + Sync> MorseConsole<F> {
+    /// Create the console switching the LED with the given closure. It gets whether the LED shall be on and the
+    /// number of dot units to keep it in this state. Messages are blinked at most once per ``interval_ms``
+    /// milliseconds.
+    pub fn new(set_led: F, interval_ms: u64) -> Self {
+        MorseConsole {
+            set_led,
+            interval_ms,
+            last: AtomicU64::new(0),
+        }
+    }
+
+    /// check if a message logged at the given time is blinked
+    fn pass(&self, record: &LogRecord) -> bool {
+        let now = match record.timestamp() {
+            Some(now) => now.as_millis() as u64 + 1,
+            None => return true,
+        };
+        let last = self.last.load(Ordering::Relaxed);
+        if last != 0 && now.saturating_sub(last) < self.interval_ms {
+            return false;
+        }
+        self.last
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+}
+
+/// ``fmt::Write`` adapter blinking the formatted message character by character
+struct Blinker<'a, F> {
+    set_led: &'a F,
+    /// the number of characters blinked so far
+    count: usize,
+    /// a word has been blinked and a word gap is pending before the next one
+    in_word: bool,
+    /// a space has been written since the last blinked character
+    space: bool,
+}
+
+impl<F: Fn(bool, u32)> Blinker<'_, F> {
+    fn blink(&mut self, code: &str) {
+        if self.in_word {
+            // the LED has already been off for one unit after the last signal
+            (self.set_led)(false, if self.space { 6 } else { 2 });
+        }
+        for signal in code.chars() {
+            (self.set_led)(true, if signal == '-' { 3 } else { 1 });
+            (self.set_led)(false, 1);
+        }
+        self.in_word = true;
+        self.space = false;
+    }
+}
+
+impl<F: Fn(bool, u32)> fmt::Write for Blinker<'_, F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.count >= MORSE_MAX_CHARS {
+                return Err(fmt::Error);
+            }
+            self.count += 1;
+            match c.to_ascii_uppercase() {
+                c @ 'A'..='Z' => self.blink(CODES[c as usize - 'A' as usize]),
+                c @ '0'..='9' => self.blink(CODES[26 + c as usize - '0' as usize]),
+                ' ' => self.space = true,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Fn(bool, u32) + Sync> RecordSink for MorseConsole<F> {
+    fn log(&self, record: &LogRecord) {
+        if record.level() != Level::Error || !self.pass(record) {
+            return;
+        }
+        let mut blinker = Blinker {
+            set_led: &self.set_led,
+            count: 0,
+            in_word: false,
+            space: false,
+        };
+        let _ = write!(blinker, "{}", record.args());
+    }
+}
+
+impl<F: Fn(bool, u32) + Sync> ConsoleImpl for MorseConsole<F> {
+    fn putc(&self, _: char) {}
+
+    fn puts(&self, _: &str) {}
+
+    fn records(&self) -> Option<&dyn RecordSink> {
+        Some(self)
+    }
+}
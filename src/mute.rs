@@ -0,0 +1,58 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Silent output
+//!
+//! The ``NullConsole`` discards everything passed to it, e.g. to attach a placeholder that does not keep the output
+//! in the early boot buffer like the default console does. To silence the output temporarily without detaching the
+//! real output channels the console could be muted, e.g. during timing sensitive measurements:
+//! ```ignore
+//! CONSOLE.use_for(|cons| cons.mute());
+//! let cycles = measure_irq_latency();
+//! CONSOLE.use_for(|cons| cons.unmute());
+//! println!("latency: {} cycles", cycles);
+//! ```
+//! While muted all output is dropped before it reaches any output channel, it is neither deferred nor kept for later.
+//! Emergency output unmutes the console, so messages of the ``alloc_error_handler`` or an exception handler are never
+//! lost.
+
+use crate::{Console, ConsoleImpl};
+use core::sync::atomic::Ordering;
+
+/// The output channel dropping all output
+#[derive(Default)]
+pub struct NullConsole;
+
+impl NullConsole {
+    /// Create the console dropping all output
+    pub const fn new() -> Self {
+        NullConsole
+    }
+}
+
+impl ConsoleImpl for NullConsole {
+    fn putc(&self, _: char) {}
+
+    fn puts(&self, _: &str) {}
+}
+
+impl Console {
+    /// Drop all output until ``unmute`` is called. The output channels stay attached.
+    pub fn mute(&self) {
+        self.muted.store(true, Ordering::Release);
+    }
+
+    /// Pass the output to the output channels again
+    pub fn unmute(&self) {
+        self.muted.store(false, Ordering::Release);
+    }
+
+    /// Check if the console is muted
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::Acquire)
+    }
+}
@@ -0,0 +1,159 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Newline translation
+//!
+//! Many terminal emulators need a carriage return in front of each line feed while Rust code usually only emits
+//! ``\n``. The newline mode of the console translates the line endings of all text output transparently:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_newline(Newline::CrLf));
+//! print!("line one\nline two\n"); // is passed as "line one\r\nline two\r\n"
+//! ```
+//! The default mode ``Newline::Raw`` passes the text as it is. Binary data passed with ``Console::put_raw`` is never
+//! translated.
+
+use crate::Console;
+use core::sync::atomic::Ordering;
+
+/// The line endings the console passes to the output channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Newline {
+    /// line endings are passed as ``\n``, a ``\r`` in front of a ``\n`` is removed
+    Lf,
+    /// line endings are passed as ``\r\n``, a ``\r`` is added in front of every ``\n`` that has none
+    CrLf,
+    /// the text is passed as it is
+    Raw,
+}
+
+impl Console {
+    /// Set the newline mode the text output is translated with
+    pub fn set_newline(&mut self, newline: Newline) {
+        self.newline = newline;
+    }
+
+    /// The current newline mode of the console
+    pub fn newline(&self) -> Newline {
+        self.newline
+    }
+
+    /// translate the string with the newline mode of the console and pass the translated parts to the output
+    /// function. A ``\r`` ending the string is remembered for the next one. In raw mode the string is passed as is.
+    pub(crate) fn translated(&self, s: &str, mut output: impl FnMut(&str)) {
+        if self.raw {
+            output(s);
+            return;
+        }
+        let prev_cr = self.last_cr.load(Ordering::Relaxed);
+        self.last_cr.store(
+            translate(self.newline, prev_cr, s, |part| {
+                self.indented(part, |part| self.limited(part, &mut output))
+            }),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+/// Translate the line endings of the string and pass the translated parts to the output function. ``prev_cr`` tells
+/// whether the previous string ended with a ``\r``, the return value tells the same for this string. A ``\r``
+/// ending the previous string is not removed in ``Newline::Lf`` mode as it has already been passed.
+pub(crate) fn translate(
+    newline: Newline,
+    prev_cr: bool,
+    s: &str,
+    mut output: impl FnMut(&str),
+) -> bool {
+    if s.is_empty() {
+        return prev_cr;
+    }
+    let mut start = 0;
+    match newline {
+        Newline::Raw => (),
+        Newline::CrLf => {
+            for (idx, _) in s.match_indices('\n') {
+                let cr = match idx {
+                    0 => prev_cr,
+                    _ => s.as_bytes()[idx - 1] == b'\r',
+                };
+                if !cr {
+                    if idx > start {
+                        output(&s[start..idx]);
+                    }
+                    output("\r\n");
+                    start = idx + 1;
+                }
+            }
+        }
+        Newline::Lf => {
+            for (idx, _) in s.match_indices("\r\n") {
+                if idx > start {
+                    output(&s[start..idx]);
+                }
+                // the line feed is passed with the next part
+                start = idx + 1;
+            }
+        }
+    }
+    if start < s.len() {
+        output(&s[start..]);
+    }
+    s.ends_with('\r')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    fn translated(newline: Newline, prev_cr: bool, s: &str) -> (String, bool) {
+        let mut output = String::new();
+        let cr = translate(newline, prev_cr, s, |part| output.push_str(part));
+        (output, cr)
+    }
+
+    #[test]
+    fn adds_carriage_returns() {
+        assert_eq!(
+            translated(Newline::CrLf, false, "a\nb\r\nc\n"),
+            ("a\r\nb\r\nc\r\n".into(), false)
+        );
+        assert_eq!(
+            translated(Newline::CrLf, false, "\n\n"),
+            ("\r\n\r\n".into(), false)
+        );
+    }
+
+    #[test]
+    fn removes_carriage_returns() {
+        assert_eq!(
+            translated(Newline::Lf, false, "a\r\nb\nc\r"),
+            ("a\nb\nc\r".into(), true)
+        );
+        assert_eq!(
+            translated(Newline::Lf, false, "\r\n\r\n"),
+            ("\n\n".into(), false)
+        );
+    }
+
+    #[test]
+    fn keeps_raw_text() {
+        assert_eq!(
+            translated(Newline::Raw, false, "a\nb\r\n"),
+            ("a\nb\r\n".into(), false)
+        );
+    }
+
+    #[test]
+    fn carries_a_carriage_return_over() {
+        let (first, cr) = translated(Newline::CrLf, false, "a\r");
+        assert!(cr);
+        let (second, cr) = translated(Newline::CrLf, cr, "\nb");
+        assert_eq!(first + &second, "a\r\nb");
+        assert!(!cr);
+        assert_eq!(translated(Newline::CrLf, true, ""), (String::new(), true));
+    }
+}
@@ -0,0 +1,120 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Pager
+//!
+//! Dumping large memory regions over a slow Uart scrolls everything but the last screenful out of sight. The pager
+//! stops after each screenful with a ``--More--`` prompt and waits for a key from the input channel of the current
+//! active console:
+//! ```ignore
+//! page(LICENSE_TEXT);
+//!
+//! let mut pager = Pager::for_terminal();
+//! for (idx, entry) in page_table.iter().enumerate() {
+//!     writeln!(pager, "{:4}: {:#018x}", idx, entry)?; // stops once the user pressed q
+//! }
+//! ```
+//! Space shows the next screenful, enter the next line and ``q`` drops the rest of the output. The ``Pager``
+//! implements ``fmt::Write`` and returns an error once the user quit, so formatting loops stop early. Without input
+//! channel the output is not paged at all.
+
+use crate::CONSOLE;
+use core::fmt;
+
+/// The number of rows of the terminal the pager assumes if the size has not been detected with
+/// ``Console::detect_terminal``
+pub const PAGER_ROWS: usize = 24;
+
+/// The prompt printed after each screenful
+const PROMPT: &str = "--More--";
+
+/// ``fmt::Write`` adapter stopping the output after each screenful
+pub struct Pager {
+    /// the number of lines printed per screenful, ``None`` if the output is not paged anymore
+    page: Option<usize>,
+    /// the number of lines printed since the last prompt
+    lines: usize,
+    quit: bool,
+}
+
+impl Pager {
+    /// Create a pager for a terminal with the given number of rows. The last row is used for the prompt.
+    pub fn new(rows: usize) -> Self {
+        Pager {
+            page: Some(rows.saturating_sub(1).max(1)),
+            lines: 0,
+            quit: false,
+        }
+    }
+
+    /// Create a pager for the terminal detected with ``Console::detect_terminal`` or for a terminal with
+    /// ``PAGER_ROWS`` rows if the size is not known
+    pub fn for_terminal() -> Self {
+        let rows = CONSOLE.use_for(|console| console.terminal().and_then(|caps| caps.size));
+        Pager::new(rows.map_or(PAGER_ROWS, |size| size.rows))
+    }
+
+    /// Check if the user quit the pager. All further output is dropped.
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+
+    /// print the prompt and wait for the key of the user
+    fn prompt(&mut self, page: usize) {
+        crate::print(PROMPT);
+        let key = CONSOLE.use_for(|console| console.read_raw());
+        // blank the prompt
+        crate::print_args(format_args!("\r{:1$}\r", "", PROMPT.len()));
+        self.lines = match key {
+            Some('q') | Some('Q') => {
+                self.quit = true;
+                0
+            }
+            // show one more line
+            Some('\r') | Some('\n') => page - 1,
+            Some(_) => 0,
+            None => {
+                self.page = None;
+                0
+            }
+        };
+    }
+}
+
+impl fmt::Write for Pager {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for line in s.split_inclusive('\n') {
+            if self.quit {
+                return Err(fmt::Error);
+            }
+            crate::print(line);
+            if let Some(page) = self.page {
+                if line.ends_with('\n') {
+                    self.lines += 1;
+                    if self.lines >= page {
+                        self.prompt(page);
+                    }
+                }
+            }
+        }
+        if self.quit {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Print the text through a pager for the attached terminal, see ``Pager::for_terminal``
+pub fn page(text: &str) {
+    let _ = fmt::Write::write_str(&mut Pager::for_terminal(), text);
+}
+
+/// Print the formatted arguments through a pager for the attached terminal, see ``Pager::for_terminal``
+pub fn page_args(args: fmt::Arguments) {
+    let _ = fmt::write(&mut Pager::for_terminal(), args);
+}
@@ -0,0 +1,69 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Panic handler
+//!
+//! Available with the ``panic-handler`` feature. Provides the ``#[panic_handler]`` of the binary that prints the
+//! panic message, the source location and the id of the panicking core (if the console has a core id provider) as
+//! error through the console and halts the core afterwards:
+//! ```ignore
+//! E: panic - panicked at src/kernel.rs:42:5: out of memory
+//! ```
+//! The message is printed regardless of the log level and the target filter of the console. If no real console is
+//! attached yet the panic ends up in the early boot buffer and the additional sinks, e.g. a ``RamLog`` that could be
+//! inspected after the next reset.
+//! The panic is never deferred, so a panic in interrupt context is printed as well. A panic raised while printing a
+//! panic is not printed again to prevent endless recursion.
+
+use crate::{Console, Level, LogRecord, CONSOLE};
+use core::fmt;
+use core::panic::{Location, PanicInfo};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Set once the first panic is printed
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    if !PANICKING.swap(true, Ordering::AcqRel) {
+        CONSOLE.use_for(|console| {
+            console.urgent(|| {
+                match info.location() {
+                    Some(location) => log_panic(
+                        console,
+                        format_args!(
+                            "panicked at {}:{}:{}: {}",
+                            location.file(),
+                            location.line(),
+                            location.column(),
+                            info.message()
+                        ),
+                        Some(location),
+                    ),
+                    None => log_panic(console, format_args!("panicked: {}", info.message()), None),
+                }
+                console.flush_direct();
+            })
+        });
+    }
+
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// print the panic regardless of the log level and the filter of the console, without deferring it
+fn log_panic(console: &Console, message: fmt::Arguments, location: Option<&Location>) {
+    let record = LogRecord::new(Level::Error, "panic", message, &[]);
+    console.poll();
+    match location {
+        Some(location) => {
+            console.write_record_direct(&record.with_location(location.file(), location.line()))
+        }
+        None => console.write_record_direct(&record),
+    }
+}
@@ -0,0 +1,151 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Progress indicators
+//!
+//! Long running operations like flashing the SD card or testing the memory show their progress on a single line of
+//! the terminal that is redrawn with a carriage return:
+//! ```ignore
+//! let mut progress = Progress::new(sectors).with_label("flashing");
+//! for sector in 0..sectors {
+//!     sdcard.write(sector, &image[sector * 512..]);
+//!     progress.update(sector + 1); // flashing [#######             ]  35%
+//! }
+//! progress.finish();
+//! ```
+//! The line is only redrawn if the bar or the percentage changes, so updating the progress often does not flood a
+//! slow Uart. Operations without a known end show a ``Spinner`` instead. Other output printed in between breaks the
+//! line, the next update starts a new one.
+
+/// The number of characters of the bar of a ``Progress``
+pub const PROGRESS_WIDTH: usize = 20;
+
+/// A progress bar redrawn on a single line
+pub struct Progress {
+    total: usize,
+    current: usize,
+    label: &'static str,
+    /// the number of filled characters and the percentage drawn last, ``None`` if nothing has been drawn so far
+    drawn: Option<(usize, usize)>,
+}
+
+impl Progress {
+    /// Create a progress bar for an operation of ``total`` steps. Nothing is drawn until the first update.
+    pub fn new(total: usize) -> Self {
+        Progress {
+            total,
+            current: 0,
+            label: "",
+            drawn: None,
+        }
+    }
+
+    /// Set the label printed in front of the bar
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = label;
+        self
+    }
+
+    /// Set the number of steps done so far and redraw the line if the progress visibly changed. Values above the
+    /// total are treated as the total.
+    pub fn update(&mut self, current: usize) {
+        self.current = current.min(self.total);
+        let (filled, percent) = match self.total {
+            0 => (PROGRESS_WIDTH, 100),
+            total => (
+                share(self.current, PROGRESS_WIDTH, total),
+                share(self.current, 100, total),
+            ),
+        };
+        if self.drawn == Some((filled, percent)) {
+            return;
+        }
+        self.drawn = Some((filled, percent));
+        let separator = if self.label.is_empty() { "" } else { " " };
+        crate::print_args(format_args!(
+            "\r{}{}[{:#<filled$}{:<empty$}] {:>3}%",
+            self.label,
+            separator,
+            "",
+            "",
+            percent,
+            filled = filled,
+            empty = PROGRESS_WIDTH - filled,
+        ));
+    }
+
+    /// Add the given number of steps to the steps done so far, see ``update``
+    pub fn advance(&mut self, steps: usize) {
+        self.update(self.current.saturating_add(steps));
+    }
+
+    /// The number of steps done so far
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Draw the completed bar and end the line
+    pub fn finish(mut self) {
+        self.update(self.total);
+        crate::print_args(format_args!("\r\n"));
+    }
+}
+
+/// ``current * scale / total`` computed without overflowing for any total
+fn share(current: usize, scale: usize, total: usize) -> usize {
+    (current as u128 * scale as u128 / total as u128) as usize
+}
+
+/// The characters a ``Spinner`` cycles through
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// A spinning indicator for operations without a known end
+pub struct Spinner {
+    label: &'static str,
+    step: usize,
+}
+
+impl Spinner {
+    /// Create a spinner printed in front of the given label. Nothing is drawn until the first tick.
+    pub fn new(label: &'static str) -> Self {
+        Spinner { label, step: 0 }
+    }
+
+    /// Redraw the line with the next character of the spinner
+    pub fn tick(&mut self) {
+        let c = SPINNER[self.step % SPINNER.len()];
+        self.step = self.step.wrapping_add(1);
+        crate::print_args(format_args!("\r{} {}", c, self.label));
+    }
+
+    /// Replace the spinner with the given message and end the line
+    pub fn finish(self, message: &str) {
+        crate::print_args(format_args!("\r{} {}\r\n", message, self.label));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_large_totals_without_overflow() {
+        let mut progress = Progress::new(usize::MAX);
+        progress.update(usize::MAX / 2);
+        assert_eq!(progress.drawn, Some((PROGRESS_WIDTH / 2 - 1, 49)));
+        progress.update(usize::MAX);
+        assert_eq!(progress.drawn, Some((PROGRESS_WIDTH, 100)));
+    }
+
+    #[test]
+    fn draws_an_empty_operation_as_complete() {
+        let mut progress = Progress::new(0);
+        progress.update(3);
+        assert_eq!(progress.current(), 0);
+        assert_eq!(progress.drawn, Some((PROGRESS_WIDTH, 100)));
+    }
+}
@@ -0,0 +1,157 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # QEMU Uart output
+//!
+//! Available with the ``qemu`` feature. The ``QemuConsole`` is a minimal driver of the PL011 Uart and the mini Uart
+//! of the Raspberry Pi, so the output of a kernel started in QEMU is visible on the very first build without any
+//! Uart crate:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.replace(unsafe { QemuConsole::raspi3() }));
+//! println!("hello from the emulator");
+//! ```
+//! ```text
+//! qemu-system-aarch64 -M raspi3b -kernel kernel8.img -serial stdio
+//! ```
+//! The emulated Uarts do not need any initialization like the baud rate or the GPIO setup. On real hardware the Uart
+//! needs to be initialized before it could be used with this console.
+
+use crate::{ConsoleImpl, ConsoleInput};
+
+/// The base address of the PL011 Uart of the Raspberry Pi 3, the first serial port of QEMU
+pub const RASPI3_PL011_BASE: usize = 0x3F20_1000;
+
+/// The base address of the mini Uart of the Raspberry Pi 3, the second serial port of QEMU
+pub const RASPI3_MINI_UART_BASE: usize = 0x3F21_5040;
+
+/// The base address of the PL011 Uart of the Raspberry Pi 4
+pub const RASPI4_PL011_BASE: usize = 0xFE20_1000;
+
+/// The Uart models the console is able to drive
+#[derive(Clone, Copy)]
+enum Uart {
+    Pl011,
+    MiniUart,
+}
+
+/// The output channel writing to the memory mapped registers of a Uart
+pub struct QemuConsole {
+    base: usize,
+    uart: Uart,
+}
+
+impl QemuConsole {
+    /// The console writing to the PL011 Uart of the emulated Raspberry Pi 3, which is connected to the first
+    /// ``-serial`` option of QEMU
+    ///
+    /// # Safety
+    /// The binary needs to run on an emulated or a real Raspberry Pi 3 where the registers of the PL011 Uart are
+    /// mapped at ``RASPI3_PL011_BASE``
+    pub const unsafe fn raspi3() -> Self {
+        QemuConsole {
+            base: RASPI3_PL011_BASE,
+            uart: Uart::Pl011,
+        }
+    }
+
+    /// The console writing to the PL011 Uart at the given base address
+    ///
+    /// # Safety
+    /// The base address needs to point to the registers of a PL011 Uart
+    pub const unsafe fn pl011(base: usize) -> Self {
+        QemuConsole {
+            base,
+            uart: Uart::Pl011,
+        }
+    }
+
+    /// The console writing to the mini Uart at the given base address, this is the address of the ``AUX_MU_IO``
+    /// register
+    ///
+    /// # Safety
+    /// The base address needs to point to the registers of a mini Uart
+    pub const unsafe fn mini_uart(base: usize) -> Self {
+        QemuConsole {
+            base,
+            uart: Uart::MiniUart,
+        }
+    }
+
+    /// the offsets of the data register, the status register and the status bits signaling a full transmit FIFO
+    /// and a received byte
+    fn registers(&self) -> (usize, usize, u32, u32) {
+        match self.uart {
+            // DR, FR with TXFF and RXFE (the receive FIFO is empty)
+            Uart::Pl011 => (0x00, 0x18, 1 << 5, 1 << 4),
+            // AUX_MU_IO, AUX_MU_LSR with transmitter empty and data ready
+            Uart::MiniUart => (0x00, 0x14, 1 << 5, 1 << 0),
+        }
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        // safe as the constructors ensure the base address points to the registers
+        unsafe { core::ptr::read_volatile((self.base + offset) as *const u32) }
+    }
+
+    fn write(&self, offset: usize, value: u32) {
+        // safe as the constructors ensure the base address points to the registers
+        unsafe { core::ptr::write_volatile((self.base + offset) as *mut u32, value) }
+    }
+
+    /// wait until the Uart accepts the byte and write it
+    fn write_byte(&self, byte: u8) {
+        let (data, status, transmit, _) = self.registers();
+        let transmit_ready = |status: u32| match self.uart {
+            Uart::Pl011 => status & transmit == 0,
+            Uart::MiniUart => status & transmit != 0,
+        };
+        while !transmit_ready(self.read(status)) {
+            core::hint::spin_loop();
+        }
+        self.write(data, byte as u32);
+    }
+
+    fn write_bytes(&self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_byte(*byte);
+        }
+    }
+}
+
+impl ConsoleImpl for QemuConsole {
+    fn putc(&self, c: char) {
+        self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    fn puts(&self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        self.write_bytes(bytes);
+    }
+
+    fn input(&self) -> Option<&dyn ConsoleInput> {
+        Some(self)
+    }
+}
+
+/// The characters are received as single bytes, so only ASCII input is received correctly
+impl ConsoleInput for QemuConsole {
+    fn try_getc(&self) -> Option<char> {
+        let (data, status, _, receive) = self.registers();
+        let received = match self.uart {
+            Uart::Pl011 => self.read(status) & receive == 0,
+            Uart::MiniUart => self.read(status) & receive != 0,
+        };
+        if received {
+            Some((self.read(data) & 0xff) as u8 as char)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,247 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Message queue
+//!
+//! A bounded queue of formatted messages that does not allocate memory and never blocks. Any number of producers
+//! (cores or interrupt handlers) may push messages at the same time, while a single consumer passes them to the
+//! output channels. A message that does not fit into a slot of ``QUEUE_MESSAGE_SIZE`` bytes is truncated, messages
+//! pushed while all ``QUEUE_SLOTS`` slots are occupied are dropped and counted.
+
+use crate::emergency::TruncatingWriter;
+use crate::Level;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+
+/// The number of messages the queue is able to hold
+pub const QUEUE_SLOTS: usize = 16;
+
+/// The maximum size in bytes of a single queued message
+pub const QUEUE_MESSAGE_SIZE: usize = 128;
+
+/// slot states
+const EMPTY: u8 = 0;
+const WRITING: u8 = 1;
+const READY: u8 = 2;
+
+/// A single message of the queue
+struct Slot {
+    state: AtomicU8,
+    level: UnsafeCell<Option<Level>>,
+    len: UnsafeCell<usize>,
+    data: UnsafeCell<[u8; QUEUE_MESSAGE_SIZE]>,
+}
+
+impl Slot {
+    // only used to initialize the slots of the queue, each use creates a new slot
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: Slot = Slot {
+        state: AtomicU8::new(EMPTY),
+        level: UnsafeCell::new(None),
+        len: UnsafeCell::new(0),
+        data: UnsafeCell::new([0; QUEUE_MESSAGE_SIZE]),
+    };
+}
+
+/// Bounded multi-producer single-consumer queue of formatted messages
+pub(crate) struct MessageQueue {
+    slots: [Slot; QUEUE_SLOTS],
+    /// the total number of slots ever claimed by producers
+    head: AtomicUsize,
+    /// the total number of messages ever taken by the consumer
+    tail: AtomicUsize,
+    /// ensures there is only one consumer at a time
+    draining: AtomicBool,
+    dropped: AtomicUsize,
+}
+
+// safe as every slot is accessed by the producer that claimed it or by the single consumer once it is ready
+unsafe impl Sync for MessageQueue {}
+
+impl MessageQueue {
+    pub(crate) const fn new() -> Self {
+        MessageQueue {
+            slots: [Slot::EMPTY; QUEUE_SLOTS],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            draining: AtomicBool::new(false),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Format the message with the given function into the next free slot. The level is kept with the message so the
+    /// consumer could pass it to the output channel this severity is routed to. Returns ``false`` if the queue is full
+    /// and the message has been dropped.
+    pub(crate) fn push(
+        &self,
+        level: Option<Level>,
+        format: impl FnOnce(&mut dyn fmt::Write) -> fmt::Result,
+    ) -> bool {
+        let mut head = self.head.load(Ordering::Relaxed);
+        let slot = loop {
+            let slot = &self.slots[head % QUEUE_SLOTS];
+            // the slot is free once the consumer took the message of the previous round
+            if head.wrapping_sub(self.tail.load(Ordering::Acquire)) >= QUEUE_SLOTS
+                || slot.state.load(Ordering::Acquire) != EMPTY
+            {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+            match self.head.compare_exchange_weak(
+                head,
+                head.wrapping_add(1),
+                Ordering::SeqCst,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break slot,
+                Err(current) => head = current,
+            }
+        };
+        slot.state.store(WRITING, Ordering::Relaxed);
+        // safe as this producer is the only one that claimed the slot
+        unsafe {
+            let mut writer = TruncatingWriter::new(&mut *slot.data.get());
+            let _ = format(&mut writer);
+            *slot.len.get() = writer.len();
+            *slot.level.get() = level;
+        }
+        slot.state.store(READY, Ordering::Release);
+        true
+    }
+
+    /// Pass at most ``limit`` queued messages in the order they have been pushed to the given output function.
+    /// Returns the number of messages passed. If another consumer is currently draining the queue nothing is done.
+    pub(crate) fn drain(&self, limit: usize, mut output: impl FnMut(Option<Level>, &str)) -> usize {
+        if self
+            .draining
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::Relaxed)
+            .is_err()
+        {
+            return 0;
+        }
+        let mut count = 0;
+        while count < limit {
+            let tail = self.tail.load(Ordering::Relaxed);
+            let slot = &self.slots[tail % QUEUE_SLOTS];
+            // a claimed slot that is still written stops the drain to keep the order of the messages
+            if slot.state.load(Ordering::Acquire) != READY {
+                break;
+            }
+            // safe as the slot is ready and the draining lock ensures there is only one consumer
+            let (level, data) =
+                unsafe { (*slot.level.get(), &(&*slot.data.get())[..*slot.len.get()]) };
+            // the writer only copies complete characters, so the slot always contains valid UTF-8
+            if let Ok(s) = core::str::from_utf8(data) {
+                output(level, s);
+            }
+            slot.state.store(EMPTY, Ordering::Release);
+            self.tail.store(tail.wrapping_add(1), Ordering::Release);
+            count += 1;
+        }
+        // sequentially consistent with claiming a slot, so a producer failing to drain right after pushing its
+        // message is guaranteed that the draining core sees the message once it checks for an empty queue
+        self.draining.store(false, Ordering::SeqCst);
+        count
+    }
+
+    /// Check if there are no messages waiting in the queue
+    pub(crate) fn is_empty(&self) -> bool {
+        self.head.load(Ordering::SeqCst) == self.tail.load(Ordering::SeqCst)
+    }
+
+    /// The number of messages dropped since the queue has been created because it was full
+    pub(crate) fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    fn drained(queue: &MessageQueue, limit: usize) -> Vec<(Option<Level>, String)> {
+        let mut messages = Vec::new();
+        queue.drain(limit, |level, s| messages.push((level, String::from(s))));
+        messages
+    }
+
+    #[test]
+    fn drains_in_push_order() {
+        let queue = MessageQueue::new();
+        assert!(queue.is_empty());
+        assert!(queue.push(Some(Level::Warn), |w| w.write_str("first")));
+        assert!(queue.push(None, |w| write!(w, "second {}", 2)));
+        assert!(!queue.is_empty());
+        assert_eq!(
+            drained(&queue, usize::MAX),
+            [
+                (Some(Level::Warn), String::from("first")),
+                (None, String::from("second 2"))
+            ]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn drains_at_most_the_limit() {
+        let queue = MessageQueue::new();
+        for idx in 0..3 {
+            queue.push(None, |w| write!(w, "{}", idx));
+        }
+        assert_eq!(drained(&queue, 2).len(), 2);
+        assert_eq!(drained(&queue, 2), [(None, String::from("2"))]);
+    }
+
+    #[test]
+    fn drops_messages_if_full() {
+        let queue = MessageQueue::new();
+        for idx in 0..QUEUE_SLOTS {
+            assert!(queue.push(None, |w| write!(w, "{}", idx)));
+        }
+        assert!(!queue.push(None, |w| w.write_str("dropped")));
+        assert_eq!(queue.dropped(), 1);
+        let messages = drained(&queue, usize::MAX);
+        assert_eq!(messages.len(), QUEUE_SLOTS);
+        assert_eq!(messages[QUEUE_SLOTS - 1].1, (QUEUE_SLOTS - 1).to_string());
+        assert!(queue.push(None, |w| w.write_str("again")));
+    }
+
+    #[test]
+    fn keeps_the_order_when_wrapping_around() {
+        let queue = MessageQueue::new();
+        let mut expected = 0;
+        for round in 0..5 * QUEUE_SLOTS {
+            assert!(queue.push(None, |w| write!(w, "{}", round)));
+            // keep a few messages in the queue, so the slots wrap around with a partly filled queue
+            if round >= 4 {
+                assert_eq!(drained(&queue, 1), [(None, expected.to_string())]);
+                expected += 1;
+            }
+        }
+        for (_, message) in drained(&queue, usize::MAX) {
+            assert_eq!(message, expected.to_string());
+            expected += 1;
+        }
+        assert_eq!(expected, 5 * QUEUE_SLOTS);
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[test]
+    fn truncates_long_messages_at_a_character() {
+        let queue = MessageQueue::new();
+        queue.push(None, |w| {
+            w.write_str("a")?;
+            (0..QUEUE_MESSAGE_SIZE).try_for_each(|_| w.write_char('ü'))
+        });
+        let message = drained(&queue, 1).remove(0).1;
+        assert_eq!(message.len(), QUEUE_MESSAGE_SIZE - 1);
+        assert!(message.starts_with("aü"));
+    }
+}
@@ -0,0 +1,206 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Persistent RAM log
+//!
+//! A console sink mirroring all output into a reserved RAM region. The content of the RAM survives a warm reset, so
+//! the log of the previous boot could be checked after the device has been reset by a watchdog or hang. This helps
+//! diagnosing issues even without a serial cable attached.
+//! ```ignore
+//! let mut ramlog = unsafe { RamLog::new(0x0800_0000 as *mut u8, 0x1_0000) };
+//! if ramlog.has_previous() {
+//!     println!("log of the previous boot:");
+//!     ramlog.print_previous();
+//! }
+//! ramlog.reset();
+//! CONSOLE.take_for(|cons| cons.add_sink(ramlog));
+//! ```
+//! The region starts with a small header containing a magic value. Without calling ``reset`` the output is appended
+//! to the log found in the region. Once the region is full the oldest output is overwritten.
+
+use crate::early::replay_bytes;
+use crate::ConsoleImpl;
+use core::mem::size_of;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The magic value identifying a valid log in the RAM region ("RPLG")
+const RAMLOG_MAGIC: u32 = 0x474C_5052;
+
+/// The header at the start of the RAM region
+#[repr(C)]
+struct RamLogHeader {
+    magic: u32,
+    /// the capacity of the log data, used to detect whether the region has been set up with the same size
+    capacity: u32,
+    /// the total number of bytes ever written, the write position is this value modulo the capacity
+    written: u32,
+}
+
+/// A console sink writing all output into a reserved RAM region
+pub struct RamLog {
+    header: *mut RamLogHeader,
+    data: *mut u8,
+    capacity: usize,
+    lock: AtomicBool,
+}
+
+impl RamLog {
+    /// Create the RAM log in the region starting at ``base`` with ``size`` bytes.
+    /// # Safety
+    /// The region need to be valid for reads and writes, aligned to 4 bytes and reserved for the RAM log only. No
+    /// other code is allowed to access it as long as the ``RamLog`` exists.
+    pub unsafe fn new(base: *mut u8, size: usize) -> Self {
+        let capacity = size.saturating_sub(size_of::<RamLogHeader>()).min(u32::MAX as usize / 2);
+        RamLog {
+            header: base as *mut RamLogHeader,
+            data: base.add(size_of::<RamLogHeader>()),
+            capacity,
+            lock: AtomicBool::new(false),
+        }
+    }
+
+    /// Check whether the region contains the log of a previous boot
+    pub fn has_previous(&self) -> bool {
+        // safe as the region is valid as per the contract of ``new``
+        unsafe {
+            read_volatile(&(*self.header).magic) == RAMLOG_MAGIC
+                && read_volatile(&(*self.header).capacity) as usize == self.capacity
+        }
+    }
+
+    /// Print the log of the previous boot through the console. Nothing is printed if there is no previous log. Call
+    /// this before the RAM log is attached to the console, otherwise the output would be mirrored into the log again.
+    pub fn print_previous(&self) {
+        if !self.has_previous() || self.capacity == 0 {
+            return;
+        }
+        // safe as the region is valid as per the contract of ``new``
+        let written = unsafe { read_volatile(&(*self.header).written) } as usize;
+        let (start, len) = if written > self.capacity {
+            (written % self.capacity, self.capacity)
+        } else {
+            (0, written)
+        };
+        let bytes = (0..len).map(|idx| {
+            // safe as the region is valid as per the contract of ``new``
+            unsafe { read_volatile(self.data.add((start + idx) % self.capacity)) }
+        });
+        replay_bytes(bytes, crate::print);
+    }
+
+    /// Discard the log found in the region and start a new one
+    pub fn reset(&mut self) {
+        self.init_header();
+    }
+
+    fn init_header(&self) {
+        // safe as the region is valid as per the contract of ``new``
+        unsafe {
+            write_volatile(&mut (*self.header).capacity, self.capacity as u32);
+            write_volatile(&mut (*self.header).written, 0);
+            write_volatile(&mut (*self.header).magic, RAMLOG_MAGIC);
+        }
+    }
+
+    fn write(&self, s: &str) {
+        if self.capacity == 0
+            || self
+                .lock
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
+            // writing to the console never blocks, so the output is dropped if the log is currently in use
+            return;
+        }
+        if !self.has_previous() {
+            self.init_header();
+        }
+        // safe as the lock ensures exclusive access to the region
+        unsafe {
+            let mut written = read_volatile(&(*self.header).written);
+            for byte in s.bytes() {
+                write_volatile(self.data.add(written as usize % self.capacity), byte);
+                written += 1;
+                // once wrapped around keep the counter below twice the capacity, so it never overflows
+                if written as usize >= 2 * self.capacity {
+                    written -= self.capacity as u32;
+                }
+            }
+            write_volatile(&mut (*self.header).written, written);
+        }
+        self.lock.store(false, Ordering::Release);
+    }
+}
+
+impl ConsoleImpl for RamLog {
+    fn putc(&self, c: char) {
+        self.write(c.encode_utf8(&mut [0; 4]));
+    }
+
+    fn puts(&self, s: &str) {
+        self.write(s);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{vec, vec::Vec};
+
+    /// the bytes of the log data in the order they have been written
+    fn logged(ramlog: &RamLog) -> Vec<u8> {
+        let header = unsafe { &*ramlog.header };
+        let written = header.written as usize;
+        let (start, len) = if written > ramlog.capacity {
+            (written % ramlog.capacity, ramlog.capacity)
+        } else {
+            (0, written)
+        };
+        (0..len)
+            .map(|idx| unsafe { *ramlog.data.add((start + idx) % ramlog.capacity) })
+            .collect()
+    }
+
+    #[test]
+    fn survives_a_reset() {
+        let mut region = vec![0u32; 16];
+        let base = region.as_mut_ptr() as *mut u8;
+        let ramlog = unsafe { RamLog::new(base, 64) };
+        assert!(!ramlog.has_previous());
+        ramlog.puts("boot 1");
+        // the next boot finds the log of the previous one and appends to it
+        let mut ramlog = unsafe { RamLog::new(base, 64) };
+        assert!(ramlog.has_previous());
+        ramlog.putc('!');
+        assert_eq!(logged(&ramlog), b"boot 1!");
+        ramlog.reset();
+        assert!(ramlog.has_previous());
+        assert_eq!(logged(&ramlog), b"");
+    }
+
+    #[test]
+    fn ignores_a_log_of_a_different_size() {
+        let mut region = vec![0u32; 16];
+        let base = region.as_mut_ptr() as *mut u8;
+        unsafe { RamLog::new(base, 64) }.puts("boot 1");
+        assert!(!unsafe { RamLog::new(base, 32) }.has_previous());
+    }
+
+    #[test]
+    fn overwrites_the_oldest_output_if_full() {
+        let mut region = vec![0u32; 16];
+        let ramlog = unsafe { RamLog::new(region.as_mut_ptr() as *mut u8, 20) };
+        assert_eq!(ramlog.capacity, 8);
+        for _ in 0..5 {
+            ramlog.puts("0123456789");
+        }
+        assert_eq!(logged(&ramlog), b"23456789");
+        // the counter of written bytes stays below twice the capacity
+        assert!(unsafe { (*ramlog.header).written } < 16);
+    }
+}
@@ -0,0 +1,45 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Raw output mode
+//!
+//! Tunnelling a file transfer like XMODEM over the Uart of the console requires the data to reach the output
+//! channel unmodified. In raw mode the console disables all output transformations: the line endings are no longer
+//! translated, the severity prefix is not colorized and log records are written as their bare message without any
+//! prefix or fields. The mode could be enabled for the duration of a scope:
+//! ```ignore
+//! with_raw_mode(|| xmodem_send(&image));
+//! ```
+
+use crate::{Console, CONSOLE};
+
+impl Console {
+    /// Enable or disable the raw mode that disables all output transformations
+    pub fn set_raw(&mut self, enable: bool) {
+        self.raw = enable;
+    }
+
+    /// Check if the raw mode is enabled
+    pub fn raw_enabled(&self) -> bool {
+        self.raw
+    }
+}
+
+/// Run the given function with the raw mode of the console enabled. The output printed before is flushed with the
+/// transformations still applied, and the previous mode is restored once the function returns.
+pub fn with_raw_mode<R>(f: impl FnOnce() -> R) -> R {
+    let previous = CONSOLE.take_for(|console| {
+        console.flush();
+        core::mem::replace(&mut console.raw, true)
+    });
+    let result = f();
+    CONSOLE.take_for(|console| {
+        console.flush();
+        console.raw = previous;
+    });
+    result
+}
@@ -0,0 +1,329 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Log records
+//!
+//! The severity macros pass their message as ``LogRecord`` to the console. Besides the message a record carries
+//! key-value fields given in front of the message:
+//! ```ignore
+//! info!(addr = %ptr, len = len, "mapped region");
+//! ```
+//! A field given as ``key = value`` or ``key = %value`` is formatted with ``Display``, ``key = ?value`` is formatted
+//! with ``Debug``. Output channels that only handle text get the fields appended to the message like
+//! ``I: kernel - mapped region addr=0x8000 len=4096``. Output channels that return a ``RecordSink`` from their
+//! ``ConsoleImpl::records`` receive the structured record instead and may serialize it the way they like.
+//!
+//! With the ``location`` feature the severity macros also capture the source file and line of the message.
+//! Filtering, routing and formatting all operate on the record. The console stamps each record with the timestamp
+//! the core id and the sequence number once before it is passed on, so every output channel sees the same metadata.
+
+use crate::newline::translate;
+use crate::span::indent;
+use crate::truncate::{truncate, LineState};
+use crate::{Console, ConsoleImpl, Level, Newline, SPAN_INDENT};
+use core::{fmt, time::Duration};
+
+/// A key-value field of a log record
+#[derive(Clone, Copy)]
+pub struct Field<'a> {
+    key: &'static str,
+    value: fmt::Arguments<'a>,
+}
+
+impl<'a> Field<'a> {
+    /// Create a new field with the given key and formatted value
+    pub fn new(key: &'static str, value: fmt::Arguments<'a>) -> Self {
+        Field { key, value }
+    }
+
+    /// The key of this field
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// The formatted value of this field
+    pub fn value(&self) -> fmt::Arguments<'a> {
+        self.value
+    }
+}
+
+/// A single message of the severity macros with all its metadata
+#[derive(Clone, Copy)]
+pub struct LogRecord<'a> {
+    level: Level,
+    target: &'a str,
+    args: fmt::Arguments<'a>,
+    fields: &'a [Field<'a>],
+    file: Option<&'a str>,
+    line: Option<u32>,
+    timestamp: Option<Duration>,
+    core_id: Option<usize>,
+    sequence: Option<u64>,
+}
+
+impl<'a> LogRecord<'a> {
+    /// Create a new record of the given severity and target (usually the module path)
+    pub fn new(
+        level: Level,
+        target: &'a str,
+        args: fmt::Arguments<'a>,
+        fields: &'a [Field<'a>],
+    ) -> Self {
+        LogRecord {
+            level,
+            target,
+            args,
+            fields,
+            file: None,
+            line: None,
+            timestamp: None,
+            core_id: None,
+            sequence: None,
+        }
+    }
+
+    /// Set the source file and line this record has been created at
+    pub fn with_location(mut self, file: &'a str, line: u32) -> Self {
+        self.file = Some(file);
+        self.line = Some(line);
+        self
+    }
+
+    /// Set the timestamp of this record
+    pub fn with_timestamp(mut self, timestamp: Duration) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Set the id of the core this record has been created on
+    pub fn with_core_id(mut self, core_id: usize) -> Self {
+        self.core_id = Some(core_id);
+        self
+    }
+
+    /// Set the sequence number of this record
+    pub fn with_sequence(mut self, sequence: u64) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// The severity of this record
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// The target of this record, usually the module path it has been created in
+    pub fn target(&self) -> &'a str {
+        self.target
+    }
+
+    /// The message of this record
+    pub fn args(&self) -> fmt::Arguments<'a> {
+        self.args
+    }
+
+    /// The key-value fields of this record
+    pub fn fields(&self) -> &'a [Field<'a>] {
+        self.fields
+    }
+
+    /// The source file this record has been created in, if known
+    pub fn file(&self) -> Option<&'a str> {
+        self.file
+    }
+
+    /// The source line this record has been created at, if known
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// The timestamp of this record if the console has a timestamp source
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+
+    /// The id of the core this record has been created on if the console has a core id provider
+    pub fn core_id(&self) -> Option<usize> {
+        self.core_id
+    }
+
+    /// The sequence number the console stamped this record with
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+}
+
+/// Output channels that are able to handle structured log records need to implement this trait and return it from
+/// ``ConsoleImpl::records``
+pub trait RecordSink {
+    /// pass a log record to the output channel
+    fn log(&self, record: &LogRecord);
+}
+
+/// ``fmt::Write`` adapter passing the formatted chunks to a single output channel, translating the line endings
+/// with the given newline mode, indenting them by the open spans and cutting lines beyond the line length limit
+struct SinkWriter<'a> {
+    sink: &'a dyn ConsoleImpl,
+    newline: Newline,
+    prev_cr: bool,
+    indent: usize,
+    line_start: bool,
+    max_line: Option<usize>,
+    line: LineState,
+}
+
+impl fmt::Write for SinkWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let sink = self.sink;
+        let max_line = self.max_line;
+        let line = &mut self.line;
+        let (spaces, line_start) = (self.indent, &mut self.line_start);
+        self.prev_cr = translate(self.newline, self.prev_cr, s, |part| {
+            indent(spaces, line_start, part, |part| match max_line {
+                Some(max) => truncate(max, line, part, |part| sink.puts(part)),
+                None => sink.puts(part),
+            })
+        });
+        Ok(())
+    }
+}
+
+impl Console {
+    /// Stamp a log record with the timestamp, the core id and the sequence number and pass it to the output channel
+    /// its severity is routed to, or to the current active console and all additional sinks if there is no such
+    /// route. The record is formatted to text with the formatter of the console for every output channel that does
+    /// not provide a ``RecordSink``. Records logged by an output channel while it is writing are dropped. In
+    /// interrupt context or queued mode the record is formatted to text and deferred. Repeats of the last record are
+    /// counted instead if the repeat suppression is enabled.
+    pub fn log(&self, record: &LogRecord) {
+        if !self.suppress_repeat(record) {
+            self.write_record(record);
+        }
+    }
+
+    /// stamp the record and pass it to the output channels
+    pub(crate) fn write_record(&self, record: &LogRecord) {
+        if self.defers_output() {
+            let record = &self.stamp(record);
+            self.defer(Some(record.level()), |w| self.format_record(record, w));
+            return;
+        }
+        self.write_record_direct(record);
+    }
+
+    /// stamp the record and pass it to the output channels without deferring it, even in interrupt context
+    pub(crate) fn write_record_direct(&self, record: &LogRecord) {
+        let record = &self.stamp(record);
+        self.guarded(|| {
+            match self
+                .routes
+                .iter()
+                .find(|(route, _)| *route == record.level())
+            {
+                Some((_, sink)) => self.log_to(sink.as_ref(), record),
+                None => {
+                    self.log_to(self.get_current(), record);
+                    for sink in self.sink_iter() {
+                        self.log_to(sink, record);
+                    }
+                }
+            }
+        });
+    }
+
+    /// stamp the record with the timestamp, the core id and the sequence number unless the record already carries
+    /// them
+    fn stamp<'a>(&self, record: &LogRecord<'a>) -> LogRecord<'a> {
+        let mut record = *record;
+        if record.timestamp.is_none() {
+            record.timestamp = self.timestamp();
+        }
+        if record.core_id.is_none() {
+            record.core_id = self.core_id();
+        }
+        if record.sequence.is_none() {
+            record.sequence = Some(self.next_sequence());
+        }
+        record
+    }
+
+    fn log_to(&self, sink: &dyn ConsoleImpl, record: &LogRecord) {
+        match sink.records() {
+            Some(records) => records.log(record),
+            None => {
+                let mut writer = SinkWriter {
+                    sink,
+                    newline: if self.raw { Newline::Raw } else { self.newline },
+                    prev_cr: false,
+                    indent: if self.raw {
+                        0
+                    } else {
+                        self.span_depth() * SPAN_INDENT
+                    },
+                    line_start: true,
+                    max_line: if self.raw {
+                        None
+                    } else {
+                        self.max_line_length()
+                    },
+                    line: LineState::default(),
+                };
+                let _ = self.format_record(record, &mut writer);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::String, vec::Vec};
+
+    fn described(record: &LogRecord) -> String {
+        let fields: Vec<String> = record
+            .fields()
+            .iter()
+            .map(|field| format!(" {}={}", field.key(), field.value()))
+            .collect();
+        format!("{} {}: {}{}", record.level(), record.target(), record.args(), fields.concat())
+    }
+
+    #[test]
+    fn carries_the_fields() {
+        let len = 4096;
+        assert_eq!(
+            described(&LogRecord::new(
+                Level::Info,
+                "mmu",
+                format_args!("mapped {}", "region"),
+                &[
+                    Field::new("addr", format_args!("{:#x}", 0x8000)),
+                    Field::new("len", format_args!("{}", len)),
+                ],
+            )),
+            "INFO mmu: mapped region addr=0x8000 len=4096"
+        );
+    }
+
+    #[test]
+    fn carries_the_metadata_once_set() {
+        let record = LogRecord::new(Level::Warn, "sdhci", format_args!("timeout"), &[]);
+        assert_eq!(record.file(), None);
+        assert_eq!(record.line(), None);
+        assert_eq!(record.timestamp(), None);
+        assert_eq!(record.core_id(), None);
+        let record = record
+            .with_location("src/sdhci.rs", 42)
+            .with_timestamp(Duration::from_millis(1500))
+            .with_core_id(3);
+        assert_eq!(record.file(), Some("src/sdhci.rs"));
+        assert_eq!(record.line(), Some(42));
+        assert_eq!(record.timestamp(), Some(Duration::from_millis(1500)));
+        assert_eq!(record.core_id(), Some(3));
+        assert_eq!(format!("{}", record.args()), "timeout");
+    }
+}
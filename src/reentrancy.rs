@@ -0,0 +1,81 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Reentrancy guard
+//!
+//! An output channel that prints through the console itself, e.g. a driver logging its own errors with ``error!``
+//! from within ``puts``, would re-enter the console while it is still busy with the outer message. Depending on the
+//! locking of the output channel this ends in a deadlock or endless recursion. The console therefore marks itself as
+//! busy for the core that currently passes output to the channels and drops nested output of the same core.
+//! Other cores are not affected.
+//!
+//! The core is identified with the core id provider of the console. Without a provider all output is treated as if
+//! it comes from core 0. Core ids above ``MAX_CORES`` share their slot with ``core_id % MAX_CORES``.
+
+use crate::Console;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The number of cores the console tracks separately
+pub const MAX_CORES: usize = 4;
+
+/// The busy state of the console for each core
+pub(crate) struct ReentrancyGuard {
+    busy: [AtomicBool; MAX_CORES],
+}
+
+impl ReentrancyGuard {
+    pub(crate) const fn new() -> Self {
+        ReentrancyGuard {
+            busy: [
+                AtomicBool::new(false),
+                AtomicBool::new(false),
+                AtomicBool::new(false),
+                AtomicBool::new(false),
+            ],
+        }
+    }
+
+    /// Mark the console busy for the given core. ``None`` if it is already busy on this core.
+    fn enter(&self, core: usize) -> Option<Entered<'_>> {
+        let busy = &self.busy[core % MAX_CORES];
+        if busy.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            Some(Entered(busy))
+        }
+    }
+}
+
+/// Marks the console busy for a core until dropped
+struct Entered<'a>(&'a AtomicBool);
+
+impl Drop for Entered<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl Console {
+    /// Run the given output function unless the console is muted or already busy on the current core. ``None`` if
+    /// the output has been dropped. Other cores wait until the function returns.
+    pub(crate) fn guarded<R>(&self, f: impl FnOnce() -> R) -> Option<R> {
+        if self.is_muted() {
+            return None;
+        }
+        self.exclusive(|| {
+            let _entered = self.guard.enter(self.core_id().unwrap_or(0))?;
+            self.wait_for_xon();
+            Some(f())
+        })
+    }
+
+    /// Check if the console is currently passing output to the channels on the current core, e.g. to find out
+    /// whether a ``println!`` in a driver would be dropped as nested output
+    pub fn is_busy(&self) -> bool {
+        self.guard.busy[self.core_id().unwrap_or(0) % MAX_CORES].load(Ordering::Acquire)
+    }
+}
@@ -0,0 +1,107 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Named consoles
+//!
+//! Besides the anonymous active console several output channels could be registered with a name and selected or
+//! combined at runtime:
+//! ```ignore
+//! CONSOLE.take_for(|cons| {
+//!     cons.register("uart0", uart);
+//!     cons.register("fb", framebuffer);
+//!     cons.register("mem", RamLog::new(base, size));
+//!     cons.select(&["uart0", "mem"]);
+//! });
+//! ```
+//! Every selected console receives the same output as the additional sinks. Registered consoles that are not
+//! selected keep their state and could be selected again later on.
+
+use crate::{Console, ConsoleImpl};
+use alloc::boxed::Box;
+
+/// A console registered with a name
+pub(crate) struct NamedConsole {
+    name: &'static str,
+    console: Box<dyn ConsoleImpl>,
+    selected: bool,
+}
+
+impl Console {
+    /// Register an output channel with the given name. It does not receive any output until it is selected. A console
+    /// registered with the same name before is flushed, closed and dropped. The Console takes ownership of the
+    /// console.
+    pub fn register<T: ConsoleImpl + 'static>(&mut self, name: &'static str, console: T) {
+        if let Some(mut previous) = self.unregister(name) {
+            previous.close();
+        }
+        self.registry.push(NamedConsole {
+            name,
+            console: Box::from(console),
+            selected: false,
+        });
+    }
+
+    /// Remove the console registered with the given name. It is flushed and handed back without being closed.
+    pub fn unregister(&mut self, name: &str) -> Option<Box<dyn ConsoleImpl>> {
+        let idx = self.registry.iter().position(|named| named.name == name)?;
+        let named = self.registry.remove(idx);
+        named.console.flush();
+        Some(named.console)
+    }
+
+    /// Select exactly the registered consoles with the given names to receive the output. Returns ``false`` if one
+    /// of the names is not registered, the known ones are selected anyway.
+    pub fn select(&mut self, names: &[&str]) -> bool {
+        for named in self.registry.iter_mut() {
+            named.selected = names.contains(&named.name);
+        }
+        names.iter().all(|name| self.is_registered(name))
+    }
+
+    /// Select or deselect a single registered console while keeping the selection of the others. Returns ``false``
+    /// if there is no console registered with this name.
+    pub fn set_selected(&mut self, name: &str, selected: bool) -> bool {
+        match self.registry.iter_mut().find(|named| named.name == name) {
+            Some(named) => {
+                if named.selected && !selected {
+                    named.console.flush();
+                }
+                named.selected = selected;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check if a console is registered with the given name
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.registry.iter().any(|named| named.name == name)
+    }
+
+    /// Check if the console registered with the given name is selected
+    pub fn is_selected(&self, name: &str) -> bool {
+        self.registry
+            .iter()
+            .any(|named| named.name == name && named.selected)
+    }
+
+    /// The names of all registered consoles in the order they have been registered
+    pub fn registered(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.registry.iter().map(|named| named.name)
+    }
+
+    /// all output channels receiving the output besides the current active console: the additional sinks followed by
+    /// the selected registered consoles
+    pub(crate) fn sink_iter(&self) -> impl Iterator<Item = &dyn ConsoleImpl> {
+        self.sinks.iter().map(|sink| sink.as_ref()).chain(
+            self.registry
+                .iter()
+                .filter(|named| named.selected)
+                .map(|named| named.console.as_ref()),
+        )
+    }
+}
@@ -0,0 +1,137 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Repeat suppression
+//!
+//! A polling loop that fails over and over again prints the same log message consecutively. Once the repeat
+//! suppression is enabled the console collapses those repeats like syslog does:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_suppress_repeats(true));
+//! ```
+//! The first message is printed as usual, the following identical ones are counted. As soon as a different message
+//! is logged or the console is flushed a summary like ``W: console - last message repeated 41 times`` is printed with
+//! the severity of the repeated message. Two records are identical if their severity, target, message and fields
+//! match, the timestamp and the core id are not compared.
+
+use crate::{Console, Level, LogRecord};
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+
+/// The state of the repeat suppression
+pub(crate) struct RepeatState {
+    /// the hash of the last record
+    last: AtomicU64,
+    /// the level of the last record
+    level: AtomicU8,
+    /// the number of repeats of the last record suppressed so far
+    repeats: AtomicUsize,
+}
+
+impl RepeatState {
+    pub(crate) const fn new() -> Self {
+        RepeatState {
+            last: AtomicU64::new(0),
+            level: AtomicU8::new(0),
+            repeats: AtomicUsize::new(0),
+        }
+    }
+
+    /// take the number of suppressed repeats and the level of the repeated record
+    fn take_repeats(&self) -> Option<(Level, usize)> {
+        let repeats = self.repeats.swap(0, Ordering::AcqRel);
+        let level = Level::ALL
+            .iter()
+            .copied()
+            .find(|level| *level as u8 == self.level.load(Ordering::Acquire))?;
+        if repeats > 0 {
+            Some((level, repeats))
+        } else {
+            None
+        }
+    }
+}
+
+/// FNV-1a hash of the formatted parts of a record
+struct Hasher(u64);
+
+impl fmt::Write for Hasher {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        Ok(())
+    }
+}
+
+fn hash(record: &LogRecord) -> u64 {
+    let mut hasher = Hasher(0xcbf2_9ce4_8422_2325);
+    let _ = write!(
+        hasher,
+        "{}\0{}\0{}",
+        record.level() as u8,
+        record.target(),
+        record.args()
+    );
+    for field in record.fields() {
+        let _ = write!(hasher, "\0{}={}", field.key(), field.value());
+    }
+    hasher.0
+}
+
+impl Console {
+    /// Enable or disable the suppression of consecutive identical log records
+    pub fn set_suppress_repeats(&mut self, enable: bool) {
+        if !enable {
+            self.flush_repeats();
+        }
+        self.suppress_repeats = enable;
+    }
+
+    /// Check if the suppression of consecutive identical log records is enabled
+    pub fn suppress_repeats_enabled(&self) -> bool {
+        self.suppress_repeats
+    }
+
+    /// Check if the record repeats the last one and count it in this case. Otherwise the summary of the repeats of
+    /// the last record is printed and the record becomes the last one. Returns ``true`` if the record is suppressed.
+    pub(crate) fn suppress_repeat(&self, record: &LogRecord) -> bool {
+        if !self.suppress_repeats {
+            return false;
+        }
+        let hash = hash(record);
+        if self.repeat.last.swap(hash, Ordering::AcqRel) == hash {
+            self.repeat.repeats.fetch_add(1, Ordering::AcqRel);
+            return true;
+        }
+        self.flush_repeats();
+        self.repeat
+            .level
+            .store(record.level() as u8, Ordering::Release);
+        false
+    }
+
+    /// print the summary of the suppressed repeats of the last record if there are any
+    pub(crate) fn flush_repeats(&self) {
+        if let Some((level, repeats)) = self.repeat.take_repeats() {
+            match repeats {
+                1 => self.write_record(&LogRecord::new(
+                    level,
+                    "console",
+                    format_args!("last message repeated once"),
+                    &[],
+                )),
+                _ => self.write_record(&LogRecord::new(
+                    level,
+                    "console",
+                    format_args!("last message repeated {} times", repeats),
+                    &[],
+                )),
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Segger RTT output
+//!
+//! Available with the ``rtt`` feature. The ``RttConsole`` writes the output into the up buffer of a Segger Real Time
+//! Transfer control block in memory. A J-Link probe attached to the debug port reads the buffer while the core is
+//! running, so the output is available without wiring up a Uart:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.replace(RttConsole::new()));
+//! ```
+//! The control block is exported as ``_SEGGER_RTT`` and holds a single up buffer of ``RTT_BUFFER_SIZE`` bytes and a
+//! small down buffer the characters typed in the RTT viewer are received from. The probe might not be connected at
+//! all, so output that does not fit into the up buffer is dropped instead of waiting for the probe to read it.
+
+use crate::{ConsoleImpl, ConsoleInput, ConsoleLock, SpinLock};
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// The size of the up buffer the output is written to
+pub const RTT_BUFFER_SIZE: usize = 1024;
+
+/// The size of the down buffer the input is received from
+const RTT_INPUT_SIZE: usize = 16;
+
+/// The id the probe searches the memory for, written once the control block is initialized
+const RTT_ID: [u8; 16] = *b"SEGGER RTT\0\0\0\0\0\0";
+
+/// A ring buffer of the control block, the layout is defined by Segger
+#[repr(C)]
+struct Channel {
+    name: *const u8,
+    buffer: *mut u8,
+    size: u32,
+    /// the offset the target writes to next in the up buffer, the probe in the down buffer
+    write: AtomicU32,
+    /// the offset the probe reads from next in the up buffer, the target in the down buffer
+    read: AtomicU32,
+    flags: u32,
+}
+
+/// The RTT control block with one up and one down channel, the layout is defined by Segger
+#[repr(C)]
+struct ControlBlock {
+    id: UnsafeCell<[u8; 16]>,
+    max_up: i32,
+    max_down: i32,
+    up: Channel,
+    down: Channel,
+}
+
+// safe as the buffers are only accessed with the offsets and the lock of the console
+unsafe impl Sync for ControlBlock {}
+
+/// The memory of a ring buffer
+struct Buffer<const N: usize>(UnsafeCell<[u8; N]>);
+
+// safe as the buffers are only accessed with the offsets of their channel
+unsafe impl<const N: usize> Sync for Buffer<N> {}
+
+static UP_BUFFER: Buffer<RTT_BUFFER_SIZE> = Buffer(UnsafeCell::new([0; RTT_BUFFER_SIZE]));
+static DOWN_BUFFER: Buffer<RTT_INPUT_SIZE> = Buffer(UnsafeCell::new([0; RTT_INPUT_SIZE]));
+static NAME: &[u8] = b"Terminal\0";
+
+#[no_mangle]
+static _SEGGER_RTT: ControlBlock = ControlBlock {
+    id: UnsafeCell::new([0; 16]),
+    max_up: 1,
+    max_down: 1,
+    up: Channel {
+        name: NAME.as_ptr(),
+        buffer: UP_BUFFER.0.get() as *mut u8,
+        size: RTT_BUFFER_SIZE as u32,
+        write: AtomicU32::new(0),
+        read: AtomicU32::new(0),
+        flags: 0,
+    },
+    down: Channel {
+        name: NAME.as_ptr(),
+        buffer: DOWN_BUFFER.0.get() as *mut u8,
+        size: RTT_INPUT_SIZE as u32,
+        write: AtomicU32::new(0),
+        read: AtomicU32::new(0),
+        flags: 0,
+    },
+};
+
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+static LOCK: SpinLock = SpinLock::new();
+
+/// The output channel writing to the RTT control block. All instances share the same control block.
+pub struct RttConsole;
+
+impl RttConsole {
+    /// Create a new output channel writing to the RTT control block. The control block is initialized with the
+    /// first call.
+    pub fn new() -> Self {
+        if !INITIALIZED.swap(true, Ordering::AcqRel) {
+            // write the id backwards, so the probe does not find a partial id, and byte by byte, so the complete id
+            // is not placed anywhere else in memory
+            let id = _SEGGER_RTT.id.get() as *mut u8;
+            for idx in (0..RTT_ID.len()).rev() {
+                // safe as the id is only written once
+                unsafe { core::ptr::write_volatile(id.add(idx), RTT_ID[idx]) };
+            }
+        }
+        RttConsole
+    }
+
+    /// write as many bytes into the up buffer as fit into it
+    fn write_bytes(&self, bytes: &[u8]) {
+        let up = &_SEGGER_RTT.up;
+        LOCK.lock();
+        let read = up.read.load(Ordering::Acquire) as usize;
+        let mut write = up.write.load(Ordering::Relaxed) as usize;
+        // one byte stays free to distinguish a full buffer from an empty one
+        let free = if read > write {
+            read - write - 1
+        } else {
+            RTT_BUFFER_SIZE - write + read - 1
+        };
+        for byte in bytes.iter().take(free) {
+            // safe as the write offset is always inside the buffer and the lock ensures a single writer
+            unsafe { core::ptr::write_volatile(up.buffer.add(write), *byte) };
+            write = (write + 1) % RTT_BUFFER_SIZE;
+        }
+        up.write.store(write as u32, Ordering::Release);
+        LOCK.unlock();
+    }
+}
+
+impl Default for RttConsole {
+    fn default() -> Self {
+        RttConsole::new()
+    }
+}
+
+impl ConsoleImpl for RttConsole {
+    fn putc(&self, c: char) {
+        self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    fn puts(&self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        self.write_bytes(bytes);
+    }
+
+    fn input(&self) -> Option<&dyn ConsoleInput> {
+        Some(self)
+    }
+}
+
+/// The characters are received as single bytes, so only ASCII input is received correctly
+impl ConsoleInput for RttConsole {
+    fn try_getc(&self) -> Option<char> {
+        let down = &_SEGGER_RTT.down;
+        LOCK.lock();
+        let write = down.write.load(Ordering::Acquire) as usize;
+        let read = down.read.load(Ordering::Relaxed) as usize;
+        let c = if read != write {
+            // safe as the read offset is always inside the buffer and the lock ensures a single reader
+            let byte = unsafe { core::ptr::read_volatile(down.buffer.add(read)) };
+            down.read
+                .store(((read + 1) % RTT_INPUT_SIZE) as u32, Ordering::Release);
+            Some(byte as char)
+        } else {
+            None
+        };
+        LOCK.unlock();
+        c
+    }
+}
@@ -0,0 +1,58 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Screen control
+//!
+//! Simple full screen status displays move the cursor of an ANSI terminal and clear the screen or single lines
+//! without embedding raw escape sequences:
+//! ```ignore
+//! clear_screen();
+//! loop {
+//!     cursor_to(2, 0);
+//!     clear_line();
+//!     print!("temperature: {}°C", temperature());
+//! }
+//! ```
+//! A scroll region keeps a header or a footer in place while the output scrolls in between:
+//! ```ignore
+//! clear_screen();
+//! println!("=== kernel monitor ===");
+//! set_scroll_region(1, 24);
+//! cursor_to(1, 0);
+//! ```
+//! The escape sequences are passed to the current active console and all additional sinks like any other output,
+//! so they should only be used if all of them are connected to an ANSI terminal. Rows and columns are counted from
+//! zero starting at the top left corner. The ``CharDisplayConsole`` and the ``FrameBufferConsole`` emulate these
+//! sequences. The ``ansi`` module provides the sequences these helpers are built of.
+
+use crate::ansi::{Cursor, Erase, Scroll};
+
+/// Move the cursor to the given row and column
+pub fn cursor_to(row: usize, col: usize) {
+    Cursor::To(row, col).print();
+}
+
+/// Clear the whole screen and move the cursor to the top left corner
+pub fn clear_screen() {
+    crate::print_args(format_args!("{}{}", Erase::Screen, Cursor::To(0, 0)));
+}
+
+/// Clear the line of the cursor and move the cursor to the start of the line
+pub fn clear_line() {
+    crate::print_args(format_args!("{}\r", Erase::Line));
+}
+
+/// Restrict the scrolling of the terminal to the rows ``top`` to ``bottom``, e.g. to keep a header or a footer in
+/// place while the output scrolls in between. The cursor moves to the top left corner.
+pub fn set_scroll_region(top: usize, bottom: usize) {
+    Scroll::Region(top, bottom).print();
+}
+
+/// Scroll all rows of the terminal again
+pub fn reset_scroll_region() {
+    Scroll::Reset.print();
+}
@@ -0,0 +1,99 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # ARM semihosting output
+//!
+//! Available with the ``semihosting`` feature. The ``SemihostingConsole`` passes the output to the debugger or
+//! emulator with the ``SYS_WRITE0`` semihosting call, so the output of a kernel started in QEMU with
+//! ``-semihosting`` or under a debugger is visible before any peripheral has been initialized:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.replace(SemihostingConsole));
+//! ```
+//! A semihosting call halts the core until the debugger has handled it. Without a debugger or an emulator with
+//! semihosting enabled the call ends in an exception, so this console is meant to be used during development only.
+//! On architectures other than ``aarch64`` and ``arm`` the output is dropped.
+
+use crate::ConsoleImpl;
+
+/// The semihosting operation writing a NUL terminated string to the debug console
+const SYS_WRITE0: usize = 0x04;
+
+/// The size of the buffer the output is NUL terminated in before it is passed to the debugger
+const CHUNK_SIZE: usize = 64;
+
+/// perform a semihosting call with the given operation and parameter
+#[cfg(target_arch = "aarch64")]
+unsafe fn semihosting_call(op: usize, param: *const u8) -> usize {
+    let result;
+    core::arch::asm!("hlt #0xf000", inout("x0") op => result, in("x1") param, options(nostack));
+    result
+}
+
+/// perform a semihosting call with the given operation and parameter
+#[cfg(all(target_arch = "arm", not(target_feature = "thumb-mode")))]
+unsafe fn semihosting_call(op: usize, param: *const u8) -> usize {
+    let result;
+    core::arch::asm!("svc #0x123456", inout("r0") op => result, in("r1") param, options(nostack));
+    result
+}
+
+/// perform a semihosting call with the given operation and parameter
+#[cfg(all(target_arch = "arm", target_feature = "thumb-mode"))]
+unsafe fn semihosting_call(op: usize, param: *const u8) -> usize {
+    let result;
+    core::arch::asm!("bkpt #0xab", inout("r0") op => result, in("r1") param, options(nostack));
+    result
+}
+
+/// there is no semihosting on this architecture
+#[cfg(not(any(target_arch = "aarch64", target_arch = "arm")))]
+unsafe fn semihosting_call(_: usize, _: *const u8) -> usize {
+    0
+}
+
+/// The output channel passing the output to the debugger with semihosting calls
+pub struct SemihostingConsole;
+
+impl SemihostingConsole {
+    /// pass the bytes to the debugger in NUL terminated chunks. NUL characters of the output are skipped as they
+    /// would terminate the chunk.
+    fn write_bytes(&self, bytes: &[u8]) {
+        let mut chunk = [0; CHUNK_SIZE + 1];
+        let mut len = 0;
+        for byte in bytes.iter().filter(|byte| **byte != 0) {
+            chunk[len] = *byte;
+            len += 1;
+            if len == CHUNK_SIZE {
+                self.write_chunk(&mut chunk, len);
+                len = 0;
+            }
+        }
+        if len > 0 {
+            self.write_chunk(&mut chunk, len);
+        }
+    }
+
+    fn write_chunk(&self, chunk: &mut [u8; CHUNK_SIZE + 1], len: usize) {
+        chunk[len] = 0;
+        // safe as the chunk is NUL terminated and the debugger only reads it
+        unsafe { semihosting_call(SYS_WRITE0, chunk.as_ptr()) };
+    }
+}
+
+impl ConsoleImpl for SemihostingConsole {
+    fn putc(&self, c: char) {
+        self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    fn puts(&self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        self.write_bytes(bytes);
+    }
+}
@@ -0,0 +1,38 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Sequence numbers
+//!
+//! The console stamps every log record with a monotonically increasing sequence number. Records deferred from
+//! interrupt context or written by several cores may reach the output channels out of order, the sequence number
+//! allows host side tooling to detect gaps and to sort them again. The number is printed once enabled:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_sequence(true));
+//! info!("mapped region");
+//! // #42 I: kernel - mapped region
+//! ```
+//! Records suppressed by the filter or the repeat suppression do not consume a sequence number.
+
+use crate::Console;
+use core::sync::atomic::Ordering;
+
+impl Console {
+    /// Enable or disable the sequence number in the formatted log records
+    pub fn set_sequence(&mut self, enable: bool) {
+        self.sequence = enable;
+    }
+
+    /// Check if the sequence number is part of the formatted log records
+    pub fn sequence_enabled(&self) -> bool {
+        self.sequence
+    }
+
+    /// the sequence number of the next log record
+    pub(crate) fn next_sequence(&self) -> u64 {
+        self.next_sequence.fetch_add(1, Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,91 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # ``embedded-hal`` serial adapter
+//!
+//! Available with the ``embedded-hal`` feature. The ``SerialConsole`` turns any serial writer implementing
+//! ``embedded_hal::serial::Write<u8>``, like the Uart driver of a HAL crate, into an output channel of the console:
+//! ```ignore
+//! let serial = hal::serial::Serial::new(peripherals.UART0, pins, 115_200.bps());
+//! CONSOLE.take_for(|cons| cons.replace(SerialConsole::new(serial)));
+//! ```
+//! The bytes are written blocking until the writer accepts them. The writer is locked while a string is written, so
+//! the console is able to use it with a shared reference. An interrupt handler printing on the same core while the
+//! writer is locked waits forever, so either an interrupt detector needs to defer the output of interrupt handlers
+//! or the ``IrqSafeLock`` masks the interrupts while the console is used.
+
+use crate::{ConsoleError, ConsoleImpl, Locked};
+use embedded_hal::serial::Write;
+
+/// The output channel writing to a serial writer of ``embedded-hal``
+pub struct SerialConsole<W> {
+    serial: Locked<W>,
+}
+
+impl<W> SerialConsole<W> {
+    /// Create a new output channel writing to the given serial writer
+    pub const fn new(serial: W) -> Self {
+        SerialConsole {
+            serial: Locked::new(serial),
+        }
+    }
+
+    /// Hand back the serial writer
+    pub fn into_inner(self) -> W {
+        self.serial.into_inner()
+    }
+}
+
+impl<W: Write<u8>> SerialConsole<W> {
+    /// write all bytes, waiting until the writer accepts each of them. Stops at the first byte the writer failed to
+    /// write.
+    fn write_bytes(&self, bytes: &[u8]) -> Result<(), ConsoleError> {
+        self.serial.with(|serial| {
+            for byte in bytes {
+                block(|| serial.write(*byte))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// retry the given operation until it does not signal ``WouldBlock`` any longer
+fn block<E>(mut op: impl FnMut() -> nb::Result<(), E>) -> Result<(), ConsoleError> {
+    loop {
+        match op() {
+            Ok(()) => return Ok(()),
+            Err(nb::Error::WouldBlock) => core::hint::spin_loop(),
+            Err(nb::Error::Other(_)) => return Err(ConsoleError::Failed),
+        }
+    }
+}
+
+impl<W: Write<u8>> ConsoleImpl for SerialConsole<W> {
+    fn putc(&self, c: char) {
+        let _ = self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    fn puts(&self, s: &str) {
+        let _ = self.write_bytes(s.as_bytes());
+    }
+
+    fn try_putc(&self, c: char) -> Result<(), ConsoleError> {
+        self.write_bytes(c.encode_utf8(&mut [0; 4]).as_bytes())
+    }
+
+    fn try_puts(&self, s: &str) -> Result<(), ConsoleError> {
+        self.write_bytes(s.as_bytes())
+    }
+
+    fn puts_bytes(&self, bytes: &[u8]) {
+        let _ = self.write_bytes(bytes);
+    }
+
+    fn flush(&self) {
+        let _ = self.serial.with(|serial| block(|| serial.flush()));
+    }
+}
@@ -0,0 +1,174 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Debug shell
+//!
+//! The ``Shell`` turns the console into a small debug monitor. Commands are registered with a name, a help text and
+//! a handler receiving the arguments of the command:
+//! ```ignore
+//! let mut shell = Shell::new("rpi> ");
+//! shell.register("peek", "peek <addr> - print the word at the address", |args| {
+//!     let addr = args.first().ok_or("address missing")?;
+//!     let addr = usize::from_str_radix(addr, 16).map_err(|_| "invalid address")?;
+//!     println!("{:#010x}", unsafe { core::ptr::read_volatile(addr as *const u32) });
+//!     Ok(())
+//! });
+//! shell.run();
+//! ```
+//! ``run`` reads the lines with a ``LineEditor`` and executes them until ``exit`` is entered or the console does not
+//! provide any further input. Arguments are separated by whitespace, double quotes group an argument containing
+//! whitespace. The commands ``help`` and ``exit`` are built in, the error returned by a handler is printed below the
+//! command.
+
+use crate::LineEditor;
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+/// The handler of a shell command receiving the arguments following the command name
+pub type CommandHandler = Box<dyn FnMut(&[&str]) -> Result<(), String>>;
+
+/// A command registered with the shell
+struct Command {
+    name: &'static str,
+    help: &'static str,
+    handler: CommandHandler,
+}
+
+/// A shell executing the registered commands read from the current active console
+pub struct Shell {
+    prompt: &'static str,
+    commands: Vec<Command>,
+    editor: LineEditor,
+}
+
+impl Shell {
+    /// Create a shell without any command, printing the given prompt in front of every line
+    pub fn new(prompt: &'static str) -> Self {
+        Shell {
+            prompt,
+            commands: Vec::new(),
+            editor: LineEditor::new(),
+        }
+    }
+
+    /// Register a command with the given name and help text. A command registered with the same name before is
+    /// replaced.
+    pub fn register<F>(&mut self, name: &'static str, help: &'static str, handler: F)
+    where
+        F: FnMut(&[&str]) -> Result<(), String> + 'static,
+    {
+        self.unregister(name);
+        self.commands.push(Command {
+            name,
+            help,
+            handler: Box::new(handler),
+        });
+    }
+
+    /// Remove the command registered with the given name. Returns ``false`` if there is none.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let count = self.commands.len();
+        self.commands.retain(|command| command.name != name);
+        self.commands.len() != count
+    }
+
+    /// The names of all registered commands in the order they have been registered
+    pub fn commands(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.commands.iter().map(|command| command.name)
+    }
+
+    /// The line editor reading the commands, e.g. to configure the completion
+    pub fn editor_mut(&mut self) -> &mut LineEditor {
+        &mut self.editor
+    }
+
+    /// Read and execute the commands until ``exit`` is entered or the current active console does not provide any
+    /// further input
+    pub fn run(&mut self) {
+        while let Some(line) = self.editor.read_line(self.prompt) {
+            if !self.execute(&line) {
+                break;
+            }
+        }
+    }
+
+    /// Parse and execute a single command line and print its error if it fails. Returns ``false`` if the line
+    /// requests to leave the shell.
+    pub fn execute(&mut self, line: &str) -> bool {
+        let words = split(line);
+        let words: Vec<&str> = words.iter().map(|word| word.as_str()).collect();
+        let (name, args) = match words.split_first() {
+            Some(split) => split,
+            None => return true,
+        };
+        match *name {
+            "exit" => return false,
+            "help" => self.print_help(),
+            _ => match self
+                .commands
+                .iter_mut()
+                .find(|command| command.name == *name)
+            {
+                Some(command) => {
+                    if let Err(err) = (command.handler)(args) {
+                        crate::print_args(format_args!("{}: {}\r\n", name, err));
+                    }
+                }
+                None => crate::print_args(format_args!("unknown command: {}\r\n", name)),
+            },
+        }
+        true
+    }
+
+    /// print the help texts of all commands
+    fn print_help(&self) {
+        for command in &self.commands {
+            crate::print_args(format_args!("{:<12}{}\r\n", command.name, command.help));
+        }
+        crate::print_args(format_args!("{:<12}{}\r\n", "help", "print this help"));
+        crate::print_args(format_args!("{:<12}{}\r\n", "exit", "leave the shell"));
+    }
+}
+
+/// split a command line into its whitespace separated words, double quotes group words containing whitespace
+fn split(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_words() {
+        assert_eq!(split("  peek 0x8000\t16 "), ["peek", "0x8000", "16"]);
+        assert!(split("   ").is_empty());
+    }
+
+    #[test]
+    fn groups_quoted_words() {
+        assert_eq!(
+            split("echo \"hello world\" x"),
+            ["echo", "hello world", "x"]
+        );
+        assert_eq!(split("set name \"\""), ["set", "name", ""]);
+        assert_eq!(split("a\"b c\"d"), ["ab cd"]);
+    }
+}
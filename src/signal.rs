@@ -0,0 +1,59 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Break handler
+//!
+//! Long running operations can be interrupted from the attached terminal. The break handler is called as soon as the
+//! console reads a Ctrl-C from the input channel:
+//! ```ignore
+//! static ABORT: AtomicBool = AtomicBool::new(false);
+//!
+//! CONSOLE.take_for(|cons| cons.set_break_handler(|| ABORT.store(true, Ordering::Relaxed)));
+//! ```
+//! A driver that detects a break condition on the line, e.g. the Uart in its interrupt handler, calls
+//! ``signal_break`` to trigger the handler as well. The handler runs while the console is in use and must not
+//! change the console itself. The Ctrl-C is still passed on to the reader.
+
+use crate::{Console, CONSOLE};
+use alloc::boxed::Box;
+
+/// The character terminals send for Ctrl-C
+pub const CTRL_C: char = '\u{3}';
+
+impl Console {
+    /// Set the function called once a Ctrl-C is received or a break is signaled. The Console takes ownership of the
+    /// handler.
+    pub fn set_break_handler<F: Fn() + 'static>(&mut self, handler: F) {
+        self.break_handler.replace(Box::new(handler));
+    }
+
+    /// Remove the break handler. Ctrl-C is received like any other character again.
+    pub fn clear_break_handler(&mut self) {
+        self.break_handler = None;
+    }
+
+    /// Call the break handler if there is one
+    pub fn signal_break(&self) {
+        if let Some(ref handler) = self.break_handler {
+            handler();
+        }
+    }
+
+    /// call the break handler if the received character is a Ctrl-C
+    pub(crate) fn check_break(&self, c: char) -> char {
+        if c == CTRL_C {
+            self.signal_break();
+        }
+        c
+    }
+}
+
+/// Call the break handler of the console, e.g. from the interrupt handler of a driver that detected a break
+/// condition on the line
+pub fn signal_break() {
+    CONSOLE.use_for(|console| console.signal_break());
+}
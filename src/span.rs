@@ -0,0 +1,150 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Spans
+//!
+//! Nested initialization sequences are easier to follow if the output of every step is indented below the step.
+//! The ``span!`` macro prints an enter marker, runs the given block with all console output indented by
+//! ``SPAN_INDENT`` spaces and prints an exit marker once the block is done:
+//! ```ignore
+//! span!("mmu init", {
+//!     info!("mapping kernel");
+//!     span!("page tables", { build_page_tables() });
+//! });
+//! ```
+//! prints
+//! ```text
+//! > mmu init #1
+//!   I: kernel::mmu - mapping kernel
+//!   > page tables #2
+//!   < page tables #2 took 1.2ms
+//! < mmu init #1 took 3.4ms
+//! ```
+//! Every span gets a unique id, so the markers of interleaved spans of several cores can be matched. The duration
+//! is measured with the timestamp source of the console and not printed without one. ``Span::enter`` opens a span
+//! for the lifetime of the returned guard. The indentation is shared by all cores.
+
+use crate::{Console, CONSOLE};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
+
+/// The number of spaces the output is indented with per open span
+pub const SPAN_INDENT: usize = 2;
+
+/// The spaces the output is indented with, passed in chunks of this size
+const SPACES: &str = "                ";
+
+/// The number of open spans and whether the next output starts a line
+pub(crate) struct SpanState {
+    depth: AtomicUsize,
+    line_start: AtomicBool,
+    /// the id of the next span
+    next_id: AtomicU64,
+}
+
+impl SpanState {
+    pub(crate) const fn new() -> Self {
+        SpanState {
+            depth: AtomicUsize::new(0),
+            line_start: AtomicBool::new(true),
+            next_id: AtomicU64::new(1),
+        }
+    }
+}
+
+/// An open span, closed once it is dropped
+pub struct Span {
+    name: &'static str,
+    id: u64,
+    start: Option<Duration>,
+}
+
+impl Span {
+    /// Print the enter marker of the span and indent all following output until the span is dropped
+    pub fn enter(name: &'static str) -> Self {
+        let id = CONSOLE.use_for(|console| console.spans.next_id.fetch_add(1, Ordering::Relaxed));
+        crate::print_args(format_args!("> {} #{}\r\n", name, id));
+        CONSOLE.use_for(|console| console.spans.depth.fetch_add(1, Ordering::AcqRel));
+        Span {
+            name,
+            id,
+            start: crate::timestamp(),
+        }
+    }
+
+    /// The name of the span
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The unique id of the span
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The time elapsed since the span has been entered. ``None`` without a timestamp source.
+    pub fn elapsed(&self) -> Option<Duration> {
+        Some(crate::timestamp()?.saturating_sub(self.start?))
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = self.elapsed();
+        CONSOLE.use_for(|console| console.spans.depth.fetch_sub(1, Ordering::AcqRel));
+        match elapsed {
+            Some(elapsed) => crate::print_args(format_args!(
+                "< {} #{} took {:?}\r\n",
+                self.name, self.id, elapsed
+            )),
+            None => crate::print_args(format_args!("< {} #{}\r\n", self.name, self.id)),
+        }
+    }
+}
+
+impl Console {
+    /// The number of spans currently open
+    pub fn span_depth(&self) -> usize {
+        self.spans.depth.load(Ordering::Acquire)
+    }
+
+    /// indent every line of the string by the open spans and pass it to the output function
+    pub(crate) fn indented(&self, s: &str, mut output: impl FnMut(&str)) {
+        let mut line_start = self.spans.line_start.load(Ordering::Relaxed);
+        indent(
+            self.span_depth() * SPAN_INDENT,
+            &mut line_start,
+            s,
+            &mut output,
+        );
+        self.spans.line_start.store(line_start, Ordering::Relaxed);
+    }
+}
+
+/// Pass the string to the output function with every line indented by the given number of spaces. ``line_start``
+/// tells whether the string starts a new line and is updated for the next one.
+pub(crate) fn indent(spaces: usize, line_start: &mut bool, s: &str, mut output: impl FnMut(&str)) {
+    if spaces == 0 {
+        if let Some(last) = s.chars().last() {
+            *line_start = last == '\r' || last == '\n';
+        }
+        output(s);
+        return;
+    }
+    for segment in s.split_inclusive(['\r', '\n']) {
+        if *line_start && !segment.starts_with(['\r', '\n']) {
+            let mut left = spaces;
+            while left > 0 {
+                let chunk = left.min(SPACES.len());
+                output(&SPACES[..chunk]);
+                left -= chunk;
+            }
+        }
+        output(segment);
+        *line_start = segment.ends_with(['\r', '\n']);
+    }
+}
@@ -0,0 +1,73 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Console stack
+//!
+//! The active console could be overridden temporarily, e.g. to redirect the output into a memory buffer during a
+//! test. ``push_console`` attaches a new active console and keeps the previous one on a stack, ``pop_console``
+//! removes it again and restores the previous one:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.push_console(CaptureConsole::new()));
+//! run_self_test();
+//! let memory = CONSOLE.take_for(|cons| cons.pop_console());
+//! ```
+//! ``with_console`` does the same for the duration of a closure, e.g. to capture the output of the initialization of
+//! a single subsystem:
+//! ```ignore
+//! let capture = CaptureConsole::new();
+//! with_console(capture.clone(), || sdcard.initialize());
+//! ```
+
+use crate::{Console, ConsoleImpl, CONSOLE};
+use alloc::boxed::Box;
+
+impl Console {
+    /// Attach a new active console on top of the current one. The current one is flushed and kept until the new one
+    /// is removed with ``pop_console``. The Console takes ownership of the new one.
+    pub fn push_console<T: ConsoleImpl + 'static>(&mut self, console: T) {
+        if let Some(ref current) = self.current {
+            current.flush();
+        }
+        let previous = self.current.replace(Box::from(console));
+        self.stack.push(previous);
+    }
+
+    /// Remove the active console attached with ``push_console`` and restore the one that has been active before.
+    /// The removed console is flushed and handed back without being closed. ``None`` if no console has been pushed.
+    pub fn pop_console(&mut self) -> Option<Box<dyn ConsoleImpl>> {
+        let previous = self.stack.pop()?;
+        let current = core::mem::replace(&mut self.current, previous);
+        if let Some(ref current) = current {
+            current.flush();
+        }
+        current
+    }
+
+    /// The number of consoles waiting on the stack to be restored with ``pop_console``
+    pub fn stack_depth(&self) -> usize {
+        self.stack.len()
+    }
+}
+
+/// Run the given function with the given console attached as active console on top of the current one. Once the
+/// function returns, or unwinds, the console is flushed, closed and dropped and the previous one is restored.
+pub fn with_console<T: ConsoleImpl + 'static, R>(console: T, f: impl FnOnce() -> R) -> R {
+    CONSOLE.take_for(|cons| cons.push_console(console));
+    let _pushed = PushedConsole;
+    f()
+}
+
+/// Removes the console attached by ``with_console`` once it is dropped
+struct PushedConsole;
+
+impl Drop for PushedConsole {
+    fn drop(&mut self) {
+        if let Some(mut console) = CONSOLE.take_for(|cons| cons.pop_console()) {
+            console.close();
+        }
+    }
+}
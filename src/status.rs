@@ -0,0 +1,109 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Status line
+//!
+//! The bottom line of an ANSI terminal could be reserved for a persistent status like the core load or the uptime.
+//! The console restricts the scrolling of the terminal to the lines above, so the regular output scrolls while the
+//! status line stays in place:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_status_line(25));
+//! loop {
+//!     status!("uptime {}s | load {}%", uptime, load);
+//!     // ...
+//! }
+//! ```
+//! The number of rows of the terminal has to be given unless the terminal has been detected with
+//! ``Console::detect_terminal``. The status line is drawn with ANSI escape sequences to the current active console and
+//! all additional sinks, so it should only be used if all of them are connected to an ANSI terminal.
+
+use crate::ansi::{Cursor, Erase, Scroll};
+use crate::{Console, TerminalCaps, CONSOLE};
+use core::fmt;
+
+/// ``fmt::Write`` adapter passing the formatted chunks to the console
+struct StatusWriter<'a>(&'a Console);
+
+impl fmt::Write for StatusWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.puts(s);
+        Ok(())
+    }
+}
+
+impl Console {
+    /// Reserve the bottom line of a terminal with the given number of rows for the status line. The terminal
+    /// scrolls only the rows above from now on. With 0 rows the size of the terminal detected with
+    /// ``detect_terminal`` is used. Terminals with less than 2 rows have no room for a status line, terminals
+    /// detected without ANSI support none at all.
+    pub fn set_status_line(&mut self, rows: usize) {
+        let rows = match self.terminal {
+            Some(caps) if !caps.ansi => return,
+            Some(TerminalCaps {
+                size: Some(size), ..
+            }) if rows == 0 => size.rows,
+            _ => rows,
+        };
+        if rows < 2 {
+            return;
+        }
+        self.status_rows = rows;
+        // save the cursor as setting the scroll region moves it to the top left corner
+        self.write_sequence(format_args!(
+            "{}{}{}",
+            Cursor::Save,
+            Scroll::Region(0, rows - 2),
+            Cursor::Restore
+        ));
+    }
+
+    /// Release the status line. The terminal scrolls all rows again and the status line is cleared.
+    pub fn clear_status_line(&mut self) {
+        if self.status_rows == 0 {
+            return;
+        }
+        let rows = core::mem::replace(&mut self.status_rows, 0);
+        self.write_sequence(format_args!(
+            "{}{}{}{}{}",
+            Cursor::Save,
+            Scroll::Reset,
+            Cursor::To(rows - 1, 0),
+            Erase::Line,
+            Cursor::Restore
+        ));
+    }
+
+    /// Check if the bottom line of the terminal is reserved for the status line
+    pub fn has_status_line(&self) -> bool {
+        self.status_rows != 0
+    }
+
+    /// Replace the content of the status line. Does nothing if no status line has been reserved.
+    pub fn set_status(&self, args: fmt::Arguments) {
+        if self.status_rows == 0 {
+            return;
+        }
+        self.write_sequence(format_args!(
+            "{}{}{}{}{}",
+            Cursor::Save,
+            Cursor::To(self.status_rows - 1, 0),
+            Erase::Line,
+            args,
+            Cursor::Restore
+        ));
+    }
+
+    /// write an escape sequence without being interrupted by the output of other cores
+    pub(crate) fn write_sequence(&self, args: fmt::Arguments) {
+        let _ = self.exclusive_message(|| fmt::write(&mut StatusWriter(self), args));
+    }
+}
+
+/// The function hidden behind the status! macro. Replaces the content of the status line.
+pub fn print_status(args: fmt::Arguments) {
+    CONSOLE.use_for(|console| console.set_status(args));
+}
@@ -0,0 +1,181 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Tables
+//!
+//! Memory maps, task lists or device inventories are easier to read as aligned table. The ``Table`` collects the
+//! rows and pads the cells to the widest one of every column:
+//! ```ignore
+//! let mut table = Table::new()
+//!     .column("region", Align::Left)
+//!     .column("base", Align::Right)
+//!     .column("size", Align::Right);
+//! table.row(&[&"kernel", &format_args!("{:#x}", 0x8_0000), &"2 MiB"]);
+//! table.row(&[&"heap", &format_args!("{:#x}", 0x28_0000), &"16 MiB"]);
+//! table.print();
+//! ```
+//! prints
+//! ```text
+//! +--------+----------+--------+
+//! | region |     base |   size |
+//! +--------+----------+--------+
+//! | kernel |  0x80000 |  2 MiB |
+//! | heap   | 0x280000 | 16 MiB |
+//! +--------+----------+--------+
+//! ```
+//! The width of the cells is their display width, so wide characters keep the columns aligned. Cells missing in a
+//! row are left empty, cells beyond the last column are dropped.
+
+use crate::display_width;
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+/// The alignment of the cells of a column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// A column of the table
+struct Column {
+    header: String,
+    align: Align,
+}
+
+/// A table rendered with aligned columns
+#[derive(Default)]
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Create a table without any column
+    pub fn new() -> Self {
+        Table {
+            columns: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Add a column with the given header and alignment
+    pub fn column(mut self, header: &str, align: Align) -> Self {
+        self.columns.push(Column {
+            header: String::from(header),
+            align,
+        });
+        self
+    }
+
+    /// Add a row with the given cells, one per column
+    pub fn row(&mut self, cells: &[&dyn fmt::Display]) {
+        let row = cells
+            .iter()
+            .take(self.columns.len())
+            .map(|cell| {
+                let mut text = String::new();
+                let _ = write!(text, "{}", cell);
+                text
+            })
+            .collect();
+        self.rows.push(row);
+    }
+
+    /// The number of rows added so far
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Check if no row has been added so far
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Print the table with the console
+    pub fn print(&self) {
+        crate::print_args(format_args!("{}", self));
+    }
+
+    /// the width of every column
+    fn widths(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(idx))
+                    .map(|cell| display_width(cell))
+                    .fold(display_width(&column.header), usize::max)
+            })
+            .collect()
+    }
+}
+
+/// write the line separating the header and the rows
+fn write_separator(f: &mut fmt::Formatter, widths: &[usize]) -> fmt::Result {
+    for width in widths {
+        write!(f, "+{:-<1$}", "", width + 2)?;
+    }
+    f.write_str("+\r\n")
+}
+
+/// write a single row of cells, padding every cell to the width of its column
+fn write_row<'a>(
+    f: &mut fmt::Formatter,
+    widths: &[usize],
+    columns: &[Column],
+    cells: impl Iterator<Item = &'a str>,
+) -> fmt::Result {
+    let mut cells = cells.fuse();
+    for (width, column) in widths.iter().zip(columns) {
+        let cell = cells.next().unwrap_or("");
+        let pad = width - display_width(cell);
+        let (left, right) = match column.align {
+            Align::Left => (0, pad),
+            Align::Right => (pad, 0),
+            Align::Center => (pad / 2, pad - pad / 2),
+        };
+        write!(
+            f,
+            "| {:left$}{}{:right$} ",
+            "",
+            cell,
+            "",
+            left = left,
+            right = right
+        )?;
+    }
+    f.write_str("|\r\n")
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.columns.is_empty() {
+            return Ok(());
+        }
+        let widths = self.widths();
+        write_separator(f, &widths)?;
+        let headers = self.columns.iter().map(|column| column.header.as_str());
+        write_row(f, &widths, &self.columns, headers)?;
+        write_separator(f, &widths)?;
+        for row in &self.rows {
+            write_row(
+                f,
+                &widths,
+                &self.columns,
+                row.iter().map(|cell| cell.as_str()),
+            )?;
+        }
+        if !self.rows.is_empty() {
+            write_separator(f, &widths)?;
+        }
+        Ok(())
+    }
+}
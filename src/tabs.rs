@@ -0,0 +1,161 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Tab expansion
+//!
+//! Output channels without native tab handling show ``\t`` as garbage or not at all. The ``TabConsole`` wraps an
+//! output channel and expands the tabs written to it:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.add_sink(TabConsole::new(screen, TabExpansion::Stops(8))));
+//! ```
+//! ``TabExpansion::Stops`` moves to the next tab stop, which requires to track the column of the output. It counts
+//! the display width of the characters and skips escape sequences. ``TabExpansion::Spaces`` replaces every tab with
+//! a fixed number of spaces and ``TabExpansion::Pass`` passes the tabs unmodified.
+
+use crate::{char_width, ConsoleImpl, ConsoleInput};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// The spaces a tab is expanded with, passed in chunks of this size
+const SPACES: &str = "                ";
+
+/// The way a ``TabConsole`` expands tabs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TabExpansion {
+    /// Pass the tabs unmodified
+    Pass,
+    /// Replace every tab with the given number of spaces
+    Spaces(usize),
+    /// Fill up with spaces up to the next tab stop, the tab stops are the given number of columns apart
+    Stops(usize),
+}
+
+/// The output channel expanding the tabs written to another output channel
+pub struct TabConsole<C> {
+    inner: C,
+    expansion: TabExpansion,
+    /// the column of the cursor
+    column: AtomicUsize,
+    /// an escape sequence is written at the moment
+    escape: AtomicBool,
+}
+
+impl<C: ConsoleImpl> TabConsole<C> {
+    /// Create a new output channel expanding the tabs written to the given output channel
+    pub const fn new(inner: C, expansion: TabExpansion) -> Self {
+        TabConsole {
+            inner,
+            expansion,
+            column: AtomicUsize::new(0),
+            escape: AtomicBool::new(false),
+        }
+    }
+
+    /// Hand back the wrapped output channel
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// the number of spaces a tab at the current column expands to
+    fn tab_spaces(&self) -> usize {
+        match self.expansion {
+            TabExpansion::Pass => 0,
+            TabExpansion::Spaces(count) => count,
+            TabExpansion::Stops(stop) => {
+                stop.max(1) - self.column.load(Ordering::Relaxed) % stop.max(1)
+            }
+        }
+    }
+
+    /// update the column for a character written
+    fn advance(&self, c: char) {
+        if self.escape.load(Ordering::Relaxed) {
+            // escape sequences end with a letter
+            self.escape
+                .store(!c.is_ascii_alphabetic(), Ordering::Relaxed);
+            return;
+        }
+        match c {
+            '\u{1b}' => self.escape.store(true, Ordering::Relaxed),
+            '\r' | '\n' => self.column.store(0, Ordering::Relaxed),
+            '\u{8}' => {
+                let column = self.column.load(Ordering::Relaxed);
+                self.column
+                    .store(column.saturating_sub(1), Ordering::Relaxed);
+            }
+            c => {
+                self.column.fetch_add(char_width(c), Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn write_str(&self, s: &str) {
+        if self.expansion == TabExpansion::Pass {
+            self.inner.puts(s);
+            return;
+        }
+        let mut start = 0;
+        for (idx, c) in s.char_indices() {
+            if c == '\t' {
+                if start < idx {
+                    self.inner.puts(&s[start..idx]);
+                }
+                self.expand_tab();
+                start = idx + 1;
+            } else if let TabExpansion::Stops(_) = self.expansion {
+                self.advance(c);
+            }
+        }
+        if start == 0 {
+            self.inner.puts(s);
+        } else if start < s.len() {
+            self.inner.puts(&s[start..]);
+        }
+    }
+
+    fn expand_tab(&self) {
+        let mut spaces = self.tab_spaces();
+        self.column.fetch_add(spaces, Ordering::Relaxed);
+        while spaces > 0 {
+            let chunk = spaces.min(SPACES.len());
+            self.inner.puts(&SPACES[..chunk]);
+            spaces -= chunk;
+        }
+    }
+}
+
+impl<C: ConsoleImpl> ConsoleImpl for TabConsole<C> {
+    fn putc(&self, c: char) {
+        match (c, self.expansion) {
+            (_, TabExpansion::Pass) => self.inner.putc(c),
+            ('\t', _) => self.expand_tab(),
+            (c, _) => {
+                self.advance(c);
+                self.inner.putc(c);
+            }
+        }
+    }
+
+    fn puts(&self, s: &str) {
+        self.write_str(s);
+    }
+
+    fn input(&self) -> Option<&dyn ConsoleInput> {
+        self.inner.input()
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+
+    fn poll(&self) {
+        self.inner.poll();
+    }
+
+    fn close(&mut self) {
+        self.inner.close();
+    }
+}
@@ -0,0 +1,129 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Test Anything Protocol
+//!
+//! Kernels running their tests with ``custom_test_frameworks`` report the results through the console. ``Tap``
+//! prints them as Test Anything Protocol lines like ``ok 1 - mmu_init``, so a script on the host is able to parse
+//! the results from the Uart:
+//! ```ignore
+//! static TAP: Tap = Tap::new();
+//!
+//! pub fn test_runner(tests: &[&dyn Testable]) {
+//!     TAP.plan(tests.len());
+//!     for test in tests {
+//!         TAP.result(test.run(), test.name());
+//!     }
+//!     if !TAP.done() {
+//!         TAP.diag(format_args!("{} of {} tests failed", TAP.failed(), TAP.count()));
+//!     }
+//! }
+//! ```
+//! The test numbers are counted automatically. If the number of tests is not known in advance ``done`` prints the
+//! plan after the last test instead.
+
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// The state of a test run reported in the Test Anything Protocol
+pub struct Tap {
+    /// the number of tests reported so far
+    count: AtomicUsize,
+    /// the number of failed tests reported so far
+    failed: AtomicUsize,
+    /// the plan has been printed
+    planned: AtomicBool,
+}
+
+impl Tap {
+    /// Create a new test run without any reported tests
+    pub const fn new() -> Self {
+        Tap {
+            count: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            planned: AtomicBool::new(false),
+        }
+    }
+
+    /// Print the version and the plan announcing the given number of tests
+    pub fn plan(&self, tests: usize) {
+        self.planned.store(true, Ordering::Relaxed);
+        crate::print_args(format_args!("TAP version 13\r\n1..{}\r\n", tests));
+    }
+
+    /// Report a passed test
+    pub fn ok(&self, name: &str) {
+        let number = self.next();
+        crate::print_args(format_args!("ok {} - {}\r\n", number, name));
+    }
+
+    /// Report a failed test
+    pub fn not_ok(&self, name: &str) {
+        let number = self.next();
+        self.failed.fetch_add(1, Ordering::Relaxed);
+        crate::print_args(format_args!("not ok {} - {}\r\n", number, name));
+    }
+
+    /// Report a passed or failed test. Returns whether the test has passed.
+    pub fn result(&self, passed: bool, name: &str) -> bool {
+        if passed {
+            self.ok(name);
+        } else {
+            self.not_ok(name);
+        }
+        passed
+    }
+
+    /// Report a test that has not been run for the given reason
+    pub fn skip(&self, name: &str, reason: &str) {
+        let number = self.next();
+        crate::print_args(format_args!(
+            "ok {} - {} # SKIP {}\r\n",
+            number, name, reason
+        ));
+    }
+
+    /// Print a diagnostic line that is not part of the results, e.g. details of a failed test
+    pub fn diag(&self, args: fmt::Arguments) {
+        crate::print_args(format_args!("# {}\r\n", args));
+    }
+
+    /// Abort the test run for the given reason
+    pub fn bail_out(&self, reason: &str) {
+        crate::print_args(format_args!("Bail out! {}\r\n", reason));
+    }
+
+    /// Finish the test run. If no plan has been printed so far it is printed with the number of reported tests.
+    /// Returns whether all tests have passed.
+    pub fn done(&self) -> bool {
+        if !self.planned.swap(true, Ordering::Relaxed) {
+            crate::print_args(format_args!("1..{}\r\n", self.count()));
+        }
+        self.failed() == 0
+    }
+
+    /// The number of tests reported so far
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// The number of failed tests reported so far
+    pub fn failed(&self) -> usize {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// count the next test and return its number
+    fn next(&self) -> usize {
+        self.count.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+impl Default for Tap {
+    fn default() -> Self {
+        Tap::new()
+    }
+}
@@ -0,0 +1,379 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Terminal detection
+//!
+//! Layout aware output like tables or the status line needs to know the size of the attached terminal. The console
+//! moves the cursor of an ANSI terminal to the bottom right corner, asks the terminal to report the cursor position
+//! and restores the cursor afterwards:
+//! ```ignore
+//! if let Some(size) = query_terminal_size() {
+//!     CONSOLE.take_for(|cons| cons.set_status_line(size.rows));
+//! }
+//! ```
+//! ``detect_terminal`` performs a whole handshake with the terminal and records its capabilities on the console:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.detect_terminal());
+//! CONSOLE.take_for(|cons| cons.set_status_line(0)); // uses the detected number of rows
+//! ```
+//! It asks for the device status to find out if the terminal understands ANSI escape sequences at all, for the
+//! device attributes to find out if it supports colors and checks whether it keeps a true color. From then on the
+//! colorized severity prefix is only printed if the terminal supports colors and the status line is only reserved on
+//! ANSI terminals.
+//!
+//! The replies are read from the input channel of the current active console. Characters received in front of a
+//! reply, e.g. typed by the user, are dropped. With a timestamp source the console waits at most
+//! ``TERMINAL_QUERY_TIMEOUT_MS`` milliseconds for each reply, otherwise it polls the input channel at most
+//! ``TERMINAL_QUERY_POLLS`` times.
+
+use crate::ansi::{Cursor, RESET};
+use crate::{Console, ConsoleInput, CONSOLE};
+use core::time::Duration;
+
+/// The time in milliseconds the console waits for the reply of the terminal if it has a timestamp source
+pub const TERMINAL_QUERY_TIMEOUT_MS: u64 = 200;
+
+/// The number of times the console polls the input channel for the reply of the terminal without a timestamp source
+pub const TERMINAL_QUERY_POLLS: usize = 1_000_000;
+
+/// The maximum length of a reply of the terminal
+const REPLY_SIZE: usize = 64;
+
+/// The size of a terminal in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalSize {
+    /// the number of lines
+    pub rows: usize,
+    /// the number of characters per line
+    pub columns: usize,
+}
+
+/// The colors a terminal is able to show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// no colors at all
+    Monochrome,
+    /// the 8 basic colors and their bright variants
+    Basic,
+    /// colors given as red, green and blue component
+    TrueColor,
+}
+
+/// The capabilities of a terminal found out by ``Console::detect_terminal``
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalCaps {
+    /// the terminal understands ANSI escape sequences
+    pub ansi: bool,
+    /// the colors the terminal is able to show
+    pub colors: ColorDepth,
+    /// the size of the terminal if it reported the cursor position
+    pub size: Option<TerminalSize>,
+}
+
+/// A control sequence sent by the terminal without the introducing escape character
+struct Reply {
+    data: [u8; REPLY_SIZE],
+    len: usize,
+}
+
+impl Reply {
+    fn as_str(&self) -> &str {
+        // only ASCII characters are collected
+        core::str::from_utf8(&self.data[..self.len]).unwrap_or("")
+    }
+}
+
+impl Console {
+    /// Query the size of the attached ANSI terminal. ``None`` if the current active console does not provide any
+    /// input or the terminal did not reply in time.
+    pub fn query_terminal_size(&self) -> Option<TerminalSize> {
+        let input = self.get_current().input()?;
+        self.write_sequence(format_args!(
+            "{}{}{}",
+            Cursor::Save,
+            Cursor::To(998, 998),
+            Cursor::ReportPosition
+        ));
+        let size = self.read_reply(input, |reply| {
+            let (rows, columns) = reply
+                .strip_prefix('[')?
+                .strip_suffix('R')?
+                .split_once(';')?;
+            Some(TerminalSize {
+                rows: rows.parse().ok()?,
+                columns: columns.parse().ok()?,
+            })
+        });
+        self.write_sequence(format_args!("{}", Cursor::Restore));
+        size
+    }
+
+    /// Probe the capabilities of the attached terminal and record them on the console. ``None`` if the current
+    /// active console does not provide any input. A terminal that does not reply is recorded as a terminal without
+    /// ANSI support.
+    pub fn detect_terminal(&mut self) -> Option<TerminalCaps> {
+        let input = self.get_current().input()?;
+        let mut caps = TerminalCaps {
+            ansi: false,
+            colors: ColorDepth::Monochrome,
+            size: None,
+        };
+        // the device status report is answered by every ANSI terminal
+        self.write_sequence(format_args!("\u{1b}[5n"));
+        if self
+            .read_reply(input, |reply| (reply == "[0n").then_some(()))
+            .is_some()
+        {
+            caps.ansi = true;
+            // the primary device attributes report parameter 22 for terminals supporting colors
+            self.write_sequence(format_args!("\u{1b}[c"));
+            let color = self.read_reply(input, |reply| {
+                let attributes = reply.strip_prefix("[?")?.strip_suffix('c')?;
+                Some(attributes.split(';').any(|attribute| attribute == "22"))
+            });
+            if color == Some(true) {
+                caps.colors = ColorDepth::Basic;
+            }
+            // a terminal supporting true colors keeps the color and reports it when asked for the current SGR
+            self.write_sequence(format_args!(
+                "\u{1b}[38;2;1;2;3m\u{1b}P$qm\u{1b}\\{}",
+                RESET
+            ));
+            let true_color = self.read_reply(input, |reply| {
+                let sgr = reply.strip_prefix("P1$r")?;
+                Some(
+                    ["38;2;1;2;3", "38:2:1:2:3", "38:2::1:2:3"]
+                        .iter()
+                        .any(|color| sgr.contains(color)),
+                )
+            });
+            if true_color == Some(true) {
+                caps.colors = ColorDepth::TrueColor;
+            }
+            caps.size = self.query_terminal_size();
+        }
+        self.terminal = Some(caps);
+        Some(caps)
+    }
+
+    /// The capabilities of the terminal recorded by ``detect_terminal``. ``None`` if the terminal has not been
+    /// detected so far.
+    pub fn terminal(&self) -> Option<TerminalCaps> {
+        self.terminal
+    }
+
+    /// Forget the capabilities of the terminal recorded by ``detect_terminal``, e.g. after attaching a different
+    /// terminal
+    pub fn clear_terminal(&mut self) {
+        self.terminal = None;
+    }
+
+    /// wait for a reply of the terminal that is accepted by ``parse``. Other control sequences and characters are
+    /// dropped. ``None`` if no accepted reply arrived in time.
+    fn read_reply<R>(
+        &self,
+        input: &dyn ConsoleInput,
+        mut parse: impl FnMut(&str) -> Option<R>,
+    ) -> Option<R> {
+        let deadline = self
+            .timestamp()
+            .map(|now| now + Duration::from_millis(TERMINAL_QUERY_TIMEOUT_MS));
+        let mut polls = 0;
+        let mut reply: Option<Reply> = None;
+        loop {
+            let c = match input.try_getc() {
+                Some(c) => c,
+                None => {
+                    polls += 1;
+                    let expired = match deadline {
+                        Some(deadline) => self.timestamp().is_some_and(|now| now >= deadline),
+                        None => polls >= TERMINAL_QUERY_POLLS,
+                    };
+                    if expired {
+                        return None;
+                    }
+                    core::hint::spin_loop();
+                    continue;
+                }
+            };
+            if c == '\u{1b}' {
+                // the string terminator ESC \ of a device control string ends the reply
+                if let Some(ref current) = reply {
+                    if current.as_str().starts_with('P') {
+                        if let Some(result) = parse(current.as_str()) {
+                            return Some(result);
+                        }
+                    }
+                }
+                reply = Some(Reply {
+                    data: [0; REPLY_SIZE],
+                    len: 0,
+                });
+                continue;
+            }
+            let current = match reply {
+                Some(ref mut current) if c.is_ascii() && current.len < REPLY_SIZE => current,
+                _ => {
+                    reply = None;
+                    continue;
+                }
+            };
+            current.data[current.len] = c as u8;
+            current.len += 1;
+            // a control sequence ends with a character in the range of '@' to '~'
+            let s = current.as_str();
+            if s.starts_with('[') && s.len() > 1 && matches!(c, '@'..='~') {
+                let result = parse(s);
+                reply = None;
+                if result.is_some() {
+                    return result;
+                }
+            }
+        }
+    }
+}
+
+/// Query the size of the attached ANSI terminal, see ``Console::query_terminal_size``
+pub fn query_terminal_size() -> Option<TerminalSize> {
+    CONSOLE.use_for(|console| console.query_terminal_size())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConsoleImpl, ConsoleInput, CONSOLE_TEST_LOCK};
+    use alloc::{collections::VecDeque, string::String};
+    use std::sync::{Mutex, PoisonError};
+
+    /// a terminal answering the queries of the console
+    struct Terminal {
+        colors: Option<ColorDepth>,
+        written: Mutex<String>,
+        replies: Mutex<VecDeque<char>>,
+    }
+
+    impl Terminal {
+        /// a terminal with the given colors, ``None`` if it does not answer at all. Some keys typed by the user are
+        /// pending already.
+        fn new(colors: Option<ColorDepth>) -> Self {
+            Terminal {
+                colors,
+                written: Mutex::new(String::new()),
+                replies: Mutex::new("ls".chars().collect()),
+            }
+        }
+
+        fn reply(&self, query: &str) -> Option<&'static str> {
+            let colors = self.colors?;
+            match query {
+                "\u{1b}[5n" => Some("\u{1b}[0n"),
+                "\u{1b}[c" if colors == ColorDepth::Monochrome => Some("\u{1b}[?1;2c"),
+                "\u{1b}[c" => Some("\u{1b}[?62;22c"),
+                "\u{1b}P$qm" if colors == ColorDepth::TrueColor => Some("\u{1b}P1$r0;38;2;1;2;3m\u{1b}\\"),
+                "\u{1b}P$qm" => None,
+                "\u{1b}[6n" => Some("\u{1b}[24;80R"),
+                _ => None,
+            }
+        }
+    }
+
+    impl ConsoleImpl for Terminal {
+        fn putc(&self, c: char) {
+            self.puts(c.encode_utf8(&mut [0; 4]));
+        }
+
+        fn puts(&self, s: &str) {
+            let mut written = self.written.lock().unwrap();
+            written.push_str(s);
+            for query in &["\u{1b}[5n", "\u{1b}[c", "\u{1b}P$qm", "\u{1b}[6n"] {
+                if written.contains(query) {
+                    written.clear();
+                    if let Some(reply) = self.reply(query) {
+                        self.replies.lock().unwrap().extend(reply.chars());
+                    }
+                }
+            }
+        }
+
+        fn input(&self) -> Option<&dyn ConsoleInput> {
+            Some(self)
+        }
+    }
+
+    impl ConsoleInput for Terminal {
+        fn try_getc(&self) -> Option<char> {
+            self.replies.lock().unwrap().pop_front()
+        }
+    }
+
+    fn with_terminal<R>(terminal: Terminal, f: impl FnOnce(&mut Console) -> R) -> R {
+        let _lock = CONSOLE_TEST_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        CONSOLE.take_for(|console| {
+            console.replace(terminal);
+            f(console)
+        })
+    }
+
+    #[test]
+    fn reads_the_size_from_the_cursor_position_report() {
+        let size = with_terminal(Terminal::new(Some(ColorDepth::Basic)), |console| {
+            console.query_terminal_size()
+        });
+        assert_eq!(
+            size,
+            Some(TerminalSize {
+                rows: 24,
+                columns: 80
+            })
+        );
+    }
+
+    #[test]
+    fn gives_up_if_the_terminal_does_not_reply() {
+        let size = with_terminal(Terminal::new(None), |console| console.query_terminal_size());
+        assert_eq!(size, None);
+    }
+
+    #[test]
+    fn detects_the_capabilities() {
+        let size = Some(TerminalSize {
+            rows: 24,
+            columns: 80,
+        });
+        for &colors in &[
+            ColorDepth::Monochrome,
+            ColorDepth::Basic,
+            ColorDepth::TrueColor,
+        ] {
+            let caps = with_terminal(Terminal::new(Some(colors)), |console| {
+                let caps = console.detect_terminal();
+                assert_eq!(console.terminal(), caps);
+                console.clear_terminal();
+                caps
+            });
+            assert_eq!(
+                caps,
+                Some(TerminalCaps {
+                    ansi: true,
+                    colors,
+                    size
+                })
+            );
+        }
+        let caps = with_terminal(Terminal::new(None), |console| console.detect_terminal());
+        assert_eq!(
+            caps,
+            Some(TerminalCaps {
+                ansi: false,
+                colors: ColorDepth::Monochrome,
+                size: None
+            })
+        );
+    }
+}
@@ -0,0 +1,59 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Rate limited logging
+//!
+//! Logging inside interrupt driven loops easily floods the Uart. The ``*_throttled!`` macros skip a message if the
+//! same call site already passed one within the given interval in milliseconds:
+//! ```ignore
+//! loop {
+//!     let status = poll_device();
+//!     warn_throttled!(1000, "device not ready: {:?}", status); // printed at most once per second
+//! }
+//! ```
+//! The interval is measured with the timestamp source of the console. Without a timestamp source the messages are
+//! not throttled.
+
+use crate::CONSOLE;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// The time of the last message that passed a ``*_throttled!`` call site
+pub struct Throttle {
+    /// the timestamp in milliseconds of the last message plus one, zero if no message passed so far
+    last: AtomicU64,
+}
+
+impl Throttle {
+    /// Create a new throttle that passes the first message
+    pub const fn new() -> Self {
+        Throttle {
+            last: AtomicU64::new(0),
+        }
+    }
+
+    /// Check if a message passes the throttle, given that messages pass at most once per ``interval_ms``
+    /// milliseconds. If several cores check at the same time only one of them passes.
+    pub fn pass(&self, interval_ms: u64) -> bool {
+        let now = match CONSOLE.use_for(|console| console.timestamp()) {
+            Some(now) => now.as_millis() as u64 + 1,
+            None => return true,
+        };
+        let last = self.last.load(Ordering::Relaxed);
+        if last != 0 && now.saturating_sub(last) < interval_ms {
+            return false;
+        }
+        self.last
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Throttle::new()
+    }
+}
@@ -0,0 +1,57 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Timestamps
+//!
+//! The console does not know about any timer. To get timestamped messages, provide a structure that implements
+//! the ``TimestampSource`` trait, e.g. backed by the system timer, and pass it to the console:
+//! ```ignore
+//! struct SystemTimer;
+//!
+//! impl TimestampSource for SystemTimer {
+//!     fn now(&self) -> Duration {
+//!         Duration::from_micros(read_system_timer())
+//!     }
+//! }
+//!
+//! CONSOLE.take_for(|cons| cons.set_timestamp_source(SystemTimer));
+//! ```
+//! From this point every message of the severity macros is prefixed with the timestamp like ``[   12.345678]``.
+
+use crate::{Console, CONSOLE};
+use alloc::boxed::Box;
+use core::time::Duration;
+
+/// Every source of timestamps need to implement this trait
+pub trait TimestampSource {
+    /// the time elapsed since an arbitrary but fixed point in time, usually the boot of the device
+    fn now(&self) -> Duration;
+}
+
+impl Console {
+    /// Set the source of the timestamps the messages of the severity macros are prefixed with. The Console takes
+    /// ownership of the source.
+    pub fn set_timestamp_source<T: TimestampSource + 'static>(&mut self, source: T) {
+        self.timestamp.replace(Box::from(source));
+    }
+
+    /// Remove the timestamp source. The messages are no longer prefixed with a timestamp.
+    pub fn clear_timestamp_source(&mut self) {
+        self.timestamp = None;
+    }
+
+    /// The current timestamp if a timestamp source has been set
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.timestamp.as_ref().map(|source| source.now())
+    }
+}
+
+/// The current timestamp of the console if a timestamp source has been set. The ``measure!`` macro uses this to
+/// measure the time of the block it runs.
+pub fn timestamp() -> Option<Duration> {
+    CONSOLE.use_for(|console| console.timestamp())
+}
@@ -0,0 +1,149 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Line length limit
+//!
+//! A single runaway message, e.g. a formatted buffer of a megabyte, keeps a slow Uart busy for minutes and wipes out
+//! the content of a small ring buffer. With a line length limit the console cuts every line after the given number
+//! of bytes and marks the cut with ``ELLIPSIS``:
+//! ```ignore
+//! CONSOLE.take_for(|cons| cons.set_max_line_length(Some(256)));
+//! ```
+//! A line ends with ``\n`` or ``\r``, the rest of a cut line is dropped up to its end. The limit applies to the text
+//! after the newline translation and to the log records formatted for every output channel. Raw mode and binary data
+//! are not limited.
+
+use crate::Console;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// The marker appended to a line that has been cut
+pub const ELLIPSIS: &str = "...";
+
+/// The length of the line written so far
+#[derive(Clone, Copy, Default)]
+pub(crate) struct LineState {
+    len: usize,
+    truncated: bool,
+}
+
+/// The line length limit of the console and the state of the current line
+pub(crate) struct LineLimit {
+    max: Option<usize>,
+    len: AtomicUsize,
+    truncated: AtomicBool,
+}
+
+impl LineLimit {
+    pub(crate) const fn new() -> Self {
+        LineLimit {
+            max: None,
+            len: AtomicUsize::new(0),
+            truncated: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Console {
+    /// Set the maximum number of bytes of a single line, ``None`` for unlimited lines
+    pub fn set_max_line_length(&mut self, max: Option<usize>) {
+        self.line_limit.max = max;
+    }
+
+    /// The maximum number of bytes of a single line
+    pub fn max_line_length(&self) -> Option<usize> {
+        self.line_limit.max
+    }
+
+    /// cut the string if the current line exceeds the line length limit and pass the rest to the output function
+    pub(crate) fn limited(&self, s: &str, mut output: impl FnMut(&str)) {
+        let max = match self.line_limit.max {
+            Some(max) => max,
+            None => {
+                output(s);
+                return;
+            }
+        };
+        let mut state = LineState {
+            len: self.line_limit.len.load(Ordering::Relaxed),
+            truncated: self.line_limit.truncated.load(Ordering::Relaxed),
+        };
+        truncate(max, &mut state, s, output);
+        self.line_limit.len.store(state.len, Ordering::Relaxed);
+        self.line_limit
+            .truncated
+            .store(state.truncated, Ordering::Relaxed);
+    }
+}
+
+/// Pass the string to the output function, cutting lines after ``max`` bytes. ``state`` keeps the length of the
+/// line across several strings.
+pub(crate) fn truncate(max: usize, state: &mut LineState, s: &str, mut output: impl FnMut(&str)) {
+    for segment in s.split_inclusive(['\r', '\n']) {
+        let (text, end) = match segment.ends_with(['\r', '\n']) {
+            true => segment.split_at(segment.len() - 1),
+            false => (segment, ""),
+        };
+        if !state.truncated && !text.is_empty() {
+            let room = max.saturating_sub(state.len);
+            if text.len() <= room {
+                output(text);
+                state.len += text.len();
+            } else {
+                let mut cut = room;
+                while !text.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                if cut > 0 {
+                    output(&text[..cut]);
+                }
+                output(ELLIPSIS);
+                state.truncated = true;
+            }
+        }
+        if !end.is_empty() {
+            output(end);
+            *state = LineState::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    fn truncated(max: usize, state: &mut LineState, s: &str) -> String {
+        let mut output = String::new();
+        truncate(max, state, s, |part| output.push_str(part));
+        output
+    }
+
+    #[test]
+    fn cuts_long_lines() {
+        let mut state = LineState::default();
+        assert_eq!(truncated(5, &mut state, "short\r\n"), "short\r\n");
+        assert_eq!(
+            truncated(5, &mut state, "too long\r\nnext\n"),
+            "too l...\r\nnext\n"
+        );
+    }
+
+    #[test]
+    fn keeps_the_line_length_across_strings() {
+        let mut state = LineState::default();
+        assert_eq!(truncated(6, &mut state, "abcd"), "abcd");
+        assert_eq!(truncated(6, &mut state, "efgh"), "ef...");
+        assert_eq!(truncated(6, &mut state, "ijkl"), "");
+        assert_eq!(truncated(6, &mut state, "\rxy"), "\rxy");
+    }
+
+    #[test]
+    fn never_splits_a_character() {
+        let mut state = LineState::default();
+        assert_eq!(truncated(4, &mut state, "aäöü"), "aä...");
+    }
+}
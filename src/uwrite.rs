@@ -0,0 +1,46 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # ``ufmt`` output
+//!
+//! Available with the ``ufmt`` feature. The formatting machinery of ``core::fmt`` adds several kilobytes to a bare
+//! metal image. Small kernels could format their output with ``ufmt`` instead, the ``uprint!`` and ``uprintln!``
+//! macros work like ``print!`` and ``println!`` but accept ``ufmt`` format strings and values implementing
+//! ``uDisplay`` or ``uDebug``:
+//! ```ignore
+//! use ufmt::derive::uDebug;
+//!
+//! #[derive(uDebug)]
+//! struct Region { base: usize, len: usize }
+//!
+//! uprintln!("mapped {:?}", Region { base: 0x8000, len: 4096 });
+//! ```
+//! The ``ufmt`` macros refer to the ``ufmt`` crate by name, so the crate using them needs ``ufmt`` as dependency
+//! itself. The ``ConsoleWriter`` implements ``uWrite`` and could be passed to ``uwrite!`` directly as well.
+
+use crate::{ConsoleWriter, CONSOLE};
+use core::convert::Infallible;
+use ufmt::uWrite;
+
+impl uWrite for ConsoleWriter {
+    type Error = Infallible;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Infallible> {
+        crate::print(s);
+        Ok(())
+    }
+}
+
+/// The printing function hidden behind the uprint! and uprintln! macro. The given function writes the formatted
+/// output to the ``ConsoleWriter`` while other cores wait until the whole message has been written.
+pub fn uprint_with(f: impl FnOnce(&mut ConsoleWriter) -> Result<(), Infallible>) {
+    let locked = CONSOLE.use_for(|console| console.lock_output());
+    let _ = f(&mut ConsoleWriter);
+    if locked {
+        CONSOLE.use_for(|console| console.unlock_output());
+    }
+}
@@ -0,0 +1,272 @@
+/***********************************************************************************************************************
+ * Copyright (c) 2019 by the authors
+ *
+ * Author: André Borrmann
+ * License: Appache License 2.0
+ **********************************************************************************************************************/
+
+//! # Line wrapping
+//!
+//! A terminal wraps a line that is longer than its width right in the middle of a word. The ``WrapConsole`` wraps an
+//! output channel and breaks long lines at the last space that fits into the width instead:
+//! ```ignore
+//! let screen = WrapConsole::for_terminal(uart);
+//! CONSOLE.take_for(|cons| cons.replace(screen));
+//! ```
+//! The width of the characters is taken into account, e.g. most CJK characters and emoji take two columns while
+//! combining marks take none. Escape sequences don't take any column either. The current word is collected until a
+//! space or a line break is written or the console is flushed. Words longer than the width or than
+//! ``WRAP_WORD_SIZE`` bytes are broken where they hit the end of the line.
+
+use crate::{ConsoleImpl, ConsoleInput, Locked, CONSOLE};
+
+/// The maximum number of bytes of a word that is moved to the next line as a whole
+pub const WRAP_WORD_SIZE: usize = 64;
+
+/// The width the lines are wrapped at if the size of the terminal has not been detected
+pub const WRAP_WIDTH: usize = 80;
+
+/// The number of columns the character takes on a terminal. Control characters and combining marks take none, wide
+/// characters like the CJK ideographs take two.
+pub fn char_width(c: char) -> usize {
+    match c as u32 {
+        0..=0x1f | 0x7f..=0x9f => 0,
+        // combining marks, zero width spaces and joiners, variation selectors
+        0x0300..=0x036f
+        | 0x1ab0..=0x1aff
+        | 0x1dc0..=0x1dff
+        | 0x200b..=0x200f
+        | 0x20d0..=0x20ff
+        | 0xfe00..=0xfe0f
+        | 0xfe20..=0xfe2f => 0,
+        // Hangul Jamo, CJK, Hangul syllables, fullwidth forms and emoji
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0x33ff
+        | 0x3400..=0x4dbf
+        | 0x4e00..=0x9fff
+        | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+/// The number of columns the string takes on a terminal
+pub fn display_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// The part of an escape sequence written so far
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    Started,
+    Csi,
+}
+
+/// The line written so far
+struct Line {
+    /// the column the next word starts at
+    column: usize,
+    /// the word collected so far
+    word: [u8; WRAP_WORD_SIZE],
+    len: usize,
+    /// the columns the collected word takes
+    word_width: usize,
+    escape: Escape,
+}
+
+/// The output channel wrapping the long lines written to another output channel at a given width
+pub struct WrapConsole<C> {
+    inner: C,
+    width: usize,
+    line: Locked<Line>,
+}
+
+impl<C: ConsoleImpl> WrapConsole<C> {
+    /// Create a new output channel wrapping the lines written to the given output channel at the given width
+    pub const fn new(inner: C, width: usize) -> Self {
+        WrapConsole {
+            inner,
+            width,
+            line: Locked::new(Line {
+                column: 0,
+                word: [0; WRAP_WORD_SIZE],
+                len: 0,
+                word_width: 0,
+                escape: Escape::None,
+            }),
+        }
+    }
+
+    /// Create a new output channel wrapping the lines at the width of the terminal detected with
+    /// ``Console::detect_terminal`` or at ``WRAP_WIDTH`` columns if the size is not known
+    pub fn for_terminal(inner: C) -> Self {
+        let size = CONSOLE.use_for(|console| console.terminal().and_then(|caps| caps.size));
+        WrapConsole::new(inner, size.map_or(WRAP_WIDTH, |size| size.columns))
+    }
+
+    /// The width the lines are wrapped at
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Hand back the wrapped output channel. The word collected so far is dropped.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn write_str(&self, s: &str) {
+        self.line.with(|line| {
+            for c in s.chars() {
+                self.write_char(line, c);
+            }
+        });
+    }
+
+    fn write_char(&self, line: &mut Line, c: char) {
+        let mut buf = [0; 4];
+        let encoded = c.encode_utf8(&mut buf).as_bytes();
+        if line.escape != Escape::None || c == '\u{1b}' {
+            line.escape = match (line.escape, c) {
+                (Escape::None, _) => Escape::Started,
+                (Escape::Started, '[') => Escape::Csi,
+                (Escape::Csi, '@'..='~') | (Escape::Started, _) => Escape::None,
+                (escape, _) => escape,
+            };
+            self.collect(line, encoded, 0);
+            return;
+        }
+        match c {
+            '\r' | '\n' => {
+                self.send_word(line);
+                self.inner.putc(c);
+                line.column = 0;
+            }
+            ' ' | '\t' => {
+                self.send_word(line);
+                let next = match c {
+                    '\t' => (line.column / 8 + 1) * 8,
+                    _ => line.column + 1,
+                };
+                if next > self.width {
+                    // the space ends the line
+                    self.inner.puts("\r\n");
+                    line.column = 0;
+                } else {
+                    self.inner.putc(c);
+                    line.column = next;
+                }
+            }
+            c => self.collect(line, encoded, char_width(c)),
+        }
+    }
+
+    /// add the encoded character taking the given columns to the word. A word that gets too long is sent first.
+    fn collect(&self, line: &mut Line, encoded: &[u8], width: usize) {
+        if line.len + encoded.len() > WRAP_WORD_SIZE || line.word_width + width > self.width {
+            self.send_word(line);
+        }
+        if line.len == 0 && width == 0 {
+            self.inner.puts_bytes(encoded);
+            return;
+        }
+        line.word[line.len..line.len + encoded.len()].copy_from_slice(encoded);
+        line.len += encoded.len();
+        line.word_width += width;
+    }
+
+    /// send the word collected so far, starting a new line if it does not fit into the current one
+    fn send_word(&self, line: &mut Line) {
+        if line.len == 0 {
+            return;
+        }
+        if line.column > 0 && line.column + line.word_width > self.width {
+            self.inner.puts("\r\n");
+            line.column = 0;
+        }
+        self.inner.puts_bytes(&line.word[..line.len]);
+        line.column += line.word_width;
+        line.len = 0;
+        line.word_width = 0;
+    }
+}
+
+impl<C: ConsoleImpl> ConsoleImpl for WrapConsole<C> {
+    fn putc(&self, c: char) {
+        self.line.with(|line| self.write_char(line, c));
+    }
+
+    fn puts(&self, s: &str) {
+        self.write_str(s);
+    }
+
+    fn input(&self) -> Option<&dyn ConsoleInput> {
+        self.inner.input()
+    }
+
+    fn flush(&self) {
+        self.line.with(|line| self.send_word(line));
+        self.inner.flush();
+    }
+
+    fn poll(&self) {
+        self.inner.poll();
+    }
+
+    fn close(&mut self) {
+        self.line.with(|line| self.send_word(line));
+        self.inner.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CaptureConsole;
+
+    fn wrapped(width: usize, s: &str) -> alloc::string::String {
+        let capture = CaptureConsole::new();
+        let console = WrapConsole::new(capture.clone(), width);
+        console.puts(s);
+        console.flush();
+        capture.take()
+    }
+
+    #[test]
+    fn measures_characters() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('\n'), 0);
+        assert_eq!(char_width('\u{301}'), 0);
+        assert_eq!(char_width('日'), 2);
+        assert_eq!(char_width('\u{1f600}'), 2);
+        assert_eq!(display_width("日本a"), 5);
+    }
+
+    #[test]
+    fn wraps_at_word_boundaries() {
+        assert_eq!(
+            wrapped(10, "hello world again\n"),
+            "hello \r\nworld \r\nagain\n"
+        );
+        assert_eq!(wrapped(10, "short\r\nline"), "short\r\nline");
+    }
+
+    #[test]
+    fn breaks_words_longer_than_the_line() {
+        assert_eq!(wrapped(4, "abcdefgh"), "abcd\r\nefgh");
+    }
+
+    #[test]
+    fn escape_sequences_take_no_columns() {
+        assert_eq!(wrapped(3, "\x1b[31mred\x1b[0m"), "\x1b[31mred\x1b[0m");
+        assert_eq!(wrapped(4, "日本語"), "日本\r\n語");
+    }
+}